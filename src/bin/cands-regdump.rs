@@ -0,0 +1,76 @@
+use std::{env, fs, process};
+
+use cands_interface::{CandsResult, RegisterDiff, RegisterDump, TCAN455xTranceiver};
+
+const USAGE: &str = "\
+Usage: cands-regdump [--json] [--setup]
+       cands-regdump --diff <before.json> <after.json>
+
+  --json    Print the dump as JSON instead of a table
+  --setup   Run the default setup before reading the registers
+  --diff    Compare two dumps saved with --json, exit status 1 if they differ";
+
+#[cfg(any(feature="usb-ftdi", feature="raspberrypi", feature="raspberrypi_cm"))]
+fn open() -> CandsResult<TCAN455xTranceiver> {
+    TCAN455xTranceiver::new()
+}
+
+// Only --diff works without a board driver
+#[cfg(not(any(feature="usb-ftdi", feature="raspberrypi", feature="raspberrypi_cm")))]
+fn open() -> CandsResult<TCAN455xTranceiver> {
+    eprintln!("cands-regdump: built without a board driver, enable the usb-ftdi, raspberrypi or raspberrypi_cm feature");
+    process::exit(2);
+}
+
+fn load(path: &str) -> CandsResult<RegisterDump> {
+    let json: String = fs::read_to_string(path)?;
+    RegisterDump::from_json(&json)
+}
+
+fn diff(before: &str, after: &str) -> CandsResult<bool> {
+    let diffs: Vec<RegisterDiff> = load(before)?.diff(&load(after)?);
+    for diff in diffs.iter() {
+        println!("{}", diff);
+    }
+    Ok(!diffs.is_empty())
+}
+
+fn dump(json: bool, setup: bool) -> CandsResult<()> {
+    let mut tranceiver: TCAN455xTranceiver = open()?;
+    if setup {
+        tranceiver.setup(&[], &[])?;
+    }
+    let dump: RegisterDump = tranceiver.dump_registers()?;
+    match json {
+        true => print!("{}", dump.to_json()),
+        false => print!("{}", dump.to_table()),
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result: CandsResult<bool> = match args.iter().map(String::as_str).collect::<Vec<&str>>().as_slice() {
+        ["--help"] | ["-h"] => {
+            println!("{}", USAGE);
+            Ok(false)
+        },
+        ["--diff", before, after] => diff(before, after),
+        flags if flags.iter().all(|flag| *flag == "--json" || *flag == "--setup") => {
+            dump(flags.contains(&"--json"), flags.contains(&"--setup")).map(|_| false)
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        },
+    };
+
+    match result {
+        Ok(differs) => process::exit(differs as i32),
+        Err(e) => {
+            eprintln!("cands-regdump: {}", e);
+            process::exit(2);
+        },
+    }
+}
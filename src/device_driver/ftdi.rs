@@ -22,23 +22,16 @@ pub use ftdi_embedded_hal::libftd2xx::{
 
 //Error handling
 use std::error::Error as StdError;
-use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 use ftdi_embedded_hal::Error as FtdiError;
+use crate::error::{CandsError, CandsResult};
 
-type IoResult<T> = Result<T, IoError>;
-
-fn emap<E: StdError>() -> impl FnOnce(FtdiError<E>) -> IoError { |err| match err {
-    FtdiError::Hal(_) => IoError::new(IoErrorKind::Other, "FTDI HAL ERROR"),
-    FtdiError::Io(e) => e,
-    FtdiError::Backend(e) => IoError::new(IoErrorKind::Other, e.to_string()),
-} }
-
-use super::{GpioDriver, TCAN455xDriver, GPI_MAX_POINT, DeviceDriver};
+use super::{GpioDriver, TCAN455xDriver, InterruptDriver, GPI_MAX_POINT, DeviceDriver};
+use super::interrupt::InterruptCallback;
 
 pub struct FtdiDriver<DEVICE, E>
 where
     DEVICE: MpsseCmdExecutor<Error = E>,
-    E: StdError,
+    E: StdError + Send + Sync + 'static,
 {
     spi: SpiDevice<DEVICE>,
     pins: [OutputPin<DEVICE>; 4]
@@ -48,7 +41,7 @@ impl <DEVICE, E> FtdiDriver <DEVICE, E>
 where
     DEVICE: MpsseCmdExecutor<Error = E> + TryFrom<Ftdi>,
     <DEVICE as TryFrom<Ftdi>>::Error: StdError + 'static,
-    E: StdError,
+    E: StdError + Send + Sync + 'static,
     FtdiError<E>: From<E>,
 {
     #[allow(dead_code)]
@@ -65,24 +58,24 @@ where
         Ok(device)
     }
 
-    pub fn new(tcan455xclk_freq: u32, tcan455xclk_polarity: u8) -> IoResult<Self> {
+    pub fn new(tcan455xclk_freq: u32, tcan455xclk_polarity: u8) -> CandsResult<Self> {
 
         let device: DEVICE = match Self::find_device() {
             Ok(device) => device,
-            Err(_) => return Err(IoError::new(IoErrorKind::NotConnected, "Device Not Found."))
+            Err(_) => return Err(CandsError::DeviceNotFound)
         };
 
-        let hal: FtHal<DEVICE> = FtHal::init_freq(device, tcan455xclk_freq).map_err(emap::<E>())?;
+        let hal: FtHal<DEVICE> = FtHal::init_freq(device, tcan455xclk_freq)?;
 
         const TCAN455X_CS_INDEX: u8 = 3;
         let tcan455xclk_polarity: Polarity = if tcan455xclk_polarity == 0 { Polarity::IdleLow } else {Polarity::IdleHigh};
-        let mut spi: SpiDevice<DEVICE> = hal.spi_device(TCAN455X_CS_INDEX).map_err(emap::<E>())?;
+        let mut spi: SpiDevice<DEVICE> = hal.spi_device(TCAN455X_CS_INDEX)?;
         spi.set_clock_polarity(tcan455xclk_polarity);
         
-        let pin_1: OutputPin<DEVICE> = hal.ad4().map_err(emap::<E>())?;
-        let pin_2: OutputPin<DEVICE> = hal.ad5().map_err(emap::<E>())?;
-        let pin_3: OutputPin<DEVICE> = hal.ad6().map_err(emap::<E>())?;
-        let pin_4: OutputPin<DEVICE> = hal.ad7().map_err(emap::<E>())?;
+        let pin_1: OutputPin<DEVICE> = hal.ad4()?;
+        let pin_2: OutputPin<DEVICE> = hal.ad5()?;
+        let pin_3: OutputPin<DEVICE> = hal.ad6()?;
+        let pin_4: OutputPin<DEVICE> = hal.ad7()?;
         
         let pins: [OutputPin<DEVICE>; 4] = [pin_1, pin_2, pin_3, pin_4];
 
@@ -95,25 +88,25 @@ impl <DEVICE, E> GpioDriver for FtdiDriver <DEVICE, E>
 where
     DEVICE: MpsseCmdExecutor<Error = E> + TryFrom<Ftdi>,
     <DEVICE as TryFrom<Ftdi>>::Error: StdError + 'static,
-    E: StdError,
+    E: StdError + Send + Sync + 'static,
     FtdiError<E>: From<E>,
 {
-    fn gpio_out(&mut self, state: u8) -> IoResult<()> {
+    fn gpio_out(&mut self, state: u8) -> CandsResult<()> {
         for i in 0..4 {
             if state & (0x01 << i) == 1 {
-                self.pins[i].set_high().map_err(emap::<E>())?;
+                self.pins[i].set_high()?;
             } else {
-                self.pins[i].set_low().map_err(emap::<E>())?;
+                self.pins[i].set_low()?;
             }
         }
         Ok(())
     }
 
-    fn gpio_read(&mut self, _channel: usize) -> IoResult<bool> {
+    fn gpio_read(&mut self, _channel: usize) -> CandsResult<bool> {
         Ok(false)
     }
 
-    fn gpio_read_all(&mut self) -> IoResult<[bool; GPI_MAX_POINT]> {
+    fn gpio_read_all(&mut self) -> CandsResult<[bool; GPI_MAX_POINT]> {
         let ret: [bool; GPI_MAX_POINT] = [false; GPI_MAX_POINT];
         Ok(ret)
     }
@@ -124,29 +117,29 @@ impl <DEVICE, E> TCAN455xDriver for FtdiDriver <DEVICE, E>
 where
     DEVICE: MpsseCmdExecutor<Error = E> + TryFrom<Ftdi>,
     <DEVICE as TryFrom<Ftdi>>::Error: StdError + 'static,
-    E: StdError,
+    E: StdError + Send + Sync + 'static,
     FtdiError<E>: From<E>,
 {
-    fn tcan455x_write(&mut self, data: &[u8]) -> IoResult<usize> {
-        (&mut self.spi).write(data).map_err(emap::<E>())?;
+    fn tcan455x_write(&mut self, data: &[u8]) -> CandsResult<usize> {
+        (&mut self.spi).write(data)?;
         Ok(data.len())
     }
 
-    fn tcan455x_read(&mut self, _buffer: &mut [u8]) -> IoResult<usize> {
+    fn tcan455x_read(&mut self, _buffer: &mut [u8]) -> CandsResult<usize> {
         Ok(0)
     }
 
-    fn tcan455x_transfer(&mut self, data: &[u8], buffer: &mut [u8]) -> IoResult<usize> {
-        (&mut self.spi).transfer(buffer, data).map_err(emap::<E>())?;
+    fn tcan455x_transfer(&mut self, data: &[u8], buffer: &mut [u8]) -> CandsResult<usize> {
+        (&mut self.spi).transfer(buffer, data)?;
         Ok(data.len())
     }
 
-    fn tcan455x_transfer_in_place(&mut self, data: &mut [u8]) -> IoResult<usize> {
-        (&mut self.spi).transfer_in_place(data).map_err(emap::<E>())?;
+    fn tcan455x_transfer_in_place(&mut self, data: &mut [u8]) -> CandsResult<usize> {
+        (&mut self.spi).transfer_in_place(data)?;
         Ok(data.len())
     }
 
-    fn tcan455x_reset(&mut self) -> super::IoResult<()> {
+    fn tcan455x_reset(&mut self) -> CandsResult<()> {
 
         const RESET_WAIT_TIME: u64 = 5;
         
@@ -159,10 +152,30 @@ where
 }
 
 
+// nINT is not wired to the FT232H: the wait always times out after one interval and callers fall back to polling over SPI
+const NINT_POLL_INTERVAL_MS: u64 = 10;
+
+impl <DEVICE, E> InterruptDriver for FtdiDriver <DEVICE, E>
+where
+    DEVICE: MpsseCmdExecutor<Error = E> + TryFrom<Ftdi>,
+    <DEVICE as TryFrom<Ftdi>>::Error: StdError + 'static,
+    E: StdError + Send + Sync + 'static,
+    FtdiError<E>: From<E>,
+{
+    fn wait_for_interrupt(&mut self, timeout: Option<std::time::Duration>) -> CandsResult<bool> {
+        let interval: std::time::Duration = std::time::Duration::from_millis(NINT_POLL_INTERVAL_MS);
+        std::thread::sleep(timeout.map_or(interval, |t| t.min(interval)));
+        Ok(false)
+    }
+
+    fn set_interrupt_callback(&mut self, _callback: Option<InterruptCallback>) {}
+}
+
+
 impl <DEVICE, E> DeviceDriver for FtdiDriver <DEVICE, E>
 where
     DEVICE: MpsseCmdExecutor<Error = E> + TryFrom<Ftdi>,
     <DEVICE as TryFrom<Ftdi>>::Error: StdError + 'static,
-    E: StdError,
+    E: StdError + Send + Sync + 'static,
     FtdiError<E>: From<E>,
 {}
\ No newline at end of file
@@ -0,0 +1,83 @@
+// Only the Raspberry Pi drivers and the simulator have an nINT line to signal
+#![cfg_attr(not(any(test, feature="simulator", feature="raspberrypi", feature="raspberrypi_cm")), allow(dead_code))]
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Called each time nINT asserts
+pub type InterruptCallback = Box<dyn FnMut() + Send>;
+
+#[derive(Default)]
+struct InterruptState {
+    pending: bool,
+    callback: Option<InterruptCallback>,
+}
+
+/// nINT assertion shared between the interrupt source and the waiting tranceiver
+#[derive(Clone, Default)]
+pub(crate) struct InterruptSignal {
+    inner: Arc<(Mutex<InterruptState>, Condvar)>,
+}
+
+impl InterruptSignal {
+    fn lock(&self) -> MutexGuard<'_, InterruptState> {
+        match self.inner.0.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Mark nINT asserted, wake the waiter and run the callback
+    pub(crate) fn notify(&self) {
+        let callback: Option<InterruptCallback> = {
+            let mut state = self.lock();
+            state.pending = true;
+            state.callback.take()
+        };
+        self.inner.1.notify_all();
+
+        // Run without holding the lock so the callback may replace itself
+        if let Some(mut callback) = callback {
+            callback();
+            let mut state = self.lock();
+            if state.callback.is_none() {
+                state.callback = Some(callback);
+            }
+        }
+    }
+
+    /// Wait for an assertion since the last wait. Returns false on timeout. None waits forever.
+    pub(crate) fn wait(&self, timeout: Option<Duration>) -> bool {
+        let deadline: Option<Instant> = timeout.map(|t| Instant::now() + t);
+        let mut state = self.lock();
+        while !state.pending {
+            state = match deadline {
+                Some(deadline) => {
+                    let now: Instant = Instant::now();
+                    if now >= deadline {
+                        return false;
+                    }
+                    match self.inner.1.wait_timeout(state, deadline - now) {
+                        Ok((guard, _)) => guard,
+                        Err(poisoned) => poisoned.into_inner().0,
+                    }
+                },
+                None => match self.inner.1.wait(state) {
+                    Ok(guard) => guard,
+                    Err(poisoned) => poisoned.into_inner(),
+                },
+            };
+        }
+        state.pending = false;
+        true
+    }
+
+    /// Forget an assertion that was already handled
+    pub(crate) fn clear(&self) {
+        self.lock().pending = false;
+    }
+
+    pub(crate) fn set_callback(&self, callback: Option<InterruptCallback>) {
+        self.lock().callback = callback;
+    }
+}
@@ -7,45 +7,70 @@ pub mod raspberrypi;
 #[cfg(feature="raspberrypi_cm")]
 pub mod raspberrypi_cm;
 
-use std::io::Error as IoError;
-type IoResult<T> = Result<T, IoError>;
+#[cfg(any(test, feature="simulator"))]
+pub mod simulator;
+
+pub mod interrupt;
+
+use std::time::Duration;
+
+use crate::error::CandsResult;
+use interrupt::InterruptCallback;
 
 pub const GPI_MAX_POINT: usize = 64;
 
+// Receive buffer of the Raspberry Pi drivers, limiting reads and writes alike, the 4 byte command included
+#[cfg(any(feature="raspberrypi", feature="raspberrypi_cm"))]
+const RASPI_TCAN455X_MAX_TRANSFER: usize = 512;
+
 #[allow(dead_code)]
 pub(crate) trait TCAN455xDriver {
-    fn tcan455x_write(&mut self, data: &[u8]) -> IoResult<usize>;
-    fn tcan455x_read(&mut self, buffer: &mut [u8]) -> IoResult<usize>;
-    fn tcan455x_transfer(&mut self, data: &[u8], buffer: &mut [u8]) -> IoResult<usize>;
-    fn tcan455x_transfer_in_place(&mut self, data: &mut [u8]) -> IoResult<usize>;
-    fn tcan455x_reset(&mut self) -> IoResult<()>;
+    fn tcan455x_write(&mut self, data: &[u8]) -> CandsResult<usize>;
+    fn tcan455x_read(&mut self, buffer: &mut [u8]) -> CandsResult<usize>;
+    fn tcan455x_transfer(&mut self, data: &[u8], buffer: &mut [u8]) -> CandsResult<usize>;
+    fn tcan455x_transfer_in_place(&mut self, data: &mut [u8]) -> CandsResult<usize>;
+    fn tcan455x_reset(&mut self) -> CandsResult<()>;
+
+    /// Largest read or write transaction in bytes, command included
+    fn tcan455x_max_transfer(&self) -> usize {
+        usize::MAX
+    }
 }
 
 #[allow(dead_code)]
 pub(crate) trait ADCDriver {
-    fn adc_reset(&mut self) -> IoResult<()>;
-    fn adc_write(&mut self, data: &[u8]) -> IoResult<usize>;
-    fn adc_read(&mut self, buffer: &mut [u8]) -> IoResult<usize>;
-    fn adc_transfer(&mut self, data: &[u8], buffer: &mut [u8]) -> IoResult<usize>;
-    fn adc_transfer_in_place(&mut self, data: &mut [u8]) -> IoResult<usize>;
+    fn adc_reset(&mut self) -> CandsResult<()>;
+    fn adc_write(&mut self, data: &[u8]) -> CandsResult<usize>;
+    fn adc_read(&mut self, buffer: &mut [u8]) -> CandsResult<usize>;
+    fn adc_transfer(&mut self, data: &[u8], buffer: &mut [u8]) -> CandsResult<usize>;
+    fn adc_transfer_in_place(&mut self, data: &mut [u8]) -> CandsResult<usize>;
 }
 
 #[allow(dead_code)]
 pub(crate) trait WS2812Driver {
-    fn ws2812_write(&mut self, data: &[u8]) -> IoResult<usize>;
+    fn ws2812_write(&mut self, data: &[u8]) -> CandsResult<usize>;
 }
 
 #[allow(dead_code)]
 pub(crate) trait GpioDriver {
-    fn gpio_out(&mut self, state: u8) -> IoResult<()>;
-    fn gpio_read(&mut self, channel: usize) -> IoResult<bool>;
-    fn gpio_read_all(&mut self) -> IoResult<[bool; GPI_MAX_POINT]>;
+    fn gpio_out(&mut self, state: u8) -> CandsResult<()>;
+    fn gpio_read(&mut self, channel: usize) -> CandsResult<bool>;
+    fn gpio_read_all(&mut self) -> CandsResult<[bool; GPI_MAX_POINT]>;
+}
+
+#[allow(dead_code)]
+pub(crate) trait InterruptDriver {
+    /// Block until nINT is asserted. Returns false on timeout. None waits forever.
+    ///
+    /// A driver without nINT returns false after a fixed interval so the caller polls over SPI.
+    fn wait_for_interrupt(&mut self, timeout: Option<Duration>) -> CandsResult<bool>;
+    fn set_interrupt_callback(&mut self, callback: Option<InterruptCallback>);
 }
 
 #[allow(dead_code)]
-pub(crate) trait DeviceDriver: TCAN455xDriver {}
+pub(crate) trait DeviceDriver: TCAN455xDriver + InterruptDriver {}
 
 #[allow(dead_code)]
-pub(crate) trait RaspiDeviceDriver: TCAN455xDriver + GpioDriver + ADCDriver + WS2812Driver {}
+pub(crate) trait RaspiDeviceDriver: TCAN455xDriver + GpioDriver + ADCDriver + WS2812Driver + InterruptDriver {}
 
 
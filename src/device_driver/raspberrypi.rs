@@ -1,26 +1,15 @@
-use rppal::gpio::{Gpio, InputPin, OutputPin};
+use rppal::gpio::{Gpio, InputPin, OutputPin, Trigger};
 use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
 
 //Error handling
-use std::error::Error as StdError;
-use std::io::{Error as IoError, ErrorKind as IoErrorKind};
-use rppal::spi::Error as RaspiError;
+use crate::error::{CandsError, CandsResult};
 
-type IoResult<T> = Result<T, IoError>;
-
-fn emap() -> impl FnOnce(RaspiError) -> IoError { |err| match err {
-    RaspiError::Io(e) => e,
-    RaspiError::BitsPerWordNotSupported(_u8) => IoError::new(IoErrorKind::Other, "Bits Per Word Not Supported"),
-    RaspiError::BitOrderNotSupported(_bit_order) => IoError::new(IoErrorKind::Other, "Bit Order Not Supported"),
-    RaspiError::ClockSpeedNotSupported(_u32) => IoError::new(IoErrorKind::Other, "Clock Speed Not Supported"),
-    RaspiError::ModeNotSupported(_mode) => IoError::new(IoErrorKind::Other, "Mode Not Supported"),
-    RaspiError::PolarityNotSupported(_polarity) => IoError::new(IoErrorKind::Other, "Polarity Not Supported"),
-} }
-
-use super::{GpioDriver, ADCDriver, TCAN455xDriver, WS2812Driver, RaspiDeviceDriver, GPI_MAX_POINT};
+use super::{GpioDriver, ADCDriver, TCAN455xDriver, WS2812Driver, InterruptDriver, RaspiDeviceDriver, GPI_MAX_POINT, RASPI_TCAN455X_MAX_TRANSFER};
+use super::interrupt::{InterruptCallback, InterruptSignal};
 
 const GPIO_RESET_PIN_BCM: u8 = 5;
 const ADC_RESET_PIN_BCM: u8 = 26;
+pub const DEFAULT_TCAN_NINT_PIN_BCM: u8 = 6;
 
 
 pub const GPIO_INPUT_PIN_NUM: usize = 7;
@@ -60,19 +49,23 @@ pub struct RaspiIF {
     pub spi1: Spi,
     pub spi5: Spi,
     pub tcan_reset_pin: OutputPin,
+    pub tcan_nint_pin: InputPin,
     pub adc_reset_pin: OutputPin,
     pub input_pins: [InputPin; GPIO_INPUT_PIN_NUM],
-    pub output_pins: [OutputPin; GPIO_OUTPUT_PIN_NUM]
+    pub output_pins: [OutputPin; GPIO_OUTPUT_PIN_NUM],
+    interrupt: InterruptSignal,
 }
 
 impl  RaspiIF {
     
-    pub fn new() -> Result<Self, Box<dyn StdError>> {
+    pub fn new() -> CandsResult<Self> {
+        Self::with_nint_pin(DEFAULT_TCAN_NINT_PIN_BCM)
+    }
 
-        let gpio: Gpio = match Gpio::new() {
-            Ok(x) => x,
-            Err(e) => return Err(Box::new(e)),
-        };
+    /// Interface with the TCAN455x nINT line on BCM pin `nint_pin_bcm`
+    pub fn with_nint_pin(nint_pin_bcm: u8) -> CandsResult<Self> {
+
+        let gpio: Gpio = Gpio::new()?;
 
         //TCAN455x spi
         let spi0: Spi = Spi::new(SPI0_BUS, SPI0_SS,  SPI0_CLK_FREQ, SPI0_MODE)?;
@@ -87,49 +80,58 @@ impl  RaspiIF {
         //TCAN455x reset pin
         let tcan_reset_pin: OutputPin = match gpio.get(GPIO_RESET_PIN_BCM) {
             Ok(x) => x.into_output(),
-            Err(e) => return Err(Box::new(e)),
+            Err(e) => return Err(e.into()),
         };
 
         //ADC reset pin
         let adc_reset_pin: OutputPin = match gpio.get(ADC_RESET_PIN_BCM) {
             Ok(x) => x.into_output(),
-            Err(e) => return Err(Box::new(e)),
+            Err(e) => return Err(e.into()),
         };
 
+        //TCAN455x nINT pin: open drain, active low
+        let mut tcan_nint_pin: InputPin = match gpio.get(nint_pin_bcm) {
+            Ok(x) => x.into_input_pullup(),
+            Err(e) => return Err(e.into()),
+        };
+        let interrupt: InterruptSignal = InterruptSignal::default();
+        let signal: InterruptSignal = interrupt.clone();
+        tcan_nint_pin.set_async_interrupt(Trigger::FallingEdge, None, move |_event| signal.notify())?;
+
         //Input pins
         let input_pin_0: InputPin = match gpio.get(GPIO_INPUT_PIN_BCM[0]) {
             Ok(x) => x.into_input(),
-            Err(e) => return Err(Box::new(e)),
+            Err(e) => return Err(e.into()),
         };
 
         let input_pin_1: InputPin = match gpio.get(GPIO_INPUT_PIN_BCM[1]) {
             Ok(x) => x.into_input(),
-            Err(e) => return Err(Box::new(e)),
+            Err(e) => return Err(e.into()),
         };
 
         let input_pin_2: InputPin = match gpio.get(GPIO_INPUT_PIN_BCM[2]) {
             Ok(x) => x.into_input(),
-            Err(e) => return Err(Box::new(e)),
+            Err(e) => return Err(e.into()),
         };
 
         let input_pin_3: InputPin = match gpio.get(GPIO_INPUT_PIN_BCM[3]) {
             Ok(x) => x.into_input(),
-            Err(e) => return Err(Box::new(e)),
+            Err(e) => return Err(e.into()),
         };
 
         let input_pin_4: InputPin = match gpio.get(GPIO_INPUT_PIN_BCM[4]) {
             Ok(x) => x.into_input(),
-            Err(e) => return Err(Box::new(e)),
+            Err(e) => return Err(e.into()),
         };
 
         let input_pin_5: InputPin = match gpio.get(GPIO_INPUT_PIN_BCM[5]) {
             Ok(x) => x.into_input(),
-            Err(e) => return Err(Box::new(e)),
+            Err(e) => return Err(e.into()),
         };
 
         let input_pin_6: InputPin = match gpio.get(GPIO_INPUT_PIN_BCM[6]) {
             Ok(x) => x.into_input(),
-            Err(e) => return Err(Box::new(e)),
+            Err(e) => return Err(e.into()),
         };
 
         let input_pins: [InputPin; GPIO_INPUT_PIN_NUM] = [
@@ -145,19 +147,19 @@ impl  RaspiIF {
         // Output pin
         let output_pin_0: OutputPin = match gpio.get(GPIO_OUTPUT_PIN_BCM[0]) {
             Ok(x) => x.into_output(),
-            Err(e) => return Err(Box::new(e)),
+            Err(e) => return Err(e.into()),
         };
 
         let output_pins: [OutputPin; GPIO_OUTPUT_PIN_NUM] = [
             output_pin_0,
         ];
 
-        Ok(Self { spi0, spi1, spi5, tcan_reset_pin, adc_reset_pin, input_pins, output_pins })
+        Ok(Self { spi0, spi1, spi5, tcan_reset_pin, tcan_nint_pin, adc_reset_pin, input_pins, output_pins, interrupt })
     }
 }
 
 impl GpioDriver for RaspiIF {
-    fn gpio_out(&mut self, state: u8) -> IoResult<()> {
+    fn gpio_out(&mut self, state: u8) -> CandsResult<()> {
         for i in 0..GPIO_OUTPUT_PIN_NUM {
             if (state & (0x01 << i)) != 0 {
                 self.output_pins[i].set_high();
@@ -168,11 +170,14 @@ impl GpioDriver for RaspiIF {
         Ok(())
     }
 
-    fn gpio_read(&mut self, channel: usize) -> IoResult<bool> {
-        Ok(self.input_pins[channel].is_high())
+    fn gpio_read(&mut self, channel: usize) -> CandsResult<bool> {
+        match self.input_pins.get(channel) {
+            Some(pin) => Ok(pin.is_high()),
+            None => Err(CandsError::InvalidConfig(format!("No GPIO input channel {}", channel))),
+        }
     }
 
-    fn gpio_read_all(&mut self) -> IoResult<[bool; GPI_MAX_POINT]> {
+    fn gpio_read_all(&mut self) -> CandsResult<[bool; GPI_MAX_POINT]> {
         let mut ret: [bool; GPI_MAX_POINT] = [false; GPI_MAX_POINT];
         for i in 0..GPIO_INPUT_PIN_NUM {
             ret[i] = self.input_pins[i].is_high();
@@ -183,28 +188,32 @@ impl GpioDriver for RaspiIF {
 
 impl TCAN455xDriver for RaspiIF {
 
-    fn tcan455x_read(&mut self, buffer: &mut [u8]) -> IoResult<usize> {
-        self.spi0.read(buffer).map_err(emap())
+    fn tcan455x_read(&mut self, buffer: &mut [u8]) -> CandsResult<usize> {
+        self.spi0.read(buffer).map_err(CandsError::from)
     }
 
-    fn tcan455x_write(&mut self, buffer: &[u8]) -> IoResult<usize> {
-        self.spi0.write(buffer).map_err(emap())
+    fn tcan455x_write(&mut self, buffer: &[u8]) -> CandsResult<usize> {
+        self.spi0.write(buffer).map_err(CandsError::from)
     } 
 
-    fn tcan455x_transfer(&mut self, tx_buffer: &[u8], rx_buffer: &mut [u8]) -> IoResult<usize> {
-        self.spi0.transfer(rx_buffer, tx_buffer).map_err(emap())
+    fn tcan455x_max_transfer(&self) -> usize {
+        RASPI_TCAN455X_MAX_TRANSFER
     }
 
-    fn tcan455x_transfer_in_place(&mut self, data: &mut [u8]) -> IoResult<usize> {
-        let mut rx_buffer: [u8; 512] = [0u8; 512];
-        let size: usize = self.spi0.transfer(&mut rx_buffer, data).map_err(emap())?;
+    fn tcan455x_transfer(&mut self, tx_buffer: &[u8], rx_buffer: &mut [u8]) -> CandsResult<usize> {
+        self.spi0.transfer(rx_buffer, tx_buffer).map_err(CandsError::from)
+    }
+
+    fn tcan455x_transfer_in_place(&mut self, data: &mut [u8]) -> CandsResult<usize> {
+        let mut rx_buffer: [u8; RASPI_TCAN455X_MAX_TRANSFER] = [0u8; RASPI_TCAN455X_MAX_TRANSFER];
+        let size: usize = self.spi0.transfer(&mut rx_buffer, data).map_err(CandsError::from)?;
         for i in 0..size {
             data[i] = rx_buffer[i];
         }
         Ok(size)
     }
 
-    fn tcan455x_reset(&mut self) -> super::IoResult<()> {
+    fn tcan455x_reset(&mut self) -> CandsResult<()> {
 
         const RESET_WAIT_TIME: u64 = 5;
         
@@ -216,9 +225,24 @@ impl TCAN455xDriver for RaspiIF {
     }
 }
 
+impl InterruptDriver for RaspiIF {
+    fn wait_for_interrupt(&mut self, timeout: Option<std::time::Duration>) -> CandsResult<bool> {
+        // nINT is level triggered: an assertion still pending needs no edge
+        if self.tcan_nint_pin.is_low() {
+            self.interrupt.clear();
+            return Ok(true);
+        }
+        Ok(self.interrupt.wait(timeout))
+    }
+
+    fn set_interrupt_callback(&mut self, callback: Option<InterruptCallback>) {
+        self.interrupt.set_callback(callback);
+    }
+}
+
 impl ADCDriver for RaspiIF {
 
-    fn adc_reset(&mut self) -> IoResult<()> {
+    fn adc_reset(&mut self) -> CandsResult<()> {
 
         const RESET_WAIT_TIME: u64 = 100;
         
@@ -229,21 +253,21 @@ impl ADCDriver for RaspiIF {
         Ok(())
     }
 
-    fn adc_read(&mut self, buffer: &mut [u8]) -> IoResult<usize> {
-        self.spi1.read(buffer).map_err(emap())
+    fn adc_read(&mut self, buffer: &mut [u8]) -> CandsResult<usize> {
+        self.spi1.read(buffer).map_err(CandsError::from)
     }
 
-    fn adc_write(&mut self, buffer: &[u8]) -> IoResult<usize> {
-        self.spi1.write(buffer).map_err(emap())
+    fn adc_write(&mut self, buffer: &[u8]) -> CandsResult<usize> {
+        self.spi1.write(buffer).map_err(CandsError::from)
     } 
 
-    fn adc_transfer(&mut self, tx_buffer: &[u8], rx_buffer: &mut [u8]) -> IoResult<usize> {
-        self.spi1.transfer(rx_buffer, tx_buffer).map_err(emap())
+    fn adc_transfer(&mut self, tx_buffer: &[u8], rx_buffer: &mut [u8]) -> CandsResult<usize> {
+        self.spi1.transfer(rx_buffer, tx_buffer).map_err(CandsError::from)
     }
 
-    fn adc_transfer_in_place(&mut self, data: &mut [u8]) -> IoResult<usize> {
+    fn adc_transfer_in_place(&mut self, data: &mut [u8]) -> CandsResult<usize> {
         let mut rx_buffer: [u8; 512] = [0u8; 512];
-        let size: usize = self.spi1.transfer(&mut rx_buffer, data).map_err(emap())?;
+        let size: usize = self.spi1.transfer(&mut rx_buffer, data).map_err(CandsError::from)?;
         for i in 0..size {
             data[i] = rx_buffer[i];
         }
@@ -252,8 +276,8 @@ impl ADCDriver for RaspiIF {
 }
 
 impl WS2812Driver for RaspiIF {
-    fn ws2812_write(&mut self, buffer: &[u8]) -> IoResult<usize> {
-        self.spi5.write(buffer).map_err(emap())
+    fn ws2812_write(&mut self, buffer: &[u8]) -> CandsResult<usize> {
+        self.spi5.write(buffer).map_err(CandsError::from)
     } 
 }
 
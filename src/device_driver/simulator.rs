@@ -0,0 +1,1160 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::time::Duration;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::error::CandsResult;
+use crate::tcan4550::register::*;
+use crate::tranceiver::rx_buffer::CanFrame;
+use crate::tranceiver::power::WakeReason;
+
+use super::{GpioDriver, ADCDriver, TCAN455xDriver, WS2812Driver, InterruptDriver, GPI_MAX_POINT};
+use super::interrupt::{InterruptCallback, InterruptSignal};
+
+#[cfg(not(any(feature="raspberrypi", feature="raspberrypi_cm")))]
+use super::DeviceDriver;
+
+#[cfg(any(feature="raspberrypi", feature="raspberrypi_cm"))]
+use super::RaspiDeviceDriver;
+
+const WRITE_B_FL: u8 = 0x61;
+const READ_B_FL: u8 = 0x41;
+
+const MRAM_SIZE: usize = 2048;
+
+const SPI_STATUS_INVALID_COMMAND: u32 = 0x00020000;
+
+const CAN_DLC_TO_DLEN: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 12, 16, 20, 24, 32, 48, 64];
+const RXESC_TO_DATASIZE: [u32; 8] = [8, 12, 16, 20, 24, 32, 48, 64];
+
+// Reset values
+const RESET_VALUES: [(u16, u32); 17] = [
+    (REG_SPI_DEVICE_ID0, 0x4E414354),       // "TCAN"
+    (REG_SPI_DEVICE_ID1, 0x30353534),       // "4550"
+    (REG_SPI_REVISION, 0x00110201),
+    (REG_DEV_MODES_AND_PINS, 0xC8000468),
+    (REG_DEV_TIMESTAMP_PRESCALER, 0x00000002),
+    (REG_DEV_IR, REG_BITS_DEVICE_IR_PWRON),
+    (REG_MCAN_CREL, 0x32150320),
+    (REG_MCAN_ENDN, 0x87654321),
+    (REG_MCAN_DBTP, 0x00000A33),
+    (REG_MCAN_CCCR, REG_BITS_MCAN_CCCR_INIT),
+    (REG_MCAN_NBTP, 0x06000A03),
+    (REG_MCAN_TOCC, 0xFFFF0000),
+    (REG_MCAN_TOCV, 0x0000FFFF),
+    (REG_MCAN_PSR, 0x00000707),
+    (REG_MCAN_XIDAM, 0x1FFFFFFF),
+    (REG_MCAN_TXBTIE, 0x00000000),
+    (REG_MCAN_TXBCIE, 0x00000000),
+];
+
+// Registers writable only while CCCR.CCE = 1 and CCCR.INIT = 1
+const PROTECTED_REGISTERS: [u16; 17] = [
+    REG_MCAN_DBTP,
+    REG_MCAN_RWD,
+    REG_MCAN_NBTP,
+    REG_MCAN_TSCC,
+    REG_MCAN_TOCC,
+    REG_MCAN_TDCR,
+    REG_MCAN_GFC,
+    REG_MCAN_SIDFC,
+    REG_MCAN_XIDFC,
+    REG_MCAN_XIDAM,
+    REG_MCAN_RXF0C,
+    REG_MCAN_RXBC,
+    REG_MCAN_RXF1C,
+    REG_MCAN_RXESC,
+    REG_MCAN_TXBC,
+    REG_MCAN_TXESC,
+    REG_MCAN_TXEFC,
+];
+
+// Registers updated by the device only
+const READ_ONLY_REGISTERS: [u16; 15] = [
+    REG_SPI_DEVICE_ID0,
+    REG_SPI_DEVICE_ID1,
+    REG_SPI_REVISION,
+    REG_MCAN_CREL,
+    REG_MCAN_ENDN,
+    REG_MCAN_TOCV,
+    REG_MCAN_ECR,
+    REG_MCAN_PSR,
+    REG_MCAN_HPMS,
+    REG_MCAN_RXF0S,
+    REG_MCAN_RXF1S,
+    REG_MCAN_TXFQS,
+    REG_MCAN_TXBRP,
+    REG_MCAN_TXBTO,
+    REG_MCAN_TXBCF,
+];
+
+// Registers cleared by writing 1
+const W1C_REGISTERS: [u16; 5] = [
+    REG_SPI_STATUS,
+    REG_DEV_IR,
+    REG_MCAN_IR,
+    REG_MCAN_NDAT1,
+    REG_MCAN_NDAT2,
+];
+
+/// Where a received frame ends up after acceptance filtering
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum RxTarget {
+    Fifo(usize),
+    Buffer(u32),
+    Reject,
+    PriorityOnly,
+}
+
+struct FilterMatch {
+    target: RxTarget,
+    priority: bool,
+    fidx: u32,
+}
+
+struct SimState {
+    regs: BTreeMap<u16, u32>,
+    mram: Vec<u32>,
+    rx_fifo_get: [u32; 2],
+    rx_fifo_put: [u32; 2],
+    rx_fifo_fill: [u32; 2],
+    tx_fifo_put: u32,
+    tx_event_get: u32,
+    tx_event_put: u32,
+    tx_event_fill: u32,
+    timestamp: u16,
+    watchdog_elapsed: Duration,
+    loopback: bool,
+    bus_fault: bool,
+    nint: bool,
+    transactions: usize,
+    transmitted: Vec<CanFrame>,
+}
+
+impl SimState {
+    fn new() -> Self {
+        let mut state: Self = Self {
+            regs: BTreeMap::new(),
+            mram: vec![0u32; MRAM_SIZE / 4],
+            rx_fifo_get: [0; 2],
+            rx_fifo_put: [0; 2],
+            rx_fifo_fill: [0; 2],
+            tx_fifo_put: 0,
+            tx_event_get: 0,
+            tx_event_put: 0,
+            tx_event_fill: 0,
+            timestamp: 0,
+            watchdog_elapsed: Duration::ZERO,
+            loopback: false,
+            bus_fault: false,
+            nint: false,
+            transactions: 0,
+            transmitted: Vec::new(),
+        };
+        state.reset();
+        state
+    }
+
+    fn reset(&mut self) {
+        self.regs.clear();
+        for (addr, val) in RESET_VALUES {
+            self.regs.insert(addr, val);
+        }
+        self.mram.iter_mut().for_each(|x| *x = 0);
+        self.rx_fifo_get = [0; 2];
+        self.rx_fifo_put = [0; 2];
+        self.rx_fifo_fill = [0; 2];
+        self.tx_fifo_put = 0;
+        self.tx_event_get = 0;
+        self.tx_event_put = 0;
+        self.tx_event_fill = 0;
+        self.timestamp = 0;
+        self.watchdog_elapsed = Duration::ZERO;
+    }
+
+    fn reg(&self, addr: u16) -> u32 {
+        self.regs.get(&addr).copied().unwrap_or(0)
+    }
+
+    fn set_reg(&mut self, addr: u16, val: u32) {
+        self.regs.insert(addr, val);
+    }
+
+    fn set_bits(&mut self, addr: u16, bits: u32) {
+        let val: u32 = self.reg(addr) | bits;
+        self.set_reg(addr, val);
+    }
+
+    fn clear_bits(&mut self, addr: u16, bits: u32) {
+        let val: u32 = self.reg(addr) & !bits;
+        self.set_reg(addr, val);
+    }
+
+    fn mram_word(&self, addr: u32) -> u32 {
+        self.mram[((addr as usize) / 4) % (MRAM_SIZE / 4)]
+    }
+
+    fn set_mram_word(&mut self, addr: u32, val: u32) {
+        self.mram[((addr as usize) / 4) % (MRAM_SIZE / 4)] = val;
+    }
+
+    fn is_mram(addr: u16) -> bool {
+        addr >= REG_MRAM && (addr as usize) < REG_MRAM as usize + MRAM_SIZE
+    }
+
+    fn cccr(&self) -> u32 {
+        self.reg(REG_MCAN_CCCR)
+    }
+
+    fn configuration_enabled(&self) -> bool {
+        let cccr: u32 = self.cccr();
+        (cccr & REG_BITS_MCAN_CCCR_INIT != 0) && (cccr & REG_BITS_MCAN_CCCR_CCE != 0)
+    }
+
+    fn is_operational(&self) -> bool {
+        let mode: u32 = self.reg(REG_DEV_MODES_AND_PINS) & REG_BITS_DEVICE_MODE_DEVICEMODE_MASK;
+        mode == REG_BITS_DEVICE_MODE_DEVICEMODE_NORMAL && (self.cccr() & REG_BITS_MCAN_CCCR_INIT == 0)
+    }
+
+    fn is_loopback(&self) -> bool {
+        let internal: bool = (self.cccr() & REG_BITS_MCAN_CCCR_TEST != 0) && (self.reg(REG_MCAN_TEST) & REG_BITS_MCAN_TEST_LOOP_BACK != 0);
+        internal || self.loopback
+    }
+
+    // TX buffer configuration: (dedicated buffers, FIFO/queue elements, start offset, element size)
+    fn tx_buffer_config(&self) -> (u32, u32, u32, u32) {
+        let txbc: u32 = self.reg(REG_MCAN_TXBC);
+        let ndtb: u32 = ((txbc >> 16) & 0x3F).min(32);
+        let tfqs: u32 = ((txbc >> 24) & 0x3F).min(32 - ndtb);
+        let tbsa: u32 = txbc & 0xFFFC;
+        let tbds: u32 = RXESC_TO_DATASIZE[(self.reg(REG_MCAN_TXESC) & 0x07) as usize];
+        (ndtb, tfqs, tbsa, tbds + 8)
+    }
+
+    // RX FIFO configuration: (elements, start offset, element size, overwrite mode, watermark)
+    fn rx_fifo_config(&self, ch: usize) -> (u32, u32, u32, bool, u32) {
+        let (rxfc, shift): (u32, u32) = if ch == 0 { (self.reg(REG_MCAN_RXF0C), 0) } else { (self.reg(REG_MCAN_RXF1C), 4) };
+        let size: u32 = ((rxfc >> 16) & 0x7F).min(64);
+        let start: u32 = rxfc & 0xFFFC;
+        let data_size: u32 = RXESC_TO_DATASIZE[((self.reg(REG_MCAN_RXESC) >> shift) & 0x07) as usize];
+        let overwrite: bool = rxfc & REG_BITS_MCAN_RXF0C_F0OM_OVERWRITE != 0;
+        let watermark: u32 = (rxfc >> 24) & 0x7F;
+        (size, start, data_size + 8, overwrite, watermark)
+    }
+
+    /// nINT is asserted while any enabled device interrupt flag is set
+    fn nint_asserted(&mut self) -> bool {
+        let dev_ir: u32 = self.read_register(REG_DEV_IR);
+        (dev_ir & REG_BITS_DEVICE_IR_M_CAN_INT != 0) || (dev_ir & self.reg(REG_DEV_IE) != 0)
+    }
+
+    fn read_register(&mut self, addr: u16) -> u32 {
+        if Self::is_mram(addr) {
+            return self.mram_word((addr - REG_MRAM) as u32);
+        }
+
+        match addr {
+            REG_DEV_IR => {
+                let mut dev_ir: u32 = self.reg(REG_DEV_IR) & !(REG_BITS_DEVICE_IR_M_CAN_INT | REG_BITS_DEVICE_IR_SPIERR);
+                if self.reg(REG_MCAN_IR) & self.reg(REG_MCAN_IE) != 0 && self.reg(REG_MCAN_ILE) != 0 {
+                    dev_ir |= REG_BITS_DEVICE_IR_M_CAN_INT;
+                }
+                if self.reg(REG_SPI_STATUS) & !self.reg(REG_SPI_ERROR_STATUS_MASK) != 0 {
+                    dev_ir |= REG_BITS_DEVICE_IR_SPIERR;
+                }
+                dev_ir
+            },
+            REG_MCAN_TSCV => self.timestamp as u32,
+            REG_MCAN_PSR => {
+                // LEC and DLEC are set to 7, PXE is cleared on read
+                let psr: u32 = self.reg(REG_MCAN_PSR);
+                self.set_reg(REG_MCAN_PSR, (psr & !0x4000) | 0x0707);
+                psr
+            },
+            REG_MCAN_ECR => {
+                // CEL is cleared on read
+                let ecr: u32 = self.reg(REG_MCAN_ECR);
+                self.set_reg(REG_MCAN_ECR, ecr & !0x00FF0000);
+                ecr
+            },
+            REG_MCAN_RXF0S | REG_MCAN_RXF1S => {
+                let ch: usize = if addr == REG_MCAN_RXF0S { 0 } else { 1 };
+                let (size, _, _, _, _) = self.rx_fifo_config(ch);
+                let lost: u32 = if ch == 0 { REG_BITS_MCAN_IR_RF0L } else { REG_BITS_MCAN_IR_RF1L };
+                let full: bool = size > 0 && self.rx_fifo_fill[ch] >= size;
+                let rfl: bool = self.reg(REG_MCAN_IR) & lost != 0;
+                ((rfl as u32) << 25)
+                    | ((full as u32) << 24)
+                    | (self.rx_fifo_put[ch] << 16)
+                    | (self.rx_fifo_get[ch] << 8)
+                    | self.rx_fifo_fill[ch]
+            },
+            REG_MCAN_TXFQS => {
+                let (ndtb, tfqs, _, _) = self.tx_buffer_config();
+                let fifo_mask: u32 = Self::bit_range(ndtb, tfqs);
+                let pending: u32 = (self.reg(REG_MCAN_TXBRP) & fifo_mask).count_ones();
+                let free: u32 = tfqs - pending.min(tfqs);
+                let put: u32 = ndtb + self.tx_fifo_put;
+                let get: u32 = if pending == 0 { put } else { (self.reg(REG_MCAN_TXBRP) & fifo_mask).trailing_zeros() };
+                (((tfqs > 0 && free == 0) as u32) << 21)
+                    | (put << 16)
+                    | (get << 8)
+                    | free
+            },
+            REG_MCAN_TXEFS => {
+                let efs: u32 = (self.reg(REG_MCAN_TXEFC) >> 16) & 0x3F;
+                let full: bool = efs > 0 && self.tx_event_fill >= efs;
+                let lost: bool = self.reg(REG_MCAN_IR) & REG_BITS_MCAN_IR_TEFL != 0;
+                ((lost as u32) << 25)
+                    | ((full as u32) << 24)
+                    | (self.tx_event_put << 16)
+                    | (self.tx_event_get << 8)
+                    | self.tx_event_fill
+            },
+            _ => self.reg(addr),
+        }
+    }
+
+    fn write_register(&mut self, addr: u16, val: u32) {
+        if Self::is_mram(addr) {
+            self.set_mram_word((addr - REG_MRAM) as u32, val);
+            return;
+        }
+
+        if READ_ONLY_REGISTERS.contains(&addr) {
+            return;
+        }
+        if PROTECTED_REGISTERS.contains(&addr) && !self.configuration_enabled() {
+            return;
+        }
+        if W1C_REGISTERS.contains(&addr) {
+            self.clear_bits(addr, val);
+            return;
+        }
+
+        match addr {
+            REG_DEV_MODES_AND_PINS => {
+                if val & REG_BITS_DEVICE_MODE_DEVICE_RESET != 0 {
+                    self.reset();
+                    return;
+                }
+                // Setting the watchdog reset bit or enabling the watchdog restarts the timer
+                let enabled: bool = self.reg(addr) & REG_BITS_DEVICE_MODE_WDT_EN != 0;
+                if val & REG_BITS_DEVICE_MODE_WDT_RESET_BIT != 0 || !enabled {
+                    self.watchdog_elapsed = Duration::ZERO;
+                }
+                // The watchdog reset bit always reads 0
+                self.set_reg(addr, val & !REG_BITS_DEVICE_MODE_WDT_RESET_BIT);
+            },
+            REG_MCAN_CCCR => self.write_cccr(val),
+            REG_MCAN_TEST => {
+                if self.cccr() & REG_BITS_MCAN_CCCR_TEST != 0 {
+                    self.set_reg(addr, val);
+                }
+            },
+            REG_MCAN_TSCV => self.timestamp = 0,
+            REG_MCAN_RXF0A | REG_MCAN_RXF1A => {
+                let ch: usize = if addr == REG_MCAN_RXF0A { 0 } else { 1 };
+                self.set_reg(addr, val & 0x3F);
+                self.acknowledge_rx_fifo(ch, val & 0x3F);
+            },
+            REG_MCAN_TXBAR => self.add_tx_requests(val),
+            REG_MCAN_TXBCR => self.cancel_tx_requests(val),
+            REG_MCAN_TXEFA => {
+                self.set_reg(addr, val & 0x1F);
+                self.acknowledge_tx_event(val & 0x1F);
+            },
+            _ => self.set_reg(addr, val),
+        }
+    }
+
+    fn write_cccr(&mut self, val: u32) {
+        let old: u32 = self.cccr();
+        let mut cccr: u32 = val;
+
+        // INIT can only be cleared once a write of CSR = 0 has released CSA
+        if old & REG_BITS_MCAN_CCCR_CSA != 0 {
+            cccr |= REG_BITS_MCAN_CCCR_INIT;
+        }
+
+        // CCE is cleared together with INIT
+        if cccr & REG_BITS_MCAN_CCCR_INIT == 0 {
+            cccr &= !REG_BITS_MCAN_CCCR_CCE;
+        }
+
+        // Configuration bits can only change while INIT is set and CCE is set or being set
+        const CONFIG_BITS: u32 = REG_BITS_MCAN_CCCR_NISO_BOSCH | REG_BITS_MCAN_CCCR_TXP | REG_BITS_MCAN_CCCR_EFBI
+            | REG_BITS_MCAN_CCCR_PXHD_DIS | REG_BITS_MCAN_CCCR_BRSE | REG_BITS_MCAN_CCCR_FDOE | REG_BITS_MCAN_CCCR_TEST
+            | REG_BITS_MCAN_CCCR_DAR_DIS | REG_BITS_MCAN_CCCR_MON | REG_BITS_MCAN_CCCR_ASM;
+        let unlocked: bool = (old & REG_BITS_MCAN_CCCR_INIT != 0) && (cccr & REG_BITS_MCAN_CCCR_CCE != 0);
+        if !unlocked {
+            cccr = (cccr & !CONFIG_BITS) | (old & CONFIG_BITS);
+        }
+
+        // Clock stop request is acknowledged immediately and puts the core into INIT
+        if cccr & REG_BITS_MCAN_CCCR_CSR != 0 {
+            cccr |= REG_BITS_MCAN_CCCR_CSA | REG_BITS_MCAN_CCCR_INIT;
+        } else {
+            cccr &= !REG_BITS_MCAN_CCCR_CSA;
+        }
+
+        // The TEST register is reset when test mode is left
+        if cccr & REG_BITS_MCAN_CCCR_TEST == 0 {
+            self.set_reg(REG_MCAN_TEST, 0);
+        }
+
+        self.set_reg(REG_MCAN_CCCR, cccr & !REG_BITS_MCAN_CCCR_RESERVED_MASK);
+
+        // Clearing INIT while bus off runs the recovery sequence, which completes at once on an idle bus
+        let recovering: bool = (old & REG_BITS_MCAN_CCCR_INIT != 0) && (cccr & REG_BITS_MCAN_CCCR_INIT == 0);
+        if recovering && self.reg(REG_MCAN_PSR) & REG_BITS_MCAN_PSR_BO != 0 && !self.bus_fault {
+            self.set_error_counters(0, 0);
+        }
+
+        self.process_tx();
+    }
+
+    fn bit_range(start: u32, len: u32) -> u32 {
+        if len == 0 { return 0; }
+        let mask: u32 = if len >= 32 { u32::MAX } else { (1u32 << len) - 1 };
+        mask << start
+    }
+
+    fn add_tx_requests(&mut self, val: u32) {
+        let (ndtb, tfqs, _, _) = self.tx_buffer_config();
+        let configured: u32 = Self::bit_range(0, ndtb + tfqs);
+        let requests: u32 = val & configured & !self.reg(REG_MCAN_TXBRP);
+        if requests == 0 {
+            return;
+        }
+
+        let fifo_put_bit: u32 = 1 << (ndtb + self.tx_fifo_put);
+        if tfqs > 0 && requests & fifo_put_bit != 0 {
+            self.tx_fifo_put = (self.tx_fifo_put + 1) % tfqs;
+        }
+
+        self.clear_bits(REG_MCAN_TXBTO, requests);
+        self.clear_bits(REG_MCAN_TXBCF, requests);
+        self.set_bits(REG_MCAN_TXBRP, requests);
+        self.process_tx();
+    }
+
+    fn cancel_tx_requests(&mut self, val: u32) {
+        let pending: u32 = self.reg(REG_MCAN_TXBRP) & val;
+        if pending == 0 {
+            return;
+        }
+        self.clear_bits(REG_MCAN_TXBRP, pending);
+        self.set_bits(REG_MCAN_TXBCF, pending);
+        if self.reg(REG_MCAN_TXBCIE) & pending != 0 {
+            self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_TCF);
+        }
+    }
+
+    fn process_tx(&mut self) {
+        if !self.is_operational() {
+            return;
+        }
+
+        let (_, _, tbsa, element_size) = self.tx_buffer_config();
+        let mut pending: u32 = self.reg(REG_MCAN_TXBRP);
+        while pending != 0 {
+            let index: u32 = pending.trailing_zeros();
+            let bit: u32 = 1 << index;
+            pending &= !bit;
+
+            let addr: u32 = tbsa + index * element_size;
+            let t0: u32 = self.mram_word(addr);
+            let t1: u32 = self.mram_word(addr + 4);
+            let frame: CanFrame = self.decode_element(t0, t1, addr + 8, element_size - 8);
+
+            self.advance_timestamp(1);
+            self.clear_bits(REG_MCAN_TXBRP, bit);
+            self.set_bits(REG_MCAN_TXBTO, bit);
+            if self.reg(REG_MCAN_TXBTIE) & bit != 0 {
+                self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_TC);
+            }
+
+            // EFC: store TX event
+            if (t1 >> 23) & 0x01 != 0 {
+                self.push_tx_event(t0, t1);
+            }
+
+            if self.is_loopback() {
+                self.receive_frame(&frame);
+            }
+            self.transmitted.push(frame);
+        }
+
+        let (ndtb, tfqs, _, _) = self.tx_buffer_config();
+        if tfqs > 0 && self.reg(REG_MCAN_TXBRP) & Self::bit_range(ndtb, tfqs) == 0 {
+            self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_TFE);
+        }
+    }
+
+    fn decode_element(&self, w0: u32, w1: u32, data_addr: u32, data_size: u32) -> CanFrame {
+        let xtd: bool = (w0 >> 30) & 0x01 != 0;
+        let rtr: bool = (w0 >> 29) & 0x01 != 0;
+        let fdf: bool = (w1 >> 21) & 0x01 != 0;
+        let dlc: u8 = ((w1 >> 16) & 0x0F) as u8;
+        let id: u32 = if xtd { w0 & 0x1FFFFFFF } else { (w0 >> 18) & 0x7FF };
+
+        let dlen: usize = match (rtr, fdf) {
+            (true, false) => 0,
+            (_, true) => CAN_DLC_TO_DLEN[dlc as usize] as usize,
+            (_, false) => (dlc as usize).min(8),
+        };
+        let mut data: Vec<u8> = Vec::with_capacity(dlen);
+        for i in 0..dlen.min(data_size as usize).div_ceil(4) {
+            data.extend(self.mram_word(data_addr + 4 * i as u32).to_le_bytes());
+        }
+        data.truncate(dlen.min(data_size as usize));
+
+        CanFrame {
+            id,
+            xtd,
+            rtr,
+            esi: (w0 >> 31) & 0x01 != 0,
+            fdf,
+            brs: (w1 >> 20) & 0x01 != 0,
+            dlc,
+            data,
+            rxts: 0,
+            timestamp: None,
+            fidx: 0,
+            anmf: false,
+            source: None,
+        }
+    }
+
+    fn push_tx_event(&mut self, t0: u32, t1: u32) {
+        let txefc: u32 = self.reg(REG_MCAN_TXEFC);
+        let size: u32 = (txefc >> 16) & 0x3F;
+        let start: u32 = txefc & 0xFFFC;
+        if size == 0 {
+            return;
+        }
+        if self.tx_event_fill >= size {
+            self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_TEFL);
+            return;
+        }
+
+        // E1: MM, ET = 01 (TX event), EDL, BRS, DLC, TXTS
+        let e1: u32 = (t1 & 0xFF000000) | (0b01 << 22) | (t1 & 0x003F0000) | self.timestamp as u32;
+        let addr: u32 = start + self.tx_event_put * 8;
+        self.set_mram_word(addr, t0);
+        self.set_mram_word(addr + 4, e1);
+
+        self.tx_event_put = (self.tx_event_put + 1) % size;
+        self.tx_event_fill += 1;
+        self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_TEFN);
+        if self.tx_event_fill >= size {
+            self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_TEFF);
+        }
+        let watermark: u32 = (txefc >> 24) & 0x3F;
+        if watermark > 0 && self.tx_event_fill >= watermark {
+            self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_TEFW);
+        }
+    }
+
+    fn acknowledge_tx_event(&mut self, index: u32) {
+        let size: u32 = (self.reg(REG_MCAN_TXEFC) >> 16) & 0x3F;
+        if size == 0 || self.tx_event_fill == 0 {
+            return;
+        }
+        let released: u32 = (index + size - self.tx_event_get) % size + 1;
+        let released: u32 = released.min(self.tx_event_fill);
+        self.tx_event_get = (self.tx_event_get + released) % size;
+        self.tx_event_fill -= released;
+    }
+
+    fn acknowledge_rx_fifo(&mut self, ch: usize, index: u32) {
+        let (size, _, _, _, _) = self.rx_fifo_config(ch);
+        if size == 0 || self.rx_fifo_fill[ch] == 0 {
+            return;
+        }
+        let released: u32 = (index + size - self.rx_fifo_get[ch]) % size + 1;
+        let released: u32 = released.min(self.rx_fifo_fill[ch]);
+        self.rx_fifo_get[ch] = (self.rx_fifo_get[ch] + released) % size;
+        self.rx_fifo_fill[ch] -= released;
+    }
+
+    fn match_filters(&self, frame: &CanFrame) -> Option<FilterMatch> {
+        if frame.xtd {
+            let xidfc: u32 = self.reg(REG_MCAN_XIDFC);
+            let lse: u32 = (xidfc >> 16) & 0x7F;
+            let flesa: u32 = xidfc & 0xFFFC;
+            let xidam: u32 = self.reg(REG_MCAN_XIDAM) & 0x1FFFFFFF;
+            for i in 0..lse {
+                let f0: u32 = self.mram_word(flesa + 8 * i);
+                let f1: u32 = self.mram_word(flesa + 8 * i + 4);
+                let efec: u32 = (f0 >> 29) & 0x07;
+                let efid1: u32 = f0 & 0x1FFFFFFF;
+                let eft: u32 = (f1 >> 30) & 0x03;
+                let efid2: u32 = f1 & 0x1FFFFFFF;
+                if efec == 0 {
+                    continue;
+                }
+                let id: u32 = frame.id & xidam;
+                let matched: bool = if efec == 7 {
+                    frame.id == efid1
+                } else {
+                    match eft {
+                        0 => id >= efid1 && id <= efid2,
+                        1 => id == efid1 || id == efid2,
+                        2 => (id & efid2) == (efid1 & efid2),
+                        _ => frame.id >= efid1 && frame.id <= efid2,
+                    }
+                };
+                if matched {
+                    return Some(Self::filter_action(efec, efid2 & 0x3F, i));
+                }
+            }
+        } else {
+            let sidfc: u32 = self.reg(REG_MCAN_SIDFC);
+            let lss: u32 = (sidfc >> 16) & 0xFF;
+            let flssa: u32 = sidfc & 0xFFFC;
+            for i in 0..lss {
+                let s0: u32 = self.mram_word(flssa + 4 * i);
+                let sft: u32 = (s0 >> 30) & 0x03;
+                let sfec: u32 = (s0 >> 27) & 0x07;
+                let sfid1: u32 = (s0 >> 16) & 0x7FF;
+                let sfid2: u32 = s0 & 0x7FF;
+                if sfec == 0 {
+                    continue;
+                }
+                let matched: bool = if sfec == 7 {
+                    frame.id == sfid1
+                } else {
+                    match sft {
+                        0 => frame.id >= sfid1 && frame.id <= sfid2,
+                        1 => frame.id == sfid1 || frame.id == sfid2,
+                        2 => (frame.id & sfid2) == (sfid1 & sfid2),
+                        _ => false,
+                    }
+                };
+                if matched {
+                    return Some(Self::filter_action(sfec, sfid2 & 0x3F, i));
+                }
+            }
+        }
+        None
+    }
+
+    fn filter_action(fec: u32, buffer_index: u32, fidx: u32) -> FilterMatch {
+        let (target, priority): (RxTarget, bool) = match fec {
+            1 => (RxTarget::Fifo(0), false),
+            2 => (RxTarget::Fifo(1), false),
+            3 => (RxTarget::Reject, false),
+            4 => (RxTarget::PriorityOnly, true),
+            5 => (RxTarget::Fifo(0), true),
+            6 => (RxTarget::Fifo(1), true),
+            7 => (RxTarget::Buffer(buffer_index), false),
+            _ => (RxTarget::Reject, false),
+        };
+        FilterMatch { target, priority, fidx }
+    }
+
+    fn encode_rx_header(&self, frame: &CanFrame, fidx: u32, anmf: bool) -> (u32, u32) {
+        let id: u32 = if frame.xtd { frame.id & 0x1FFFFFFF } else { (frame.id & 0x7FF) << 18 };
+        let r0: u32 = ((frame.esi as u32) << 31) | ((frame.xtd as u32) << 30) | ((frame.rtr as u32) << 29) | id;
+        let r1: u32 = ((anmf as u32) << 31)
+            | ((fidx & 0x7F) << 24)
+            | ((frame.fdf as u32) << 21)
+            | ((frame.brs as u32) << 20)
+            | ((frame.dlc as u32 & 0x0F) << 16)
+            | self.timestamp as u32;
+        (r0, r1)
+    }
+
+    fn write_rx_element(&mut self, addr: u32, data_size: u32, frame: &CanFrame, fidx: u32, anmf: bool) {
+        let (r0, r1) = self.encode_rx_header(frame, fidx, anmf);
+        self.set_mram_word(addr, r0);
+        self.set_mram_word(addr + 4, r1);
+        let mut data: Vec<u8> = frame.data.clone();
+        data.resize(data_size as usize, 0);
+        for (i, x) in data.chunks(4).enumerate() {
+            self.set_mram_word(addr + 8 + 4 * i as u32, u32::from_le_bytes([x[0], x[1], x[2], x[3]]));
+        }
+    }
+
+    /// Returns the FIFO put index the frame was stored at
+    fn store_rx_fifo(&mut self, ch: usize, frame: &CanFrame, fidx: u32, anmf: bool) -> Option<u32> {
+        let (size, start, element_size, overwrite, watermark) = self.rx_fifo_config(ch);
+        let (new_message, full, lost, watermark_reached) = if ch == 0 {
+            (REG_BITS_MCAN_IR_RF0N, REG_BITS_MCAN_IR_RF0F, REG_BITS_MCAN_IR_RF0L, REG_BITS_MCAN_IR_RF0W)
+        } else {
+            (REG_BITS_MCAN_IR_RF1N, REG_BITS_MCAN_IR_RF1F, REG_BITS_MCAN_IR_RF1L, REG_BITS_MCAN_IR_RF1W)
+        };
+
+        if size == 0 {
+            self.set_bits(REG_MCAN_IR, lost);
+            return None;
+        }
+        if self.rx_fifo_fill[ch] >= size {
+            if !overwrite {
+                self.set_bits(REG_MCAN_IR, lost);
+                return None;
+            }
+            // Overwrite the oldest element
+            self.rx_fifo_get[ch] = (self.rx_fifo_get[ch] + 1) % size;
+            self.rx_fifo_fill[ch] -= 1;
+        }
+
+        let put: u32 = self.rx_fifo_put[ch];
+        self.write_rx_element(start + put * element_size, element_size - 8, frame, fidx, anmf);
+        self.rx_fifo_put[ch] = (put + 1) % size;
+        self.rx_fifo_fill[ch] += 1;
+
+        self.set_bits(REG_MCAN_IR, new_message);
+        if self.rx_fifo_fill[ch] >= size {
+            self.set_bits(REG_MCAN_IR, full);
+        }
+        if watermark > 0 && self.rx_fifo_fill[ch] >= watermark {
+            self.set_bits(REG_MCAN_IR, watermark_reached);
+        }
+        Some(put)
+    }
+
+    fn store_rx_buffer(&mut self, index: u32, frame: &CanFrame, fidx: u32) -> bool {
+        let rbsa: u32 = self.reg(REG_MCAN_RXBC) & 0xFFFC;
+        let data_size: u32 = RXESC_TO_DATASIZE[((self.reg(REG_MCAN_RXESC) >> 8) & 0x07) as usize];
+        let (ndat_addr, bit): (u16, u32) = if index < 32 { (REG_MCAN_NDAT1, 1 << index) } else { (REG_MCAN_NDAT2, 1 << (index - 32)) };
+
+        // A buffer holding unread data is not overwritten
+        if self.reg(ndat_addr) & bit != 0 {
+            return false;
+        }
+        self.write_rx_element(rbsa + index * (data_size + 8), data_size, frame, fidx, false);
+        self.set_bits(ndat_addr, bit);
+        self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_DRX);
+        true
+    }
+
+    fn receive_frame(&mut self, frame: &CanFrame) -> bool {
+        if !self.is_operational() {
+            return false;
+        }
+        self.advance_timestamp(1);
+
+        let gfc: u32 = self.reg(REG_MCAN_GFC);
+        let reject_remote: bool = if frame.xtd { gfc & REG_BITS_MCAN_GFC_RRFE != 0 } else { gfc & REG_BITS_MCAN_GFC_RRFS != 0 };
+        if frame.rtr && reject_remote {
+            return false;
+        }
+
+        match self.match_filters(frame) {
+            Some(FilterMatch { target, priority, fidx }) => {
+                let stored: Option<(u32, u32)> = match target {
+                    RxTarget::Fifo(ch) => self.store_rx_fifo(ch, frame, fidx, false).map(|put| (put, 0b10 | ch as u32)),
+                    RxTarget::Buffer(index) => {
+                        self.store_rx_buffer(index, frame, fidx);
+                        None
+                    },
+                    RxTarget::Reject | RxTarget::PriorityOnly => None,
+                };
+                if priority {
+                    let (bidx, msi): (u32, u32) = match (target, stored) {
+                        (_, Some(x)) => x,
+                        (RxTarget::PriorityOnly, None) => (0, 0b00),
+                        _ => (0, 0b01),
+                    };
+                    let hpms: u32 = ((frame.xtd as u32) << 15) | ((fidx & 0x7F) << 8) | (msi << 6) | (bidx & 0x3F);
+                    self.set_reg(REG_MCAN_HPMS, hpms);
+                    self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_HPM);
+                }
+                stored.is_some() || matches!(target, RxTarget::Buffer(_))
+            },
+            None => {
+                let anf: u32 = if frame.xtd { (gfc >> 2) & 0x03 } else { (gfc >> 4) & 0x03 };
+                match anf {
+                    0 => self.store_rx_fifo(0, frame, 0, true).is_some(),
+                    1 => self.store_rx_fifo(1, frame, 0, true).is_some(),
+                    _ => false,
+                }
+            },
+        }
+    }
+
+    /// Count `ticks`, raising TSW when the counter wraps around
+    fn advance_timestamp(&mut self, ticks: u32) {
+        let counter: u32 = self.timestamp as u32 + ticks;
+        self.timestamp = counter as u16;
+        if counter > 0xFFFF {
+            self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_TSW);
+        }
+    }
+
+    /// Leave sleep for standby as the given wake event would. A power on resets the device.
+    fn wake(&mut self, reason: WakeReason) {
+        let flag: u32 = match reason {
+            WakeReason::PowerOn => {
+                self.reset();
+                return;
+            },
+            WakeReason::WakePin => REG_BITS_DEVICE_IR_LWU,
+            WakeReason::CanBus => REG_BITS_DEVICE_IR_CANINT,
+        };
+        let modes: u32 = self.reg(REG_DEV_MODES_AND_PINS);
+        if modes & REG_BITS_DEVICE_MODE_DEVICEMODE_MASK == REG_BITS_DEVICE_MODE_DEVICEMODE_SLEEP {
+            self.set_reg(REG_DEV_MODES_AND_PINS, modes | REG_BITS_DEVICE_MODE_DEVICEMODE_STANDBY);
+        }
+        self.set_bits(REG_DEV_IR, flag);
+    }
+
+    /// Let `elapsed` pass without a watchdog reset, raising WDTO when the enabled watchdog times out
+    fn advance_watchdog(&mut self, elapsed: Duration) {
+        let modes: u32 = self.reg(REG_DEV_MODES_AND_PINS);
+        if modes & REG_BITS_DEVICE_MODE_WDT_EN == 0 {
+            return;
+        }
+        let timeout: Duration = match modes & REG_BITS_DEVICE_MODE_WD_TIMER_MASK {
+            REG_BITS_DEVICE_MODE_WD_TIMER_60MS => Duration::from_millis(60),
+            REG_BITS_DEVICE_MODE_WD_TIMER_600MS => Duration::from_millis(600),
+            REG_BITS_DEVICE_MODE_WD_TIMER_3S => Duration::from_secs(3),
+            _ => Duration::from_secs(6),
+        };
+        self.watchdog_elapsed += elapsed;
+        if self.watchdog_elapsed >= timeout {
+            self.set_bits(REG_DEV_IR, REG_BITS_DEVICE_IR_WDTO);
+            self.watchdog_elapsed = Duration::ZERO;
+        }
+    }
+
+    fn set_error_counters(&mut self, tec: u32, rec: u32) {
+        let bo: bool = tec > 255;
+        let ew: bool = tec >= 96 || rec >= 96;
+        let ep: bool = tec >= 128 || rec >= 128;
+        let rp: bool = rec >= 128;
+
+        let cel: u32 = self.reg(REG_MCAN_ECR) & 0x00FF0000;
+        self.set_reg(REG_MCAN_ECR, cel | ((rp as u32) << 15) | (rec.min(127) << 8) | tec.min(255));
+
+        let old: u32 = self.reg(REG_MCAN_PSR);
+        let psr: u32 = (old & !0xE0) | ((bo as u32) << 7) | ((ew as u32) << 6) | ((ep as u32) << 5);
+        self.set_reg(REG_MCAN_PSR, psr);
+
+        let changed: u32 = old ^ psr;
+        if changed & 0x80 != 0 {
+            self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_BO);
+        }
+        if changed & 0x40 != 0 {
+            self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_EW);
+        }
+        if changed & 0x20 != 0 {
+            self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_EP);
+        }
+
+        // Bus off puts the core into INIT
+        if bo {
+            let cccr: u32 = self.cccr() | REG_BITS_MCAN_CCCR_INIT;
+            self.set_reg(REG_MCAN_CCCR, cccr);
+        }
+    }
+
+    fn protocol_error(&mut self, data_phase: bool, code: u32) {
+        let psr: u32 = self.reg(REG_MCAN_PSR);
+        if data_phase {
+            self.set_reg(REG_MCAN_PSR, (psr & !0x0700) | ((code & 0x07) << 8));
+            self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_PED);
+        } else {
+            self.set_reg(REG_MCAN_PSR, (psr & !0x0007) | (code & 0x07));
+            self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_PEA);
+        }
+
+        let ecr: u32 = self.reg(REG_MCAN_ECR);
+        let cel: u32 = (ecr >> 16) & 0xFF;
+        if cel == 0xFF {
+            self.set_bits(REG_MCAN_IR, REG_BITS_MCAN_IR_ELO);
+        }
+        self.set_reg(REG_MCAN_ECR, (ecr & !0x00FF0000) | (((cel + 1) & 0xFF) << 16));
+    }
+
+    fn spi_write(&mut self, data: &[u8]) {
+        let mut pos: usize = 0;
+        while pos + 4 <= data.len() {
+            let opcode: u8 = data[pos];
+            let addr: u16 = u16::from_be_bytes([data[pos + 1], data[pos + 2]]);
+            let len: usize = data[pos + 3] as usize;
+            pos += 4;
+
+            if opcode != WRITE_B_FL {
+                self.set_bits(REG_SPI_STATUS, SPI_STATUS_INVALID_COMMAND);
+                return;
+            }
+            for i in 0..len {
+                if pos + 4 > data.len() {
+                    return;
+                }
+                let word: u32 = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+                self.write_register(addr.wrapping_add(4 * i as u16), word);
+                pos += 4;
+            }
+        }
+    }
+
+    fn spi_read(&mut self, data: &[u8], buffer: &mut [u8]) {
+        if data.len() < 4 {
+            return;
+        }
+        let opcode: u8 = data[0];
+        let addr: u16 = u16::from_be_bytes([data[1], data[2]]);
+        let len: usize = data[3] as usize;
+
+        buffer.iter_mut().for_each(|x| *x = 0);
+        if opcode == WRITE_B_FL {
+            self.spi_write(data);
+            return;
+        }
+        if opcode != READ_B_FL {
+            self.set_bits(REG_SPI_STATUS, SPI_STATUS_INVALID_COMMAND);
+            return;
+        }
+        for i in 0..len {
+            let pos: usize = 4 + 4 * i;
+            if pos + 4 > buffer.len() {
+                break;
+            }
+            let word: u32 = self.read_register(addr.wrapping_add(4 * i as u16));
+            buffer[pos..pos + 4].copy_from_slice(&word.to_be_bytes());
+        }
+    }
+}
+
+/// Simulated TCAN4550
+///
+/// Decodes the SPI commands of `TCAN455xController`, keeps a register file and 2 KB MRAM,
+/// and emulates the TX buffers, RX FIFOs, acceptance filtering, interrupt flags and loopback.
+/// Clones share the same device, so a test can keep one handle while the tranceiver owns another.
+#[derive(Clone)]
+pub struct SimulatedTCAN4550 {
+    state: Arc<Mutex<SimState>>,
+    interrupt: InterruptSignal,
+}
+
+impl Default for SimulatedTCAN4550 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimulatedTCAN4550 {
+    pub fn new() -> Self {
+        Self { state: Arc::new(Mutex::new(SimState::new())), interrupt: InterruptSignal::default() }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SimState> {
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Run `f` on the device and signal nINT if it became asserted
+    fn with_state<T>(&self, f: impl FnOnce(&mut SimState) -> T) -> T {
+        let (ret, asserted): (T, bool) = {
+            let mut state = self.lock();
+            let ret: T = f(&mut state);
+            let nint: bool = state.nint_asserted();
+            let asserted: bool = nint && !state.nint;
+            state.nint = nint;
+            (ret, asserted)
+        };
+        if asserted {
+            self.interrupt.notify();
+        }
+        ret
+    }
+
+    /// Receive every transmitted frame as if it came back from the bus
+    pub fn set_loopback(&self, enable: bool) {
+        self.lock().loopback = enable;
+    }
+
+    /// Put a frame on the bus. Returns true when it was stored in a FIFO or RX buffer.
+    pub fn inject_frame(&self, frame: &CanFrame) -> bool {
+        let mut frame: CanFrame = frame.clone();
+        if !frame.rtr {
+            frame.dlc = crate::tranceiver::TCAN455xTranceiver::CAN_DLEN_TO_DLC[frame.data.len().min(64)];
+        }
+        self.with_state(|state| state.receive_frame(&frame))
+    }
+
+    /// Frames sent on the bus so far
+    pub fn transmitted_frames(&self) -> Vec<CanFrame> {
+        self.lock().transmitted.clone()
+    }
+
+    pub fn clear_transmitted_frames(&self) {
+        self.lock().transmitted.clear();
+    }
+
+    /// Register or MRAM word as the device would return it over SPI
+    pub fn peek(&self, addr: u16) -> u32 {
+        self.lock().read_register(addr)
+    }
+
+    /// Overwrite a register or MRAM word, bypassing write protection and side effects
+    pub fn poke(&self, addr: u16, val: u32) {
+        self.with_state(|state| {
+            if SimState::is_mram(addr) {
+                state.set_mram_word((addr - REG_MRAM) as u32, val);
+            } else {
+                state.set_reg(addr, val);
+            }
+        })
+    }
+
+    /// Set TEC and REC as bus errors would, updating EW, EP and BO. A TEC above 255 puts the node bus off.
+    pub fn set_error_counters(&self, tec: u32, rec: u32) {
+        self.with_state(|state| state.set_error_counters(tec, rec))
+    }
+
+    /// Keep the bus dominant so a bus off node never completes its recovery sequence
+    pub fn set_bus_fault(&self, fault: bool) {
+        self.with_state(|state| {
+            state.bus_fault = fault;
+
+            // A recovery sequence already started completes once the bus is released
+            let recovering: bool = state.cccr() & REG_BITS_MCAN_CCCR_INIT == 0;
+            if !fault && recovering && state.reg(REG_MCAN_PSR) & REG_BITS_MCAN_PSR_BO != 0 {
+                state.set_error_counters(0, 0);
+            }
+        })
+    }
+
+    /// Let `ticks` of the timestamp counter pass
+    pub fn advance_timestamp(&self, ticks: u32) {
+        self.with_state(|state| state.advance_timestamp(ticks))
+    }
+
+    /// SPI transactions so far
+    pub fn spi_transactions(&self) -> usize {
+        self.lock().transactions
+    }
+
+    /// Wake the device from sleep or standby
+    pub fn wake(&self, reason: WakeReason) {
+        self.with_state(|state| state.wake(reason))
+    }
+
+    /// Let `elapsed` pass on the watchdog timer since the last reset of the timer
+    pub fn advance_watchdog(&self, elapsed: Duration) {
+        self.with_state(|state| state.advance_watchdog(elapsed))
+    }
+
+    /// Report a protocol error with the given LEC code in the arbitration or data phase
+    pub fn inject_protocol_error(&self, data_phase: bool, code: u32) {
+        self.with_state(|state| state.protocol_error(data_phase, code))
+    }
+
+    /// Level of the active low nINT pin
+    pub fn nint_asserted(&self) -> bool {
+        self.lock().nint_asserted()
+    }
+}
+
+impl GpioDriver for SimulatedTCAN4550 {
+    fn gpio_out(&mut self, _state: u8) -> CandsResult<()> {
+        Ok(())
+    }
+
+    fn gpio_read(&mut self, _channel: usize) -> CandsResult<bool> {
+        Ok(false)
+    }
+
+    fn gpio_read_all(&mut self) -> CandsResult<[bool; GPI_MAX_POINT]> {
+        Ok([false; GPI_MAX_POINT])
+    }
+}
+
+impl TCAN455xDriver for SimulatedTCAN4550 {
+    fn tcan455x_write(&mut self, data: &[u8]) -> CandsResult<usize> {
+        self.with_state(|state| {
+            state.transactions += 1;
+            state.spi_write(data)
+        });
+        Ok(data.len())
+    }
+
+    fn tcan455x_read(&mut self, _buffer: &mut [u8]) -> CandsResult<usize> {
+        Ok(0)
+    }
+
+    fn tcan455x_transfer(&mut self, data: &[u8], buffer: &mut [u8]) -> CandsResult<usize> {
+        if buffer.len() < data.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Receive buffer shorter than the command").into());
+        }
+        self.with_state(|state| {
+            state.transactions += 1;
+            state.spi_read(data, buffer)
+        });
+        Ok(data.len())
+    }
+
+    fn tcan455x_transfer_in_place(&mut self, data: &mut [u8]) -> CandsResult<usize> {
+        let cmd: Vec<u8> = data.to_vec();
+        self.with_state(|state| {
+            state.transactions += 1;
+            state.spi_read(&cmd, data)
+        });
+        Ok(data.len())
+    }
+
+    fn tcan455x_reset(&mut self) -> CandsResult<()> {
+        self.with_state(|state| state.reset());
+        Ok(())
+    }
+}
+
+impl InterruptDriver for SimulatedTCAN4550 {
+    fn wait_for_interrupt(&mut self, timeout: Option<Duration>) -> CandsResult<bool> {
+        // nINT is level triggered: an assertion still pending needs no edge
+        if self.nint_asserted() {
+            self.interrupt.clear();
+            return Ok(true);
+        }
+        Ok(self.interrupt.wait(timeout))
+    }
+
+    fn set_interrupt_callback(&mut self, callback: Option<InterruptCallback>) {
+        self.interrupt.set_callback(callback);
+    }
+}
+
+impl ADCDriver for SimulatedTCAN4550 {
+    fn adc_reset(&mut self) -> CandsResult<()> {
+        Ok(())
+    }
+
+    fn adc_write(&mut self, data: &[u8]) -> CandsResult<usize> {
+        Ok(data.len())
+    }
+
+    fn adc_read(&mut self, buffer: &mut [u8]) -> CandsResult<usize> {
+        buffer.iter_mut().for_each(|x| *x = 0);
+        Ok(buffer.len())
+    }
+
+    fn adc_transfer(&mut self, data: &[u8], buffer: &mut [u8]) -> CandsResult<usize> {
+        buffer.iter_mut().for_each(|x| *x = 0);
+        Ok(data.len())
+    }
+
+    fn adc_transfer_in_place(&mut self, data: &mut [u8]) -> CandsResult<usize> {
+        data.iter_mut().for_each(|x| *x = 0);
+        Ok(data.len())
+    }
+}
+
+impl WS2812Driver for SimulatedTCAN4550 {
+    fn ws2812_write(&mut self, data: &[u8]) -> CandsResult<usize> {
+        Ok(data.len())
+    }
+}
+
+#[cfg(not(any(feature="raspberrypi", feature="raspberrypi_cm")))]
+impl DeviceDriver for SimulatedTCAN4550 {}
+
+#[cfg(any(feature="raspberrypi", feature="raspberrypi_cm"))]
+impl RaspiDeviceDriver for SimulatedTCAN4550 {}
@@ -0,0 +1,107 @@
+use std::{error::Error as StdError, fmt, io};
+
+use crate::tranceiver::verify::ConfigMismatch;
+
+#[cfg(feature="usb-ftdi")]
+use ftdi_embedded_hal::Error as FtdiError;
+
+pub type CandsResult<T> = Result<T, CandsError>;
+
+/// Error of the CAN interface
+#[derive(Debug)]
+pub enum CandsError {
+    /// SPI/GPIO transport failure, keeping the error of the FTDI or rppal backend as the source
+    Transport(Box<dyn StdError + Send + Sync>),
+    /// SPI_STATUS reported a fault (register value)
+    SpiStatus(u32),
+    /// The device did not answer within the timeout
+    Timeout,
+    /// No free element in the TX FIFO/queue
+    TxFifoFull,
+    /// Dedicated TX buffer still has a transmission request pending (buffer index)
+    TxBufferPending(u8),
+    /// Frame ID, flags or payload cannot be transmitted
+    InvalidFrame(String),
+    /// Configuration value out of range
+    InvalidConfig(String),
+    /// Configuration read back differs from what was written
+    ConfigMismatch(Vec<ConfigMismatch>),
+    /// No TCAN455x answered on the SPI bus
+    DeviceNotFound,
+}
+
+impl fmt::Display for CandsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CandsError::Transport(e) => write!(f, "Transport error: {}", e),
+            CandsError::SpiStatus(status) => write!(f, "SPI status fault: 0x{:08X}", status),
+            CandsError::Timeout => write!(f, "Timed out"),
+            CandsError::TxFifoFull => write!(f, "TX FIFO full"),
+            CandsError::TxBufferPending(index) => write!(f, "TX buffer {} has a pending request", index),
+            CandsError::InvalidFrame(msg) => write!(f, "Invalid frame: {}", msg),
+            CandsError::InvalidConfig(msg) => write!(f, "Invalid configuration: {}", msg),
+            CandsError::ConfigMismatch(mismatches) => {
+                write!(f, "Configuration mismatch")?;
+                for (i, mismatch) in mismatches.iter().enumerate() {
+                    write!(f, "{} {}", if i == 0 { ":" } else { ";" }, mismatch)?;
+                }
+                Ok(())
+            },
+            CandsError::DeviceNotFound => write!(f, "Device not found"),
+        }
+    }
+}
+
+impl StdError for CandsError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            CandsError::Transport(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CandsError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::TimedOut => CandsError::Timeout,
+            _ => CandsError::Transport(Box::new(err)),
+        }
+    }
+}
+
+impl From<CandsError> for io::Error {
+    fn from(err: CandsError) -> Self {
+        match err {
+            CandsError::Transport(e) => match e.downcast::<io::Error>() {
+                Ok(e) => *e,
+                Err(e) => io::Error::other(e),
+            },
+            CandsError::Timeout => io::ErrorKind::TimedOut.into(),
+            CandsError::DeviceNotFound => io::Error::new(io::ErrorKind::NotConnected, err.to_string()),
+            CandsError::InvalidFrame(_) | CandsError::InvalidConfig(_) => io::Error::new(io::ErrorKind::InvalidInput, err.to_string()),
+            _ => io::Error::other(err.to_string()),
+        }
+    }
+}
+
+#[cfg(feature="usb-ftdi")]
+impl<E: StdError + Send + Sync + 'static> From<FtdiError<E>> for CandsError {
+    fn from(err: FtdiError<E>) -> Self {
+        CandsError::Transport(Box::new(err))
+    }
+}
+
+#[cfg(any(feature="raspberrypi", feature="raspberrypi_cm"))]
+impl From<rppal::spi::Error> for CandsError {
+    fn from(err: rppal::spi::Error) -> Self {
+        CandsError::Transport(Box::new(err))
+    }
+}
+
+#[cfg(any(feature="raspberrypi", feature="raspberrypi_cm"))]
+impl From<rppal::gpio::Error> for CandsError {
+    fn from(err: rppal::gpio::Error) -> Self {
+        CandsError::Transport(Box::new(err))
+    }
+}
@@ -1,11 +1,23 @@
+mod error;
 mod device_driver;
 mod tcan4550;
 mod tranceiver;
 
-pub use tcan4550::id_filter::{SIDConfig, XIDConfig};
+pub use tcan4550::id_filter::{SIDConfig, XIDConfig, FilterType, FilterAction, IdFilter, IdFilters, IdFilterBuilder};
 pub use tcan4550::register as tcan4550_register;
+pub use tcan4550::bitfield::{self as tcan4550_bitfield, Register};
+pub use tcan4550::register_map::{FieldInfo, RegisterInfo, DecodedRegister, RegisterDump, RegisterDiff, REGISTER_MAP};
+pub use tcan4550::controller::configurator::bit_timing::{BitTiming, ClockRef};
+pub use tcan4550::controller::configurator::global_filter::{GlobalFilterConfig, NonMatchingFrames};
+pub use tcan4550::controller::configurator::timestamp::TimestampConfig;
+pub use tcan4550::controller::configurator::modes_and_pins::{
+    DeviceConfig, WakeConfig, WatchdogTimer, WatchdogAction, Gpo1Function, Gpo2Function, Gpio1Config, NwkrqConfig,
+};
+pub use tcan4550::controller::configurator::mram::{MramLayout, MramLayoutBuilder, MramSection, FIFODATASIZE};
 
+pub use error::{CandsError, CandsResult};
 pub use tranceiver::TCAN455xTranceiver;
+pub use tranceiver::asynchronous::AsyncTCAN455x;
 
 #[cfg(feature="raspberrypi")]
 pub use device_driver::raspberrypi::GPIO_INPUT_PIN_NUM;
@@ -19,4 +31,23 @@ pub use device_driver::raspberrypi::GPIO_OUTPUT_PIN_NUM;
 #[cfg(feature="raspberrypi_cm")]
 pub use device_driver::raspberrypi_cm::GPIO_OUTPUT_PIN_NUM;
 
-pub use tranceiver::rx_buffer::RxData;
\ No newline at end of file
+#[cfg(feature="raspberrypi")]
+pub use device_driver::raspberrypi::DEFAULT_TCAN_NINT_PIN_BCM;
+
+#[cfg(feature="raspberrypi_cm")]
+pub use device_driver::raspberrypi_cm::DEFAULT_TCAN_NINT_PIN_BCM;
+
+pub use tranceiver::rx_buffer::{RxData, CanFrame, RxSource};
+pub use tranceiver::bus_state::{ErrorState, BusState, BusEvent, LastErrorCode, Activity};
+pub use tranceiver::recovery::BusOffRecovery;
+pub use tranceiver::tx_event::{TxEvent, TxEventType};
+pub use tranceiver::tx_dedicated::TxBufferStatus;
+pub use tranceiver::priority::{HighPriorityMessage, HighPriorityStatus, MessageStorage};
+pub use tranceiver::timestamp::{RxTimestamp, TimestampSync};
+pub use tranceiver::verify::ConfigMismatch;
+pub use tranceiver::watchdog::{Heartbeat, WatchdogService};
+pub use tranceiver::power::WakeReason;
+pub use tranceiver::device_event::DeviceEvent;
+pub use tranceiver::batch::WriteBatch;
+#[cfg(feature="simulator")]
+pub use device_driver::simulator::SimulatedTCAN4550;
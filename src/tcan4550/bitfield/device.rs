@@ -0,0 +1,74 @@
+use crate::tcan4550::register::*;
+
+register! {
+    /// Modes of operation and pin configuration
+    DevModesAndPins @ REG_DEV_MODES_AND_PINS => {
+        /// 0: PHY test, 1: CAN controller test
+        test_mode_config: bool = [0; 1],
+        /// Sleep wake error disable
+        swe_dis: bool = [1; 1],
+        device_reset: bool = [2; 1],
+        wd_en: bool = [3; 1],
+        /// 0: sleep, 1: standby, 2: normal
+        mode_sel: u8 = [6; 2],
+        /// 0: mirrors INH, 1: wake request interrupt
+        nwkrq_config: bool = [8; 1],
+        inh_dis: bool = [9; 1],
+        /// GPO1 function, 0: SPI fault, 1: MCAN_INT 1, 2: UVO or TSD
+        gpio1_gpo_config: u8 = [10; 2],
+        clkout_prescaler: bool = [12; 1],
+        fail_safe_en: bool = [13; 1],
+        /// 0: GPO, 1: CLKOUT, 2: GPI
+        gpio1_config: u8 = [14; 2],
+        wd_action: u8 = [16; 2],
+        /// Write 1 to reset the watchdog timer
+        wd_bit_set: bool = [18; 1],
+        /// 0: internal rail, 1: VIO rail
+        nwkrq_voltage: bool = [19; 1],
+        test_mode_en: bool = [21; 1],
+        /// 0: no action, 1: MCAN_INT 0, 2: watchdog output, 3: nINT
+        gpo2_config: u8 = [22; 2],
+        /// 0: 20 MHz, 1: 40 MHz crystal
+        clk_ref: bool = [27; 1],
+        /// 0: 60 ms, 1: 600 ms, 2: 3 s, 3: 6 s
+        wd_timer: u8 = [28; 2],
+        /// 0: disabled, 1: rising edge, 2: falling edge, 3: both edges
+        wake_config: u8 = [30; 2],
+    }
+}
+
+impl DevModesAndPins {
+    pub const MODE_SLEEP: u8 = 0;
+    pub const MODE_STANDBY: u8 = 1;
+    pub const MODE_NORMAL: u8 = 2;
+}
+
+register! {
+    /// Device interrupt flags (write 1 to clear)
+    DevIr @ REG_DEV_IR => {
+        vtwd: bool = [0; 1],
+        m_can_int: bool = [1; 1],
+        swerr: bool = [2; 1],
+        spierr: bool = [3; 1],
+        canbusfault: bool = [4; 1],
+        canerr: bool = [5; 1],
+        nwkrq: bool = [6; 1],
+        globalerr: bool = [7; 1],
+        candom: bool = [8; 1],
+        canslnt: bool = [10; 1],
+        frame_ovf: bool = [12; 1],
+        wkerr: bool = [13; 1],
+        lwu: bool = [14; 1],
+        canint: bool = [15; 1],
+        eccerr: bool = [16; 1],
+        wdto: bool = [18; 1],
+        tsd: bool = [19; 1],
+        pwron: bool = [20; 1],
+        uvio: bool = [21; 1],
+        uvsup: bool = [22; 1],
+        canbusbat: bool = [24; 1],
+        canbusgnd: bool = [25; 1],
+        canbusopen: bool = [26; 1],
+        canlgnd: bool = [27; 1],
+    }
+}
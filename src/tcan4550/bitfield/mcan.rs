@@ -0,0 +1,282 @@
+use crate::tcan4550::register::*;
+
+register! {
+    /// CC control. INIT, CCE, CSR and CSA follow the protection rules of `lock_mcan_cccr`.
+    McanCccr @ REG_MCAN_CCCR => {
+        init: bool = [0; 1],
+        cce: bool = [1; 1],
+        asm: bool = [2; 1],
+        csa: bool = [3; 1],
+        csr: bool = [4; 1],
+        mon: bool = [5; 1],
+        /// Disable automatic retransmission
+        dar: bool = [6; 1],
+        test: bool = [7; 1],
+        fdoe: bool = [8; 1],
+        brse: bool = [9; 1],
+        /// Disable protocol exception handling
+        pxhd: bool = [12; 1],
+        efbi: bool = [13; 1],
+        txp: bool = [14; 1],
+        niso: bool = [15; 1],
+    }
+}
+
+register! {
+    /// Nominal bit timing, every field holds its value minus one
+    McanNbtp @ REG_MCAN_NBTP => {
+        ntseg2: u8 = [0; 7],
+        ntseg1: u8 = [8; 8],
+        nbrp: u16 = [16; 9],
+        nsjw: u8 = [25; 7],
+    }
+}
+
+register! {
+    /// Data bit timing, every field except TDC holds its value minus one
+    McanDbtp @ REG_MCAN_DBTP => {
+        dsjw: u8 = [0; 4],
+        dtseg2: u8 = [4; 4],
+        dtseg1: u8 = [8; 5],
+        dbrp: u8 = [16; 5],
+        tdc: bool = [23; 1],
+    }
+}
+
+register! {
+    /// Transmitter delay compensation in mtq
+    McanTdcr @ REG_MCAN_TDCR => {
+        tdcf: u8 = [0; 7],
+        tdco: u8 = [8; 7],
+    }
+}
+
+register! {
+    /// Timestamp counter configuration
+    McanTscc @ REG_MCAN_TSCC => {
+        /// 0: always 0, 1: TCP prescaled bit times, 2: external counter
+        tss: u8 = [0; 2],
+        /// Prescaler minus one
+        tcp: u8 = [16; 4],
+    }
+}
+
+register! {
+    /// M_CAN interrupt flags (write 1 to clear)
+    McanIr @ REG_MCAN_IR => {
+        rf0n: bool = [0; 1],
+        rf0w: bool = [1; 1],
+        rf0f: bool = [2; 1],
+        rf0l: bool = [3; 1],
+        rf1n: bool = [4; 1],
+        rf1w: bool = [5; 1],
+        rf1f: bool = [6; 1],
+        rf1l: bool = [7; 1],
+        hpm: bool = [8; 1],
+        tc: bool = [9; 1],
+        tcf: bool = [10; 1],
+        tfe: bool = [11; 1],
+        tefn: bool = [12; 1],
+        tefw: bool = [13; 1],
+        teff: bool = [14; 1],
+        tefl: bool = [15; 1],
+        tsw: bool = [16; 1],
+        mraf: bool = [17; 1],
+        too: bool = [18; 1],
+        drx: bool = [19; 1],
+        bec: bool = [20; 1],
+        beu: bool = [21; 1],
+        elo: bool = [22; 1],
+        ep: bool = [23; 1],
+        ew: bool = [24; 1],
+        bo: bool = [25; 1],
+        wdi: bool = [26; 1],
+        pea: bool = [27; 1],
+        ped: bool = [28; 1],
+        ara: bool = [29; 1],
+    }
+}
+
+register! {
+    /// M_CAN interrupt enable
+    McanIe @ REG_MCAN_IE => {
+        rf0ne: bool = [0; 1],
+        rf0we: bool = [1; 1],
+        rf0fe: bool = [2; 1],
+        rf0le: bool = [3; 1],
+        rf1ne: bool = [4; 1],
+        rf1we: bool = [5; 1],
+        rf1fe: bool = [6; 1],
+        rf1le: bool = [7; 1],
+        hpme: bool = [8; 1],
+        tce: bool = [9; 1],
+        tcfe: bool = [10; 1],
+        tfee: bool = [11; 1],
+        tefne: bool = [12; 1],
+        tefwe: bool = [13; 1],
+        teffe: bool = [14; 1],
+        tefle: bool = [15; 1],
+        tswe: bool = [16; 1],
+        mrafe: bool = [17; 1],
+        tooe: bool = [18; 1],
+        drxe: bool = [19; 1],
+        bece: bool = [20; 1],
+        beue: bool = [21; 1],
+        eloe: bool = [22; 1],
+        epe: bool = [23; 1],
+        ewe: bool = [24; 1],
+        boe: bool = [25; 1],
+        wdie: bool = [26; 1],
+        peae: bool = [27; 1],
+        pede: bool = [28; 1],
+        arae: bool = [29; 1],
+    }
+}
+
+register! {
+    /// M_CAN interrupt line select, false: MCAN_INT0, true: MCAN_INT1
+    McanIls @ REG_MCAN_ILS => {
+        rf0nl: bool = [0; 1],
+        rf0wl: bool = [1; 1],
+        rf0fl: bool = [2; 1],
+        rf0ll: bool = [3; 1],
+        rf1nl: bool = [4; 1],
+        rf1wl: bool = [5; 1],
+        rf1fl: bool = [6; 1],
+        rf1ll: bool = [7; 1],
+        hpml: bool = [8; 1],
+        tcl: bool = [9; 1],
+        tcfl: bool = [10; 1],
+        tfel: bool = [11; 1],
+        tefnl: bool = [12; 1],
+        tefwl: bool = [13; 1],
+        teffl: bool = [14; 1],
+        tefll: bool = [15; 1],
+        tswl: bool = [16; 1],
+        mrafl: bool = [17; 1],
+        tool: bool = [18; 1],
+        drxl: bool = [19; 1],
+        becl: bool = [20; 1],
+        beul: bool = [21; 1],
+        elol: bool = [22; 1],
+        epl: bool = [23; 1],
+        ewl: bool = [24; 1],
+        bol: bool = [25; 1],
+        wdil: bool = [26; 1],
+        peal: bool = [27; 1],
+        pedl: bool = [28; 1],
+        aral: bool = [29; 1],
+    }
+}
+
+register! {
+    /// Global filter configuration
+    McanGfc @ REG_MCAN_GFC => {
+        rrfe: bool = [0; 1],
+        rrfs: bool = [1; 1],
+        /// Non-matching extended frames, 0: FIFO0, 1: FIFO1, 2 and 3: reject
+        anfe: u8 = [2; 2],
+        /// Non-matching standard frames, 0: FIFO0, 1: FIFO1, 2 and 3: reject
+        anfs: u8 = [4; 2],
+    }
+}
+
+register! {
+    /// RX FIFO 0 configuration
+    McanRxf0c @ REG_MCAN_RXF0C => {
+        /// Start address as offset into the MRAM
+        f0sa: u16 = [0; 16],
+        f0s: u8 = [16; 7],
+        f0wm: u8 = [24; 7],
+        /// Overwrite the oldest element when full
+        f0om: bool = [31; 1],
+    }
+}
+
+register! {
+    /// RX FIFO 0 status
+    McanRxf0s @ REG_MCAN_RXF0S => {
+        f0fl: u8 = [0; 7],
+        f0gi: u8 = [8; 6],
+        f0pi: u8 = [16; 6],
+        f0f: bool = [24; 1],
+        rf0l: bool = [25; 1],
+    }
+}
+
+register! {
+    /// RX FIFO 1 configuration
+    McanRxf1c @ REG_MCAN_RXF1C => {
+        /// Start address as offset into the MRAM
+        f1sa: u16 = [0; 16],
+        f1s: u8 = [16; 7],
+        f1wm: u8 = [24; 7],
+        /// Overwrite the oldest element when full
+        f1om: bool = [31; 1],
+    }
+}
+
+register! {
+    /// RX FIFO 1 status
+    McanRxf1s @ REG_MCAN_RXF1S => {
+        f1fl: u8 = [0; 7],
+        f1gi: u8 = [8; 6],
+        f1pi: u8 = [16; 6],
+        f1f: bool = [24; 1],
+        rf1l: bool = [25; 1],
+        /// Debug message status
+        dms: u8 = [30; 2],
+    }
+}
+
+register! {
+    /// TX buffer configuration
+    McanTxbc @ REG_MCAN_TXBC => {
+        /// Start address as offset into the MRAM
+        tbsa: u16 = [0; 16],
+        /// Number of dedicated TX buffers
+        ndtb: u8 = [16; 6],
+        /// Number of TX FIFO/queue elements
+        tfqs: u8 = [24; 6],
+        /// false: FIFO, true: queue
+        tfqm: bool = [30; 1],
+    }
+}
+
+register! {
+    /// TX FIFO/queue status
+    McanTxfqs @ REG_MCAN_TXFQS => {
+        tffl: u8 = [0; 6],
+        tfgi: u8 = [8; 5],
+        tfqpi: u8 = [16; 5],
+        tfqf: bool = [21; 1],
+    }
+}
+
+register! {
+    /// Error counters. Reading clears CEL.
+    McanEcr @ REG_MCAN_ECR => {
+        tec: u8 = [0; 8],
+        rec: u8 = [8; 7],
+        rp: bool = [15; 1],
+        cel: u8 = [16; 8],
+    }
+}
+
+register! {
+    /// Protocol status. Reading sets LEC and DLEC to 7 and clears PXE.
+    McanPsr @ REG_MCAN_PSR => {
+        lec: u8 = [0; 3],
+        /// 0: synchronizing, 1: idle, 2: receiver, 3: transmitter
+        act: u8 = [3; 2],
+        ep: bool = [5; 1],
+        ew: bool = [6; 1],
+        bo: bool = [7; 1],
+        dlec: u8 = [8; 3],
+        resi: bool = [11; 1],
+        rbrs: bool = [12; 1],
+        rfdf: bool = [13; 1],
+        pxe: bool = [14; 1],
+        tdcv: u8 = [16; 7],
+    }
+}
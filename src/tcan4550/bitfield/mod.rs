@@ -0,0 +1,176 @@
+/// Register with named bit fields
+pub trait Register: Sized {
+    const ADDR: u16;
+    /// Bits covered by a field
+    const FIELD_MASK: u32;
+    fn from_u32(value: u32) -> Self;
+    fn to_u32(&self) -> u32;
+}
+
+/// Field types a register word is split into
+pub trait FieldValue: Sized {
+    fn from_bits(bits: u32) -> Self;
+    fn to_bits(&self) -> u32;
+}
+
+impl FieldValue for bool {
+    fn from_bits(bits: u32) -> Self { bits != 0 }
+    fn to_bits(&self) -> u32 { *self as u32 }
+}
+
+impl FieldValue for u8 {
+    fn from_bits(bits: u32) -> Self { bits as u8 }
+    fn to_bits(&self) -> u32 { *self as u32 }
+}
+
+impl FieldValue for u16 {
+    fn from_bits(bits: u32) -> Self { bits as u16 }
+    fn to_bits(&self) -> u32 { *self as u32 }
+}
+
+impl FieldValue for u32 {
+    fn from_bits(bits: u32) -> Self { bits }
+    fn to_bits(&self) -> u32 { *self }
+}
+
+pub(crate) const fn field_mask(lsb: u32, width: u32) -> u32 {
+    let mask: u32 = if width >= 32 { u32::MAX } else { (1 << width) - 1 };
+    mask << lsb
+}
+
+/// Declare a register struct. Each field is given as `name: type = [lsb; width]`.
+/// Bits outside every field are kept in `reserved`, so a read-modify-write leaves them untouched.
+macro_rules! register {
+    (
+        $(#[$meta:meta])*
+        $name:ident @ $addr:expr => {
+            $( $(#[$fmeta:meta])* $field:ident: $ty:ty = [$lsb:literal; $width:literal], )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+        pub struct $name {
+            $( $(#[$fmeta])* pub $field: $ty, )*
+            /// Bits not covered by a field, written back unchanged
+            pub reserved: u32,
+        }
+
+        impl $name {
+            /// Bits covered by a field
+            pub const FIELD_MASK: u32 = 0 $( | $crate::tcan4550::bitfield::field_mask($lsb, $width) )*;
+
+            pub fn from_u32(value: u32) -> Self {
+                use $crate::tcan4550::bitfield::{field_mask, FieldValue};
+                Self {
+                    $( $field: <$ty as FieldValue>::from_bits((value >> $lsb) & field_mask(0, $width)), )*
+                    reserved: value & !Self::FIELD_MASK,
+                }
+            }
+
+            pub fn to_u32(&self) -> u32 {
+                use $crate::tcan4550::bitfield::{field_mask, FieldValue};
+                (self.reserved & !Self::FIELD_MASK)
+                    $( | ((self.$field.to_bits() & field_mask(0, $width)) << $lsb) )*
+            }
+        }
+
+        impl $crate::tcan4550::bitfield::Register for $name {
+            const ADDR: u16 = $addr;
+            const FIELD_MASK: u32 = $name::FIELD_MASK;
+
+            fn from_u32(value: u32) -> Self {
+                $name::from_u32(value)
+            }
+
+            fn to_u32(&self) -> u32 {
+                $name::to_u32(self)
+            }
+        }
+    };
+}
+
+mod spi;
+mod device;
+mod mcan;
+
+pub use spi::SpiStatus;
+pub use device::{DevModesAndPins, DevIr};
+pub use mcan::{
+    McanCccr, McanNbtp, McanDbtp, McanTdcr, McanTscc, McanIr, McanIe, McanIls, McanGfc,
+    McanRxf0c, McanRxf0s, McanRxf1c, McanRxf1s, McanTxbc, McanTxfqs, McanEcr, McanPsr,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tcan4550::register::*;
+
+    const PATTERNS: [u32; 5] = [0, u32::MAX, 0xA5A5A5A5, 0x5A5A5A5A, 0x12345678];
+
+    // Decoding and encoding must give back the fields, and bits outside them only through `reserved`
+    macro_rules! assert_round_trip {
+        ($($name:ident),* $(,)?) => {
+            $(
+                for value in PATTERNS {
+                    let register: $name = $name::from_u32(value);
+                    assert_eq!(register.to_u32(), value, "{} {:#010x}", stringify!($name), value);
+                    assert_eq!(register.reserved, value & !$name::FIELD_MASK, "{} {:#010x}", stringify!($name), value);
+                    let fields: $name = $name { reserved: 0, ..register };
+                    assert_eq!(fields.to_u32(), value & $name::FIELD_MASK, "{} {:#010x}", stringify!($name), value);
+                }
+                assert_eq!(<$name as Register>::FIELD_MASK, $name::FIELD_MASK);
+            )*
+        };
+    }
+
+    #[test]
+    fn every_register_round_trips() {
+        assert_round_trip!(
+            SpiStatus, DevModesAndPins, DevIr,
+            McanCccr, McanNbtp, McanDbtp, McanTdcr, McanTscc, McanIr, McanIe, McanIls, McanGfc,
+            McanRxf0c, McanRxf0s, McanRxf1c, McanRxf1s, McanTxbc, McanTxfqs, McanEcr, McanPsr,
+        );
+    }
+
+    #[test]
+    fn field_mask_covers_the_declared_bits() {
+        assert_eq!(field_mask(0, 1), 0x1);
+        assert_eq!(field_mask(25, 7), 0xFE000000);
+        assert_eq!(field_mask(0, 32), u32::MAX);
+        assert_eq!(McanNbtp::FIELD_MASK, !0x80);
+        assert_eq!(McanTdcr::FIELD_MASK, 0x7F7F);
+    }
+
+    #[test]
+    fn cccr_flags_match_the_register_bits() {
+        let bit = |cccr: McanCccr| cccr.to_u32();
+        assert_eq!(bit(McanCccr { init: true, ..McanCccr::default() }), REG_BITS_MCAN_CCCR_INIT);
+        assert_eq!(bit(McanCccr { cce: true, ..McanCccr::default() }), REG_BITS_MCAN_CCCR_CCE);
+        assert_eq!(bit(McanCccr { csa: true, ..McanCccr::default() }), REG_BITS_MCAN_CCCR_CSA);
+        assert_eq!(bit(McanCccr { csr: true, ..McanCccr::default() }), REG_BITS_MCAN_CCCR_CSR);
+        assert_eq!(bit(McanCccr { fdoe: true, ..McanCccr::default() }), REG_BITS_MCAN_CCCR_FDOE);
+
+        let cccr: McanCccr = McanCccr::from_u32(REG_BITS_MCAN_CCCR_CSR | REG_BITS_MCAN_CCCR_INIT);
+        assert!(cccr.csr && cccr.init && !cccr.csa && !cccr.cce);
+    }
+
+    #[test]
+    fn nbtp_fields_are_placed_at_their_offsets() {
+        assert_eq!(McanNbtp { nsjw: 1, ..McanNbtp::default() }.to_u32(), 1 << 25);
+        assert_eq!(McanNbtp { nbrp: 1, ..McanNbtp::default() }.to_u32(), 1 << 16);
+        assert_eq!(McanNbtp { ntseg1: 1, ..McanNbtp::default() }.to_u32(), 1 << 8);
+        assert_eq!(McanNbtp { ntseg2: 1, ..McanNbtp::default() }.to_u32(), 1);
+        assert_eq!(McanNbtp::from_u32(0xFE000000).nsjw, 0x7F);
+    }
+
+    #[test]
+    fn device_mode_matches_the_register_bits() {
+        let mode = |mode_sel: u8| DevModesAndPins { mode_sel, ..DevModesAndPins::default() }.to_u32();
+        assert_eq!(mode(DevModesAndPins::MODE_SLEEP), REG_BITS_DEVICE_MODE_DEVICEMODE_SLEEP);
+        assert_eq!(mode(DevModesAndPins::MODE_STANDBY), REG_BITS_DEVICE_MODE_DEVICEMODE_STANDBY);
+        assert_eq!(mode(DevModesAndPins::MODE_NORMAL), REG_BITS_DEVICE_MODE_DEVICEMODE_NORMAL);
+        assert_eq!(mode(0xFF), REG_BITS_DEVICE_MODE_DEVICEMODE_MASK);
+        assert!(DevModesAndPins::from_u32(REG_BITS_DEVICE_MODE_WD_CLK_40MHZ).clk_ref);
+        assert!(DevIr::from_u32(REG_BITS_DEVICE_IR_M_CAN_INT).m_can_int);
+    }
+}
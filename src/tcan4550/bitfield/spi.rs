@@ -0,0 +1,25 @@
+use crate::tcan4550::register::*;
+
+register! {
+    /// SPI_STATUS (write 1 to clear)
+    SpiStatus @ REG_SPI_STATUS => {
+        interrupt: bool = [0; 1],
+        spi_error_interrupt: bool = [1; 1],
+        internal_error_interrupt: bool = [2; 1],
+        internal_access_active: bool = [3; 1],
+        read_fifo_available: bool = [4; 1],
+        write_fifo_available: bool = [5; 1],
+        read_underflow: bool = [16; 1],
+        read_overflow: bool = [17; 1],
+        write_underflow: bool = [18; 1],
+        write_overflow: bool = [19; 1],
+        invalid_command: bool = [20; 1],
+        spi_end_error: bool = [21; 1],
+        write_fifo_overflow: bool = [24; 1],
+        read_fifo_empty: bool = [25; 1],
+        read_fifo_underflow: bool = [26; 1],
+        internal_error_log_write: bool = [27; 1],
+        internal_write_error: bool = [28; 1],
+        internal_read_error: bool = [29; 1],
+    }
+}
@@ -0,0 +1,334 @@
+use crate::error::{CandsError, CandsResult};
+use crate::tcan4550::bitfield::{McanDbtp, McanNbtp, McanTdcr};
+
+
+/// Reference clock of the TCAN4550 (CLK_REF in the modes and pins register)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClockRef {
+    Mhz20,
+    Mhz40,
+}
+
+impl ClockRef {
+    pub fn hz(&self) -> u32 {
+        match self {
+            ClockRef::Mhz20 => 20_000_000,
+            ClockRef::Mhz40 => 40_000_000,
+        }
+    }
+}
+
+// Nominal phase limits in time quanta (NBTP)
+const NBRP_MAX: u32 = 512;
+const NTSEG1_MIN: u32 = 2;
+const NTSEG1_MAX: u32 = 256;
+const NTSEG2_MIN: u32 = 1;
+const NTSEG2_MAX: u32 = 128;
+const NSJW_MAX: u32 = 128;
+
+// Data phase limits in time quanta (DBTP)
+const DBRP_MAX: u32 = 32;
+const DTSEG1_MIN: u32 = 1;
+const DTSEG1_MAX: u32 = 32;
+const DTSEG2_MIN: u32 = 1;
+const DTSEG2_MAX: u32 = 16;
+const DSJW_MAX: u32 = 16;
+
+// Transmitter delay compensation limits in mtq (TDCR)
+const TDCO_MAX: u32 = 127;
+const TDCF_MAX: u32 = 127;
+
+// Transmitter delay compensation only works with a data phase prescaler of 1 or 2
+const TDC_DBRP_MAX: u32 = 2;
+
+/// Nominal and data phase bit timing
+///
+/// Prescalers and segments are given in time quanta, not in register encoding.
+/// A bit lasts 1 + tseg1 + tseg2 time quanta.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BitTiming {
+    pub nbrp: u32,
+    pub ntseg1: u32,
+    pub ntseg2: u32,
+    pub nsjw: u32,
+    pub dbrp: u32,
+    pub dtseg1: u32,
+    pub dtseg2: u32,
+    pub dsjw: u32,
+    /// Transmitter delay compensation enable
+    pub tdc: bool,
+    /// Transmitter delay compensation offset in mtq
+    pub tdco: u32,
+    /// Transmitter delay compensation filter window length in mtq
+    pub tdcf: u32,
+    /// Reference clock the timing is derived for, `setup` fails if CLK_REF selects another one
+    pub clock: ClockRef,
+}
+
+impl Default for BitTiming {
+    /// 500 kbit/s nominal and 2 Mbit/s data at 40 MHz.
+    ///
+    /// NSJW keeps the 31 tq of the original NBTP value, above NTSEG2, so a timing built
+    /// from the default with struct update syntax needs its own `nsjw` to pass `validate`.
+    fn default() -> Self {
+        Self {
+            nbrp: 2,
+            ntseg1: 31,
+            ntseg2: 8,
+            nsjw: 31,
+            dbrp: 2,
+            dtseg1: 5,
+            dtseg2: 4,
+            dsjw: 4,
+            tdc: true,
+            tdco: 3,
+            tdcf: 0,
+            clock: ClockRef::Mhz40,
+        }
+    }
+}
+
+fn no_solution(phase: &str, bitrate: u32, clock: ClockRef) -> CandsError {
+    let msg: String = format!("No exact {} bit timing for {} bit/s at {} Hz", phase, bitrate, clock.hz());
+    CandsError::InvalidConfig(msg)
+}
+
+/// Find the smallest prescaler giving an integer number of time quanta per bit within the segment limits.
+/// Returns (brp, tseg1, tseg2).
+fn solve_phase(
+    clk: u32, bitrate: u32, sample_point: u32,
+    brp_max: u32, tseg1_range: (u32, u32), tseg2_range: (u32, u32),
+) -> Option<(u32, u32, u32)> {
+    if bitrate == 0 {
+        return None;
+    }
+
+    for brp in 1..=brp_max {
+        if !clk.is_multiple_of(brp * bitrate) {
+            continue;
+        }
+        let tq: u32 = clk / (brp * bitrate);
+        if tq < 1 + tseg1_range.0 + tseg2_range.0 || tq > 1 + tseg1_range.1 + tseg2_range.1 {
+            continue;
+        }
+
+        // Sample point position in time quanta counted from the start of the bit, rounded to nearest
+        let sp_tq: u32 = (tq * sample_point + 500) / 1000;
+        let tseg1: u32 = sp_tq.saturating_sub(1).clamp(tseg1_range.0, tseg1_range.1);
+        let tseg2: u32 = tq - 1 - tseg1;
+        if tseg2 < tseg2_range.0 || tseg2 > tseg2_range.1 {
+            continue;
+        }
+        return Some((brp, tseg1, tseg2));
+    }
+    None
+}
+
+impl BitTiming {
+
+    /// Solve the nominal and data phase timing for the given clock.
+    ///
+    /// `sample_point` is in permille (e.g. 800 for 80 %) and applies to both phases.
+    /// `sjw` is in time quanta and is limited to the phase segment 2 of each phase.
+    pub fn calculate(clock: ClockRef, nominal_bitrate: u32, data_bitrate: u32, sample_point: u32, sjw: u32) -> CandsResult<Self> {
+        if sample_point == 0 || sample_point >= 1000 {
+            return Err(CandsError::InvalidConfig("Sample point must be within 1..999 permille".to_string()));
+        }
+        if sjw == 0 {
+            return Err(CandsError::InvalidConfig("SJW must be at least 1 tq".to_string()));
+        }
+        if data_bitrate < nominal_bitrate {
+            return Err(CandsError::InvalidConfig("Data bit rate must not be lower than the nominal bit rate".to_string()));
+        }
+
+        let clk: u32 = clock.hz();
+
+        let (nbrp, ntseg1, ntseg2) = solve_phase(
+            clk, nominal_bitrate, sample_point, NBRP_MAX, (NTSEG1_MIN, NTSEG1_MAX), (NTSEG2_MIN, NTSEG2_MAX)
+        ).ok_or_else(|| no_solution("nominal", nominal_bitrate, clock))?;
+
+        let (dbrp, dtseg1, dtseg2) = solve_phase(
+            clk, data_bitrate, sample_point, DBRP_MAX, (DTSEG1_MIN, DTSEG1_MAX), (DTSEG2_MIN, DTSEG2_MAX)
+        ).ok_or_else(|| no_solution("data", data_bitrate, clock))?;
+
+        // The secondary sample point sits at the data phase sample point
+        let tdco: u32 = dbrp * (1 + dtseg1);
+        let tdc: bool = dbrp <= TDC_DBRP_MAX && tdco <= TDCO_MAX;
+
+        let timing: Self = Self {
+            nbrp,
+            ntseg1,
+            ntseg2,
+            nsjw: sjw.min(ntseg2).min(NSJW_MAX),
+            dbrp,
+            dtseg1,
+            dtseg2,
+            dsjw: sjw.min(dtseg2).min(DSJW_MAX),
+            tdc,
+            tdco: if tdc { tdco } else { 0 },
+            tdcf: 0,
+            clock,
+        };
+        timing.validate()?;
+        Ok(timing)
+    }
+
+    /// Decode NBTP, DBTP and TDCR register values of a device running from `clock`
+    pub fn from_registers(clock: ClockRef, nbtp: u32, dbtp: u32, tdcr: u32) -> Self {
+        let nbtp: McanNbtp = McanNbtp::from_u32(nbtp);
+        let dbtp: McanDbtp = McanDbtp::from_u32(dbtp);
+        let tdcr: McanTdcr = McanTdcr::from_u32(tdcr);
+        Self {
+            nbrp: nbtp.nbrp as u32 + 1,
+            ntseg1: nbtp.ntseg1 as u32 + 1,
+            ntseg2: nbtp.ntseg2 as u32 + 1,
+            nsjw: nbtp.nsjw as u32 + 1,
+            dbrp: dbtp.dbrp as u32 + 1,
+            dtseg1: dbtp.dtseg1 as u32 + 1,
+            dtseg2: dbtp.dtseg2 as u32 + 1,
+            dsjw: dbtp.dsjw as u32 + 1,
+            tdc: dbtp.tdc,
+            tdco: tdcr.tdco as u32,
+            tdcf: tdcr.tdcf as u32,
+            clock,
+        }
+    }
+
+    /// Check every field against the width of its register field, SJW against phase segment 2
+    /// and transmitter delay compensation against the data phase prescaler
+    pub fn validate(&self) -> CandsResult<()> {
+        self.validate_fields()?;
+        if self.nsjw > self.ntseg2 || self.dsjw > self.dtseg2 {
+            return Err(CandsError::InvalidConfig("SJW must not exceed phase segment 2".to_string()));
+        }
+        Ok(())
+    }
+
+    /// `validate` without the SJW check, which the default timing does not pass
+    pub(crate) fn validate_fields(&self) -> CandsResult<()> {
+        let in_range = |val: u32, min: u32, max: u32| val >= min && val <= max;
+
+        let valid: bool = in_range(self.nbrp, 1, NBRP_MAX)
+            && in_range(self.ntseg1, NTSEG1_MIN, NTSEG1_MAX)
+            && in_range(self.ntseg2, NTSEG2_MIN, NTSEG2_MAX)
+            && in_range(self.nsjw, 1, NSJW_MAX)
+            && in_range(self.dbrp, 1, DBRP_MAX)
+            && in_range(self.dtseg1, DTSEG1_MIN, DTSEG1_MAX)
+            && in_range(self.dtseg2, DTSEG2_MIN, DTSEG2_MAX)
+            && in_range(self.dsjw, 1, DSJW_MAX)
+            && self.tdco <= TDCO_MAX
+            && self.tdcf <= TDCF_MAX;
+
+        if !valid {
+            return Err(CandsError::InvalidConfig("Bit timing field out of range".to_string()));
+        }
+        if self.tdc && self.dbrp > TDC_DBRP_MAX {
+            return Err(CandsError::InvalidConfig("Transmitter delay compensation requires a data prescaler of 1 or 2".to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn nominal_bitrate(&self, clock: ClockRef) -> u32 {
+        clock.hz() / (self.nbrp * (1 + self.ntseg1 + self.ntseg2))
+    }
+
+    pub fn data_bitrate(&self, clock: ClockRef) -> u32 {
+        clock.hz() / (self.dbrp * (1 + self.dtseg1 + self.dtseg2))
+    }
+
+    /// NBTP register
+    pub fn nbtp(&self) -> McanNbtp {
+        McanNbtp {
+            nsjw: (self.nsjw - 1) as u8,
+            nbrp: (self.nbrp - 1) as u16,
+            ntseg1: (self.ntseg1 - 1) as u8,
+            ntseg2: (self.ntseg2 - 1) as u8,
+            ..McanNbtp::default()
+        }
+    }
+
+    /// DBTP register
+    pub fn dbtp(&self) -> McanDbtp {
+        McanDbtp {
+            tdc: self.tdc,
+            dbrp: (self.dbrp - 1) as u8,
+            dtseg1: (self.dtseg1 - 1) as u8,
+            dtseg2: (self.dtseg2 - 1) as u8,
+            dsjw: (self.dsjw - 1) as u8,
+            ..McanDbtp::default()
+        }
+    }
+
+    /// TDCR register
+    pub fn tdcr(&self) -> McanTdcr {
+        McanTdcr {
+            tdco: self.tdco as u8,
+            tdcf: self.tdcf as u8,
+            ..McanTdcr::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BitTiming, ClockRef};
+
+    fn sample_point(tseg1: u32, tseg2: u32) -> u32 {
+        1000 * (1 + tseg1) / (1 + tseg1 + tseg2)
+    }
+
+    #[test]
+    fn calculates_250k_nominal_and_1m_data_at_40mhz() {
+        let timing: BitTiming = BitTiming::calculate(ClockRef::Mhz40, 250_000, 1_000_000, 800, 4).unwrap();
+        assert_eq!(timing.nominal_bitrate(ClockRef::Mhz40), 250_000);
+        assert_eq!(timing.data_bitrate(ClockRef::Mhz40), 1_000_000);
+        assert_eq!((timing.nbrp, timing.ntseg1, timing.ntseg2, timing.nsjw), (1, 127, 32, 4));
+        assert_eq!((timing.dbrp, timing.dtseg1, timing.dtseg2, timing.dsjw), (1, 31, 8, 4));
+        assert_eq!(sample_point(timing.ntseg1, timing.ntseg2), 800);
+        assert!(timing.tdc);
+        assert_eq!(timing.tdco, 32);
+        assert_eq!(timing.clock, ClockRef::Mhz40);
+    }
+
+    #[test]
+    fn calculates_1m_nominal_and_5m_data_at_40mhz() {
+        let timing: BitTiming = BitTiming::calculate(ClockRef::Mhz40, 1_000_000, 5_000_000, 800, 4).unwrap();
+        assert_eq!(timing.nominal_bitrate(ClockRef::Mhz40), 1_000_000);
+        assert_eq!(timing.data_bitrate(ClockRef::Mhz40), 5_000_000);
+        assert_eq!((timing.nbrp, timing.ntseg1, timing.ntseg2, timing.nsjw), (1, 31, 8, 4));
+        // SJW is limited to phase segment 2 of the data phase
+        assert_eq!((timing.dbrp, timing.dtseg1, timing.dtseg2, timing.dsjw), (1, 5, 2, 2));
+        assert!(timing.tdc);
+        assert_eq!(timing.tdco, 6);
+    }
+
+    #[test]
+    fn rejects_bit_rates_without_an_exact_timing() {
+        assert!(BitTiming::calculate(ClockRef::Mhz40, 300_000, 1_000_000, 800, 1).is_err());
+        assert!(BitTiming::calculate(ClockRef::Mhz40, 1_000_000, 500_000, 800, 1).is_err());
+        assert!(BitTiming::calculate(ClockRef::Mhz40, 500_000, 2_000_000, 1000, 1).is_err());
+    }
+
+    #[test]
+    fn rejects_sjw_above_phase_segment_2() {
+        let timing: BitTiming = BitTiming::calculate(ClockRef::Mhz40, 500_000, 2_000_000, 800, 1).unwrap();
+        assert!(BitTiming { nsjw: timing.ntseg2, ..timing }.validate().is_ok());
+        assert!(BitTiming { nsjw: timing.ntseg2 + 1, ..timing }.validate().is_err());
+        assert!(BitTiming { dsjw: timing.dtseg2 + 1, ..timing }.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_tdc_with_a_data_prescaler_above_2() {
+        let timing: BitTiming = BitTiming::calculate(ClockRef::Mhz40, 500_000, 2_000_000, 800, 1).unwrap();
+        assert!(BitTiming { dbrp: 2, tdc: true, ..timing }.validate().is_ok());
+        assert!(BitTiming { dbrp: 3, tdc: true, ..timing }.validate().is_err());
+        assert!(BitTiming { dbrp: 3, tdc: false, tdco: 0, ..timing }.validate().is_ok());
+    }
+
+    #[test]
+    fn default_keeps_the_original_nbtp_value() {
+        let timing: BitTiming = BitTiming::default();
+        assert_eq!(timing.nbtp().to_u32(), (30 << 25) | (1 << 16) | (30 << 8) | 7);
+        assert!(timing.validate_fields().is_ok());
+    }
+}
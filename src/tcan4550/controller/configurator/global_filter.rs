@@ -0,0 +1,133 @@
+use crate::error::{CandsError, CandsResult};
+use crate::tcan4550::{bitfield::McanGfc, register::*};
+
+const CAN_XID_MAX: u32 = 0x1FFFFFFF;
+
+/// Destination of frames that match no ID filter (GFC.ANFS/ANFE)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NonMatchingFrames {
+    Fifo0,
+    Fifo1,
+    Reject,
+}
+
+impl NonMatchingFrames {
+    pub fn code(&self) -> u32 {
+        match self {
+            NonMatchingFrames::Fifo0 => 0,
+            NonMatchingFrames::Fifo1 => 1,
+            NonMatchingFrames::Reject => 2,
+        }
+    }
+}
+
+/// Global filter configuration (GFC) and extended ID AND mask (XIDAM)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GlobalFilterConfig {
+    /// Non-matching standard ID frames
+    pub anfs: NonMatchingFrames,
+    /// Non-matching extended ID frames
+    pub anfe: NonMatchingFrames,
+    /// Reject all remote frames with standard ID
+    pub rrfs: bool,
+    /// Reject all remote frames with extended ID
+    pub rrfe: bool,
+    /// ANDed with extended IDs before range filters are applied
+    pub xidam: u32,
+}
+
+impl Default for GlobalFilterConfig {
+    fn default() -> Self {
+        Self {
+            anfs: NonMatchingFrames::Fifo1,
+            anfe: NonMatchingFrames::Fifo1,
+            rrfs: false,
+            rrfe: false,
+            xidam: CAN_XID_MAX,
+        }
+    }
+}
+
+impl GlobalFilterConfig {
+    /// Reject every frame that matches no filter
+    pub fn reject_non_matching() -> Self {
+        Self { anfs: NonMatchingFrames::Reject, anfe: NonMatchingFrames::Reject, ..Self::default() }
+    }
+
+    pub fn validate(&self) -> CandsResult<()> {
+        if self.xidam > CAN_XID_MAX {
+            return Err(CandsError::InvalidConfig("XIDAM exceeds 29 bits".to_string()));
+        }
+        Ok(())
+    }
+
+    /// GFC register
+    pub fn gfc(&self) -> McanGfc {
+        McanGfc {
+            anfs: self.anfs.code() as u8,
+            anfe: self.anfe.code() as u8,
+            rrfs: self.rrfs,
+            rrfe: self.rrfe,
+            ..McanGfc::default()
+        }
+    }
+}
+
+impl super::super::TCAN455xController {
+    pub fn set_xidam(config: &GlobalFilterConfig) -> Vec<u8> {
+        Self::generate_write_command(REG_MCAN_XIDAM, vec![config.xidam & CAN_XID_MAX])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GlobalFilterConfig, NonMatchingFrames};
+    use crate::error::CandsError;
+    use crate::tcan4550::controller::TCAN455xController;
+    use crate::tcan4550::register::*;
+
+    #[test]
+    fn default_gfc_stores_non_matching_frames_in_fifo1() {
+        let gfc: u32 = GlobalFilterConfig::default().gfc().to_u32();
+        assert_eq!(gfc, REG_BITS_MCAN_GFC_ANFS_FIFO1 | REG_BITS_MCAN_GFC_ANFE_FIFO1);
+    }
+
+    #[test]
+    fn gfc_fields_are_placed_in_their_bits() {
+        let config: GlobalFilterConfig = GlobalFilterConfig {
+            anfs: NonMatchingFrames::Reject,
+            anfe: NonMatchingFrames::Fifo0,
+            rrfs: true,
+            rrfe: false,
+            ..GlobalFilterConfig::default()
+        };
+        assert_eq!(config.gfc().to_u32(), (2 << 4) | REG_BITS_MCAN_GFC_ANFE_FIFO0 | REG_BITS_MCAN_GFC_RRFS);
+
+        let config: GlobalFilterConfig = GlobalFilterConfig {
+            anfs: NonMatchingFrames::Fifo0,
+            anfe: NonMatchingFrames::Reject,
+            rrfs: false,
+            rrfe: true,
+            ..GlobalFilterConfig::default()
+        };
+        assert_eq!(config.gfc().to_u32(), REG_BITS_MCAN_GFC_ANFS_FIFO0 | (2 << 2) | REG_BITS_MCAN_GFC_RRFE);
+        assert_eq!(GlobalFilterConfig::reject_non_matching().gfc().to_u32() & !REG_BITS_MCAN_GFC_MASK, 0);
+    }
+
+    #[test]
+    fn xidam_is_masked_to_29_bits() {
+        let config: GlobalFilterConfig = GlobalFilterConfig { xidam: 0xFFFFFFFF, ..GlobalFilterConfig::default() };
+        assert!(matches!(config.validate(), Err(CandsError::InvalidConfig(_))));
+        assert_eq!(
+            TCAN455xController::set_xidam(&config),
+            TCAN455xController::generate_write_command(REG_MCAN_XIDAM, vec![0x1FFFFFFF])
+        );
+
+        let config: GlobalFilterConfig = GlobalFilterConfig { xidam: 0x1FFFFF00, ..GlobalFilterConfig::default() };
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            TCAN455xController::set_xidam(&config),
+            TCAN455xController::generate_write_command(REG_MCAN_XIDAM, vec![0x1FFFFF00])
+        );
+    }
+}
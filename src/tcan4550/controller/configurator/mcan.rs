@@ -16,29 +16,6 @@ const ASM: u32 = 0;    // Restricted Operation Mode, 0: Normal CAN Operation, 1:
 const CCE: u32 = 0;    // Configure change enable
 const INIT: u32 = 0;   // Initialization, 0: Normal operation, 1: Initilization started
 
-// Nominal timing: BitRate = FCLK / NBPRS / (NTSEG1 + NTSEG2 + 1)
-const NBPRS: u32 = 2;
-const NTSEG1: u32 = 31;
-const NTSEG2: u32 = 8;
-
-// Data timing: BitRate = FCLK / DBRPRS / (DTSEG1 + DTSEG2 + 1)
-const DBRPRS: u32 = 2;
-const DTSEG1: u32 = 5;
-const DTSEG2: u32 = 4;
- 
-const NBTP_NBPRS: u32 = (NBPRS - 1) << 16;
-const NBTP_NTSEG1: u32 = (NTSEG1 - 1) << 8;
-const NBTP_NTSEG2: u32 = (NTSEG2 - 1) << 0;
-const NBTP_NSJW: u32 = (NTSEG1 - 1) << 25;
-
-const DBTP_TDC: u32 = REG_BITS_MCAN_DBTP_TDC_EN;
-const DBTP_DBRPRS: u32 = (DBRPRS - 1) << 16;
-const DBTP_DTSEG1: u32 = (DTSEG1 - 1) << 8;
-const DBTP_DTSEG2: u32 = (DTSEG2 - 1) << 4;
-const DBTP_DSJW: u32 = (DTSEG2 - 1) << 0;
-
-const TDCO: u32 = (DTSEG1 - 2) << 8;
-
 // Interrupt
 const MCANIRQ_ARAE: u32 = 0;  //IE[29] ARAE: Access to reserved address
 const MCANIRQ_PEDE: u32 = 0;  //IE[28] PEDE: Protocol error in data phase (data bit time is used)
@@ -75,10 +52,6 @@ const MCANIRQ_INT1_EN: u32 = 1;
 const MCANIRQ_INT0_EN: u32 = 1;
   
 impl super::super::TCAN455xController {
-    pub fn protect_register(data: u32) -> u32 {
-        data & !(REG_BITS_MCAN_CCCR_CSA | REG_BITS_MCAN_CCCR_CSR | REG_BITS_MCAN_CCCR_INIT | REG_BITS_MCAN_CCCR_CCE)
-    }
-
     pub fn unprotect_register(data: u32) -> u32 {
         data & !(REG_BITS_MCAN_CCCR_CSA | REG_BITS_MCAN_CCCR_CSR) | (REG_BITS_MCAN_CCCR_INIT | REG_BITS_MCAN_CCCR_CCE)
     }
@@ -103,31 +76,8 @@ impl super::super::TCAN455xController {
         Self::generate_write_command(addr, vec![data])
     }
 
-    pub fn set_dbtp() -> Vec<u8> {
-        let addr: u16 = REG_MCAN_DBTP;
-        let data: u32 = DBTP_TDC | DBTP_DSJW | DBTP_DBRPRS | DBTP_DTSEG1 |  DBTP_DTSEG2;
-        Self::generate_write_command(addr, vec![data])
-    }
-
-    pub fn set_nbtp() -> Vec<u8> {
-        let addr: u16 = REG_MCAN_NBTP;
-        let data: u32 = NBTP_NSJW | NBTP_NBPRS | NBTP_NTSEG1 |  NBTP_NTSEG2;
-        Self::generate_write_command(addr, vec![data])
-    }
-
-    pub fn set_tdcr() -> Vec<u8> {
-        let addr: u16 = REG_MCAN_TDCR;
-        let data: u32 = TDCO;
-        Self::generate_write_command(addr, vec![data])
-    }
-    
-    pub fn set_tscc() -> Vec<u8> {
-        let addr: u16 = REG_MCAN_TSCC;
-        let data: u32 = REG_BITS_MCAN_TSCC_COUNTER_EXTERNAL;
-        Self::generate_write_command(addr, vec![data])
-    }
-
-    pub fn set_mcan_ie() -> Vec<u8> {
+    /// `enable` adds interrupts on top of the constants above
+    pub fn set_mcan_ie(enable: u32) -> Vec<u8> {
 
         let addr: u16 = REG_MCAN_IE;
         let data: u32 = (MCANIRQ_ARAE << 29)
@@ -159,7 +109,8 @@ impl super::super::TCAN455xController {
             | (MCANIRQ_RF0LE << 3)
             | (MCANIRQ_RF0FE << 2)
             | (MCANIRQ_RF0WE << 1)
-            | (MCANIRQ_RF0NE << 0);
+            | (MCANIRQ_RF0NE << 0)
+            | enable;
 
         Self::generate_write_command(addr, vec![data])
         
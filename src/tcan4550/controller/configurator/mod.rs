@@ -1,3 +1,5 @@
 pub mod modes_and_pins;
 pub mod mcan;
-pub mod mram;
\ No newline at end of file
+pub mod mram;
+pub mod bit_timing;
+pub mod global_filter;pub mod timestamp;
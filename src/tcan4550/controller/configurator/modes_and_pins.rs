@@ -1,54 +1,195 @@
-use crate::tcan4550::register::*;
-
-const WAKE_CONFIG: u32 = 3;      // Wake pin can be triggered by either edge (default)
-const WD_TIMER: u32 = 0;         // Watchdog timer, 0: 60 ms, 1: 600 ms, 2: 3 s, 3: 6s
-const CLK_REF: u32 = 1;          // Input crystal, 1: 40 MHz crystal, 0: 20 MHz
-const GPO2_CONFIG: u32 = 0;      // GPO2, 0: No Action, 1: MCAN_INT 0 interrupt (Active low), 2: Watchdog output, 3: Mirrors nINT pin (Active low)
-const TESTMODE_EN: u32 = 0;      // Test mode
-const NWKRQ_VOLTAGE: u32 = 0;    // Set nWKRQ to 0: Internal voltage rail, 1: VIO voltage rail
-const WD_BIT_RESET: u32 = 0;     // Don't reset the watchdog
-const WD_ACTION: u32 = 0;        // Watchdog set an interrupt (default)
-const GPIO1_CONFIG: u32 = 0;     // GPIO set as 0: GPO (Default), 2: GPI
-const FAIL_SAFE_EN: u32 = 0;     // Failsafe disabled (default)
-const GPIO1_GPO_CONFIG: u32 = 1; // GPO1, 0: SPI fault Interrupt (Active low), 1: MCAN_INT 1 (Active low), 2: UVO or TSD (Active low), 3: Reserved
-const INH_DIS: u32 = 0;          // INH enabled (default)
-const NWKRQ_CONFIG: u32 = 0;     // 0: Mirrors INH function, 1: Wake request interrupt
-const MODE_SEL: u32 = 1;         // MODE_SEL, 0: sleep, 1: standby, 2: normal, 3: reserved
-const WD_EN: u32 = 0;            // Watchdog disabled
-const DEVICE_RESET: u32 = 0;     // Action at software reset, 0: Current configuration, 1: Device resets to default
-const SWE_DIS: u32 = 0;          // Keep Sleep Wake Error Enabled (it's a disable bit, not an enable)
-const TESTMODE_CONFIG: u32 = 0;  // Test mode, 0: PHY test, 1: CAN controller test
+use std::time::Duration;
 
-impl super::super::TCAN455xController {
-    pub fn set_device_modes_and_pins() -> Vec<u8> {
-    let addr: u16 = REG_DEV_MODES_AND_PINS;
-    let data: u32 = (WAKE_CONFIG << 30)
-        | (WD_TIMER << 28)
-        | (CLK_REF << 27)
-        | (GPO2_CONFIG << 22)
-        | (TESTMODE_EN << 21)
-        | (NWKRQ_VOLTAGE << 19)
-        | (WD_BIT_RESET << 18)
-        | (WD_ACTION << 16)
-        | (GPIO1_CONFIG << 14)
-        | (FAIL_SAFE_EN << 13)
-        | (GPIO1_GPO_CONFIG << 10)
-        | (INH_DIS << 9)
-        | (NWKRQ_CONFIG << 8)
-        | (MODE_SEL << 6)
-        | (WD_EN << 3)
-        | (DEVICE_RESET << 2)
-        | (SWE_DIS << 1)
-        | (TESTMODE_CONFIG << 0);
-
-        Self::generate_write_command(addr, vec![data])
+use crate::tcan4550::{bitfield::DevModesAndPins, register::*};
+use super::bit_timing::ClockRef;
+
+/// WAKE pin edge that wakes the device from sleep
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WakeConfig {
+    Disabled,
+    RisingEdge,
+    FallingEdge,
+    BothEdges,
+}
+
+/// Watchdog timeout
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WatchdogTimer {
+    Ms60,
+    Ms600,
+    S3,
+    S6,
+}
+
+impl WatchdogTimer {
+    pub fn duration(&self) -> Duration {
+        match self {
+            WatchdogTimer::Ms60 => Duration::from_millis(60),
+            WatchdogTimer::Ms600 => Duration::from_millis(600),
+            WatchdogTimer::S3 => Duration::from_secs(3),
+            WatchdogTimer::S6 => Duration::from_secs(6),
+        }
     }
 }
 
+/// Watchdog action on timeout
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WatchdogAction {
+    /// Set the WDTO interrupt
+    Interrupt,
+    /// Pulse INH
+    InhPulse,
+    /// Pulse the watchdog output pin
+    WatchdogPulse,
+}
+
+/// GPO1 function while GPIO1 is an output (all active low)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Gpo1Function {
+    SpiFault,
+    McanInt1,
+    UvoOrTsd,
+}
+
+/// GPO2 function (active low)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Gpo2Function {
+    NoAction,
+    McanInt0,
+    Watchdog,
+    /// Mirrors the nINT pin
+    NInt,
+}
 
+/// GPIO1 direction
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Gpio1Config {
+    /// Output with the function of `Gpo1Function`
+    Gpo,
+    ClkOut,
+    /// Watchdog input
+    Gpi,
+}
 
+/// nWKRQ pin function
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NwkrqConfig {
+    MirrorsInh,
+    WakeRequest,
+}
 
+/// Device modes and pins (MODES_AND_PINS) written by `setup`. MODE_SEL, the watchdog reset and
+/// the device reset bit are left to the mode switching functions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DeviceConfig {
+    pub wake_config: WakeConfig,
+    pub wd_timer: WatchdogTimer,
+    pub wd_action: WatchdogAction,
+    pub wd_enable: bool,
+    /// Crystal or clock on OSC1, also used for bitrate and timestamp calculations
+    pub clk_ref: ClockRef,
+    pub gpo1: Gpo1Function,
+    pub gpo2: Gpo2Function,
+    pub gpio1: Gpio1Config,
+    /// CLKOUT divides the reference clock by 2
+    pub clkout_div2: bool,
+    pub nwkrq_config: NwkrqConfig,
+    /// Supply nWKRQ from VIO instead of the internal rail
+    pub nwkrq_vio: bool,
+    pub inh_disable: bool,
+    pub fail_safe: bool,
+    /// Disable the sleep wake error timer
+    pub swe_disable: bool,
+    pub test_mode_enable: bool,
+    /// Test mode target, false: PHY, true: CAN controller
+    pub test_mode_controller: bool,
+}
 
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        Self {
+            wake_config: WakeConfig::BothEdges,
+            wd_timer: WatchdogTimer::Ms60,
+            wd_action: WatchdogAction::Interrupt,
+            wd_enable: false,
+            clk_ref: ClockRef::Mhz40,
+            gpo1: Gpo1Function::McanInt1,
+            gpo2: Gpo2Function::NoAction,
+            gpio1: Gpio1Config::Gpo,
+            clkout_div2: false,
+            nwkrq_config: NwkrqConfig::MirrorsInh,
+            nwkrq_vio: false,
+            inh_disable: false,
+            fail_safe: false,
+            swe_disable: false,
+            test_mode_enable: false,
+            test_mode_controller: false,
+        }
+    }
+}
 
+impl DeviceConfig {
+    /// MODES_AND_PINS with the device left in standby, without a watchdog or device reset
+    pub fn dev_modes_and_pins(&self) -> DevModesAndPins {
+        DevModesAndPins {
+            wake_config: match self.wake_config {
+                WakeConfig::Disabled => 0,
+                WakeConfig::RisingEdge => 1,
+                WakeConfig::FallingEdge => 2,
+                WakeConfig::BothEdges => 3,
+            },
+            wd_timer: match self.wd_timer {
+                WatchdogTimer::Ms60 => 0,
+                WatchdogTimer::Ms600 => 1,
+                WatchdogTimer::S3 => 2,
+                WatchdogTimer::S6 => 3,
+            },
+            clk_ref: self.clk_ref == ClockRef::Mhz40,
+            gpo2_config: match self.gpo2 {
+                Gpo2Function::NoAction => 0,
+                Gpo2Function::McanInt0 => 1,
+                Gpo2Function::Watchdog => 2,
+                Gpo2Function::NInt => 3,
+            },
+            test_mode_en: self.test_mode_enable,
+            nwkrq_voltage: self.nwkrq_vio,
+            wd_bit_set: false,
+            wd_action: match self.wd_action {
+                WatchdogAction::Interrupt => 0,
+                WatchdogAction::InhPulse => 1,
+                WatchdogAction::WatchdogPulse => 2,
+            },
+            gpio1_config: match self.gpio1 {
+                Gpio1Config::Gpo => 0,
+                Gpio1Config::ClkOut => 1,
+                Gpio1Config::Gpi => 2,
+            },
+            fail_safe_en: self.fail_safe,
+            clkout_prescaler: self.clkout_div2,
+            gpio1_gpo_config: match self.gpo1 {
+                Gpo1Function::SpiFault => 0,
+                Gpo1Function::McanInt1 => 1,
+                Gpo1Function::UvoOrTsd => 2,
+            },
+            inh_dis: self.inh_disable,
+            nwkrq_config: self.nwkrq_config == NwkrqConfig::WakeRequest,
+            mode_sel: DevModesAndPins::MODE_STANDBY,
+            wd_en: self.wd_enable,
+            device_reset: false,
+            swe_dis: self.swe_disable,
+            test_mode_config: self.test_mode_controller,
+            ..DevModesAndPins::default()
+        }
+    }
 
+    /// MODES_AND_PINS register value with the device left in standby
+    pub fn modes_and_pins(&self) -> u32 {
+        self.dev_modes_and_pins().to_u32()
+    }
+}
 
+impl super::super::TCAN455xController {
+    pub fn set_device_modes_and_pins(config: &DeviceConfig) -> Vec<u8> {
+        Self::generate_write_command(REG_DEV_MODES_AND_PINS, vec![config.modes_and_pins()])
+    }
+}
@@ -1,15 +1,25 @@
+use crate::error::{CandsError, CandsResult};
 use crate::tcan4550::register::*;
+use crate::tcan4550::bitfield::{McanRxf0c, McanRxf1c, McanTxbc};
 use crate::tcan4550::id_filter::{SIDConfig, XIDConfig};
 
 // MRAM sections
 const MRAM_SECTIONS_NUM: usize = 7;
 
-#[derive(Copy, Clone)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct FIFODATASIZE {
     pub size: u32,
     pub code: u32,
 }
 
+impl FIFODATASIZE {
+    /// Look up the data field size entry for a size in bytes
+    pub fn from_size(size: u32) -> Option<Self> {
+        FIFODATASIZE_LIST.iter().find(|x| x.size == size).copied()
+    }
+}
+
 // MRAM size
 pub const FIFODATASIZE_LIST: [FIFODATASIZE; 8] = [
     FIFODATASIZE {size: 8,  code: 0b000},
@@ -22,6 +32,7 @@ pub const FIFODATASIZE_LIST: [FIFODATASIZE; 8] = [
     FIFODATASIZE {size: 64, code: 0b111},
 ];
 
+// Default layout
 pub const RXFIFO0DATASIZE: FIFODATASIZE = FIFODATASIZE_LIST[7];
 pub const RXFIFO1DATASIZE: FIFODATASIZE = FIFODATASIZE_LIST[7];
 pub const RXBCDATASIZE: FIFODATASIZE = FIFODATASIZE_LIST[0];
@@ -31,9 +42,7 @@ pub const MRAMCONFIG_BYTESPERELEMENT_SID: u32 = 4;
 pub const MRAMCONFIG_BYTESPERELEMENT_XID: u32 = 8;
 pub const MRAMCONFIG_BYTESPERELEMENT_RXFIFO0: u32 = RXFIFO0DATASIZE.size + 8;
 pub const MRAMCONFIG_BYTESPERELEMENT_RXFIFO1: u32 = RXFIFO1DATASIZE.size + 8;
-pub const MRAMCONFIG_BYTESPERELEMENT_RXBC: u32 =  0;
 pub const MRAMCONFIG_BYTESPERELEMENT_TXEFC: u32 =  8;
-pub const MRAMCONFIG_BYTESPERELEMENT_TXBC: u32 =  TXFIFODATASIZE.size + 8;
 
 pub const MRAMCONFIG_NUMOFELEMENTS_SID: u32 = 2;
 pub const MRAMCONFIG_NUMOFELEMENTS_XID: u32 = 1;
@@ -43,198 +52,396 @@ pub const MRAMCONFIG_NUMOFELEMENTS_RXBC: u32 = 0;
 pub const MRAMCONFIG_NUMOFELEMENTS_TXEFC: u32 = 3;
 pub const MRAMCONFIG_NUMOFELEMENTS_TXBC: u32 = 10;
 
-#[allow(dead_code)]
-pub const MRAMCONFIG_BYTESPERELEMENT: [u32; MRAM_SECTIONS_NUM] = [
-    MRAMCONFIG_BYTESPERELEMENT_SID,
-    MRAMCONFIG_BYTESPERELEMENT_XID,
-    MRAMCONFIG_BYTESPERELEMENT_RXFIFO0,
-    MRAMCONFIG_BYTESPERELEMENT_RXFIFO1,
-    MRAMCONFIG_BYTESPERELEMENT_RXBC,
-    MRAMCONFIG_BYTESPERELEMENT_TXEFC,
-    MRAMCONFIG_BYTESPERELEMENT_TXBC,
-];
-
-pub const MRAMCONFIG_NUMOFELEMENTS: [u32; MRAM_SECTIONS_NUM] = [
-    MRAMCONFIG_NUMOFELEMENTS_SID,
-    MRAMCONFIG_NUMOFELEMENTS_XID,
-    MRAMCONFIG_NUMOFELEMENTS_RXFIFO0,
-    MRAMCONFIG_NUMOFELEMENTS_RXFIFO1,
-    MRAMCONFIG_NUMOFELEMENTS_RXBC,
-    MRAMCONFIG_NUMOFELEMENTS_TXEFC,
-    MRAMCONFIG_NUMOFELEMENTS_TXBC,
-];
+pub const RXDATA_BLOCKSIZE: [u32; 2] = [MRAMCONFIG_BYTESPERELEMENT_RXFIFO0, MRAMCONFIG_BYTESPERELEMENT_RXFIFO1];
 
-pub const MRAMCONFIG_MEMSIZE: [u32; MRAM_SECTIONS_NUM] = [
-    MRAMCONFIG_NUMOFELEMENTS_SID * MRAMCONFIG_BYTESPERELEMENT_SID,
-    MRAMCONFIG_NUMOFELEMENTS_XID * MRAMCONFIG_BYTESPERELEMENT_XID,
-    MRAMCONFIG_NUMOFELEMENTS_RXFIFO0 * MRAMCONFIG_BYTESPERELEMENT_RXFIFO0,
-    MRAMCONFIG_NUMOFELEMENTS_RXFIFO1 * MRAMCONFIG_BYTESPERELEMENT_RXFIFO1,
-    MRAMCONFIG_NUMOFELEMENTS_RXBC * MRAMCONFIG_BYTESPERELEMENT_RXBC,
-    MRAMCONFIG_NUMOFELEMENTS_TXEFC * MRAMCONFIG_BYTESPERELEMENT_TXEFC,
-    MRAMCONFIG_NUMOFELEMENTS_TXBC * MRAMCONFIG_BYTESPERELEMENT_TXBC,
-];
+// Maximum number of elements per section
+pub const MRAMCONFIG_MAXELEMENTS_SID: u32 = 128;
+pub const MRAMCONFIG_MAXELEMENTS_XID: u32 = 64;
+pub const MRAMCONFIG_MAXELEMENTS_RXFIFO: u32 = 64;
+pub const MRAMCONFIG_MAXELEMENTS_RXBC: u32 = 64;
+pub const MRAMCONFIG_MAXELEMENTS_TXEFC: u32 = 32;
+pub const MRAMCONFIG_MAXELEMENTS_TXBC: u32 = 32;
 
 // MRAM address
 pub const MRAM_BASEADDR: u16 = 0x8000;
+pub const MRAM_SIZE: u32 = 2048;
 
-pub const MRAM_OFFSETADDR: [u16; MRAM_SECTIONS_NUM] = get_mram_offset_addrs();
-pub const MRAM_OFFSETADDR_SID: u16 = MRAM_OFFSETADDR[0];
-pub const MRAM_OFFSETADDR_XID: u16 = MRAM_OFFSETADDR[1];
-pub const MRAM_OFFSETADDR_RXFIFO0: u16 = MRAM_OFFSETADDR[2];
-pub const MRAM_OFFSETADDR_RXFIFO1: u16 = MRAM_OFFSETADDR[3];
-pub const MRAM_OFFSETADDR_RXBC: u16 = MRAM_OFFSETADDR[4];
-pub const MRAM_OFFSETADDR_TXEFC: u16 = MRAM_OFFSETADDR[5];
-pub const MRAM_OFFSETADDR_TXBC: u16 = MRAM_OFFSETADDR[6];
-
-pub const MRAM_STARTADDR: [u16; MRAM_SECTIONS_NUM] = get_mram_start_addrs(MRAM_OFFSETADDR);
-pub const MRAM_STARTADDR_SID: u16 = MRAM_STARTADDR[0];
-pub const MRAM_STARTADDR_XID: u16 = MRAM_STARTADDR[1];
-pub const MRAM_STARTADDR_RXFIFO0: u16 = MRAM_STARTADDR[2];
-pub const MRAM_STARTADDR_RXFIFO1: u16 = MRAM_STARTADDR[3];
+// Water interrupt
 #[allow(dead_code)]
-pub const MRAM_STARTADDR_RXBC: u16 = MRAM_STARTADDR[4];
+pub const RXFIFO_WM_MAX: u32 = 64;
 #[allow(dead_code)]
-pub const MRAM_STARTADDR_TXEFC: u16 = MRAM_STARTADDR[5];
-pub const MRAM_STARTADDR_TXBC: u16 = MRAM_STARTADDR[6];
+pub const TXEFC_WM_MAX: u32 = 32;
+pub const RXFIFO0_WM: u32 = 0;
+pub const RXFIFO1_WM: u32 = 0;
+pub const TXEFC_WM: u32 = 2;
 
-pub const RXDATA_BLOCKSIZE: [u32; 2] = [MRAMCONFIG_BYTESPERELEMENT_RXFIFO0, MRAMCONFIG_BYTESPERELEMENT_RXFIFO1];
-pub const RXDATA_FIFOSIZE: [u32; 2] = [MRAMCONFIG_NUMOFELEMENTS_RXFIFO0, MRAMCONFIG_NUMOFELEMENTS_RXFIFO1];
-
-const fn get_mram_offset_addrs() -> [u16; MRAM_SECTIONS_NUM] {
-    let mut mram_start_addrs: [u16; MRAM_SECTIONS_NUM] = [0; MRAM_SECTIONS_NUM];
-
-    mram_start_addrs[0] = 0;
-    mram_start_addrs[1] = mram_start_addrs[0] + MRAMCONFIG_MEMSIZE[0] as u16;
-    mram_start_addrs[2] = mram_start_addrs[1] + MRAMCONFIG_MEMSIZE[1] as u16;
-    mram_start_addrs[3] = mram_start_addrs[2] + MRAMCONFIG_MEMSIZE[2] as u16;
-    mram_start_addrs[4] = mram_start_addrs[3] + MRAMCONFIG_MEMSIZE[3] as u16;
-    mram_start_addrs[5] = mram_start_addrs[4] + MRAMCONFIG_MEMSIZE[4] as u16;
-    mram_start_addrs[6] = mram_start_addrs[5] + MRAMCONFIG_MEMSIZE[5] as u16;
-
-    if MRAMCONFIG_NUMOFELEMENTS[0] == 0 { mram_start_addrs[0] = 0; }
-    if MRAMCONFIG_NUMOFELEMENTS[1] == 0 { mram_start_addrs[1] = 0; }
-    if MRAMCONFIG_NUMOFELEMENTS[2] == 0 { mram_start_addrs[2] = 0; }
-    if MRAMCONFIG_NUMOFELEMENTS[3] == 0 { mram_start_addrs[3] = 0; }
-    if MRAMCONFIG_NUMOFELEMENTS[4] == 0 { mram_start_addrs[4] = 0; }
-    if MRAMCONFIG_NUMOFELEMENTS[5] == 0 { mram_start_addrs[5] = 0; }
-    if MRAMCONFIG_NUMOFELEMENTS[6] == 0 { mram_start_addrs[6] = 0; }
-
-    mram_start_addrs
+/// Section of the message RAM, in the order they are laid out
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MramSection {
+    Sid = 0,
+    Xid = 1,
+    RxFifo0 = 2,
+    RxFifo1 = 3,
+    RxBuffer = 4,
+    TxEvent = 5,
+    TxBuffer = 6,
+}
+
+/// Message RAM partition
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MramLayout {
+    num_of_elements: [u32; MRAM_SECTIONS_NUM],
+    bytes_per_element: [u32; MRAM_SECTIONS_NUM],
+    offset_addrs: [u16; MRAM_SECTIONS_NUM],
+    rx_fifo0_data: FIFODATASIZE,
+    rx_fifo1_data: FIFODATASIZE,
+    rx_buffer_data: FIFODATASIZE,
+    tx_data: FIFODATASIZE,
+    tx_dedicated_buffers: u32,
 }
 
-const fn get_mram_start_addrs(offset_addr: [u16; MRAM_SECTIONS_NUM]) -> [u16; MRAM_SECTIONS_NUM] {
-    let mut mram_start_addrs: [u16; MRAM_SECTIONS_NUM] = [0; MRAM_SECTIONS_NUM];
+impl Default for MramLayout {
+    fn default() -> Self {
+        MramLayoutBuilder::new().build().expect("Default MRAM layout must fit")
+    }
+}
+
+impl MramLayout {
+    pub fn builder() -> MramLayoutBuilder {
+        MramLayoutBuilder::new()
+    }
 
-    mram_start_addrs[0] = MRAM_BASEADDR + offset_addr[0];
-    mram_start_addrs[1] = MRAM_BASEADDR + offset_addr[1];
-    mram_start_addrs[2] = MRAM_BASEADDR + offset_addr[2];
-    mram_start_addrs[3] = MRAM_BASEADDR + offset_addr[3];
-    mram_start_addrs[4] = MRAM_BASEADDR + offset_addr[4];
-    mram_start_addrs[5] = MRAM_BASEADDR + offset_addr[5];
-    mram_start_addrs[6] = MRAM_BASEADDR + offset_addr[6];
+    pub fn num_of_elements(&self, section: MramSection) -> u32 {
+        self.num_of_elements[section as usize]
+    }
 
-    mram_start_addrs
+    pub fn bytes_per_element(&self, section: MramSection) -> u32 {
+        self.bytes_per_element[section as usize]
+    }
+
+    /// Offset from the MRAM base address, 0 when the section is empty
+    pub fn offset_addr(&self, section: MramSection) -> u16 {
+        self.offset_addrs[section as usize]
+    }
+
+    pub fn start_addr(&self, section: MramSection) -> u16 {
+        MRAM_BASEADDR + self.offset_addr(section)
+    }
+
+    pub fn mem_size(&self, section: MramSection) -> u32 {
+        self.num_of_elements(section) * self.bytes_per_element(section)
+    }
+
+    /// Bytes used by all sections
+    pub fn total_size(&self) -> u32 {
+        self.num_of_elements.iter().zip(self.bytes_per_element.iter()).map(|(n, b)| n * b).sum()
+    }
+
+    pub fn rx_fifo0_data_size(&self) -> FIFODATASIZE { self.rx_fifo0_data }
+    pub fn rx_fifo1_data_size(&self) -> FIFODATASIZE { self.rx_fifo1_data }
+    pub fn rx_buffer_data_size(&self) -> FIFODATASIZE { self.rx_buffer_data }
+    pub fn tx_data_size(&self) -> FIFODATASIZE { self.tx_data }
+
+    /// Number of dedicated TX buffers, placed in front of the TX FIFO/queue
+    pub fn tx_dedicated_buffers(&self) -> u32 {
+        self.tx_dedicated_buffers
+    }
+
+    /// Number of TX FIFO/queue elements
+    pub fn tx_fifo_elements(&self) -> u32 {
+        self.num_of_elements(MramSection::TxBuffer) - self.tx_dedicated_buffers
+    }
+
+    /// RX element size of FIFO0 or FIFO1
+    pub fn rx_fifo_element_size(&self, ch: usize) -> u32 {
+        match ch {
+            0 => self.bytes_per_element(MramSection::RxFifo0),
+            _ => self.bytes_per_element(MramSection::RxFifo1),
+        }
+    }
+
+    pub fn rx_fifo_elements(&self, ch: usize) -> u32 {
+        match ch {
+            0 => self.num_of_elements(MramSection::RxFifo0),
+            _ => self.num_of_elements(MramSection::RxFifo1),
+        }
+    }
 }
 
-// Water interrupt
-#[allow(dead_code)]
-pub const RXFIFO_WM_MAX: u32 = 64;
-#[allow(dead_code)]
-pub const TXEFC_WM_MAX: u32 = 32;
-pub const RXFIFO0_WM: u32 = 0;
-pub const RXFIFO1_WM: u32 = 0;
-pub const TXEFC_WM: u32 = 2;
+/// Builder for `MramLayout`
+///
+/// Element counts and data field sizes start from the default layout.
+#[derive(Debug, Copy, Clone)]
+pub struct MramLayoutBuilder {
+    sid_filters: u32,
+    xid_filters: u32,
+    rx_fifo0_elements: u32,
+    rx_fifo0_data_size: u32,
+    rx_fifo1_elements: u32,
+    rx_fifo1_data_size: u32,
+    rx_buffers: u32,
+    rx_buffer_data_size: u32,
+    tx_events: u32,
+    tx_dedicated_buffers: u32,
+    tx_fifo_elements: u32,
+    tx_data_size: u32,
+}
+
+impl Default for MramLayoutBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MramLayoutBuilder {
+    pub fn new() -> Self {
+        Self {
+            sid_filters: MRAMCONFIG_NUMOFELEMENTS_SID,
+            xid_filters: MRAMCONFIG_NUMOFELEMENTS_XID,
+            rx_fifo0_elements: MRAMCONFIG_NUMOFELEMENTS_RXFIFO0,
+            rx_fifo0_data_size: RXFIFO0DATASIZE.size,
+            rx_fifo1_elements: MRAMCONFIG_NUMOFELEMENTS_RXFIFO1,
+            rx_fifo1_data_size: RXFIFO1DATASIZE.size,
+            rx_buffers: MRAMCONFIG_NUMOFELEMENTS_RXBC,
+            rx_buffer_data_size: RXBCDATASIZE.size,
+            tx_events: MRAMCONFIG_NUMOFELEMENTS_TXEFC,
+            tx_dedicated_buffers: 0,
+            tx_fifo_elements: MRAMCONFIG_NUMOFELEMENTS_TXBC,
+            tx_data_size: TXFIFODATASIZE.size,
+        }
+    }
+
+    pub fn sid_filters(mut self, num: u32) -> Self {
+        self.sid_filters = num;
+        self
+    }
+
+    pub fn xid_filters(mut self, num: u32) -> Self {
+        self.xid_filters = num;
+        self
+    }
+
+    /// `data_size` in bytes: 8, 12, 16, 20, 24, 32, 48 or 64
+    pub fn rx_fifo0(mut self, num: u32, data_size: u32) -> Self {
+        self.rx_fifo0_elements = num;
+        self.rx_fifo0_data_size = data_size;
+        self
+    }
+
+    pub fn rx_fifo1(mut self, num: u32, data_size: u32) -> Self {
+        self.rx_fifo1_elements = num;
+        self.rx_fifo1_data_size = data_size;
+        self
+    }
+
+    pub fn rx_buffers(mut self, num: u32, data_size: u32) -> Self {
+        self.rx_buffers = num;
+        self.rx_buffer_data_size = data_size;
+        self
+    }
+
+    pub fn tx_events(mut self, num: u32) -> Self {
+        self.tx_events = num;
+        self
+    }
+
+    /// Dedicated TX buffers and TX FIFO/queue elements share the TX buffer section and its data field size
+    pub fn tx_buffers(mut self, dedicated: u32, fifo: u32, data_size: u32) -> Self {
+        self.tx_dedicated_buffers = dedicated;
+        self.tx_fifo_elements = fifo;
+        self.tx_data_size = data_size;
+        self
+    }
+
+    pub fn build(self) -> CandsResult<MramLayout> {
+        let data_size = |size: u32| FIFODATASIZE::from_size(size).ok_or_else(|| {
+            CandsError::InvalidConfig(format!("Invalid data field size: {} bytes", size))
+        });
+
+        let rx_fifo0_data: FIFODATASIZE = data_size(self.rx_fifo0_data_size)?;
+        let rx_fifo1_data: FIFODATASIZE = data_size(self.rx_fifo1_data_size)?;
+        let rx_buffer_data: FIFODATASIZE = data_size(self.rx_buffer_data_size)?;
+        let tx_data: FIFODATASIZE = data_size(self.tx_data_size)?;
+
+        let limits: [(&str, u32, u32); 7] = [
+            ("SID filters", self.sid_filters, MRAMCONFIG_MAXELEMENTS_SID),
+            ("XID filters", self.xid_filters, MRAMCONFIG_MAXELEMENTS_XID),
+            ("RX FIFO0 elements", self.rx_fifo0_elements, MRAMCONFIG_MAXELEMENTS_RXFIFO),
+            ("RX FIFO1 elements", self.rx_fifo1_elements, MRAMCONFIG_MAXELEMENTS_RXFIFO),
+            ("RX buffers", self.rx_buffers, MRAMCONFIG_MAXELEMENTS_RXBC),
+            ("TX events", self.tx_events, MRAMCONFIG_MAXELEMENTS_TXEFC),
+            ("TX buffers", self.tx_dedicated_buffers + self.tx_fifo_elements, MRAMCONFIG_MAXELEMENTS_TXBC),
+        ];
+        for (name, num, max) in limits {
+            if num > max {
+                let msg: String = format!("Too many {}: {} > {}", name, num, max);
+                return Err(CandsError::InvalidConfig(msg));
+            }
+        }
+
+        let num_of_elements: [u32; MRAM_SECTIONS_NUM] = [
+            self.sid_filters,
+            self.xid_filters,
+            self.rx_fifo0_elements,
+            self.rx_fifo1_elements,
+            self.rx_buffers,
+            self.tx_events,
+            self.tx_dedicated_buffers + self.tx_fifo_elements,
+        ];
+
+        let bytes_per_element: [u32; MRAM_SECTIONS_NUM] = [
+            MRAMCONFIG_BYTESPERELEMENT_SID,
+            MRAMCONFIG_BYTESPERELEMENT_XID,
+            rx_fifo0_data.size + 8,
+            rx_fifo1_data.size + 8,
+            rx_buffer_data.size + 8,
+            MRAMCONFIG_BYTESPERELEMENT_TXEFC,
+            tx_data.size + 8,
+        ];
+
+        let total: u32 = num_of_elements.iter().zip(bytes_per_element.iter()).map(|(n, b)| n * b).sum();
+        if total > MRAM_SIZE {
+            let msg: String = format!("MRAM layout needs {} bytes, only {} available", total, MRAM_SIZE);
+            return Err(CandsError::InvalidConfig(msg));
+        }
+
+        Ok(MramLayout {
+            num_of_elements,
+            bytes_per_element,
+            offset_addrs: get_mram_offset_addrs(&num_of_elements, &bytes_per_element),
+            rx_fifo0_data,
+            rx_fifo1_data,
+            rx_buffer_data,
+            tx_data,
+            tx_dedicated_buffers: self.tx_dedicated_buffers,
+        })
+    }
+}
+
+fn get_mram_offset_addrs(num_of_elements: &[u32; MRAM_SECTIONS_NUM], bytes_per_element: &[u32; MRAM_SECTIONS_NUM]) -> [u16; MRAM_SECTIONS_NUM] {
+    let mut mram_offset_addrs: [u16; MRAM_SECTIONS_NUM] = [0; MRAM_SECTIONS_NUM];
+
+    let mut offset: u16 = 0;
+    for i in 0..MRAM_SECTIONS_NUM {
+        mram_offset_addrs[i] = if num_of_elements[i] == 0 { 0 } else { offset };
+        offset += (num_of_elements[i] * bytes_per_element[i]) as u16;
+    }
+
+    mram_offset_addrs
+}
 
 impl super::super::TCAN455xController {
-    pub fn set_sidfc() -> Vec<u8> {
+    pub fn set_sidfc(layout: &MramLayout) -> Vec<u8> {
         let addr: u16 =  REG_MCAN_SIDFC;
-        let data: u32 = (MRAMCONFIG_NUMOFELEMENTS_SID << 16) | MRAM_OFFSETADDR_SID as u32;
+        let data: u32 = (layout.num_of_elements(MramSection::Sid) << 16) | layout.offset_addr(MramSection::Sid) as u32;
         Self::generate_write_command(addr, vec![data])
     }
 
-    pub fn set_xidfc() -> Vec<u8> {
+    pub fn set_xidfc(layout: &MramLayout) -> Vec<u8> {
         let addr: u16 =  REG_MCAN_XIDFC;
-        let data: u32 = (MRAMCONFIG_NUMOFELEMENTS_XID << 16) | MRAM_OFFSETADDR_XID as u32;
+        let data: u32 = (layout.num_of_elements(MramSection::Xid) << 16) | layout.offset_addr(MramSection::Xid) as u32;
         Self::generate_write_command(addr, vec![data])
     }
 
-    pub fn set_rxf0c() -> Vec<u8> {
-        let addr: u16 =  REG_MCAN_RXF0C;
-        let data: u32 = REG_BITS_MCAN_RXF0C_F0OM_OVERWRITE | (RXFIFO0_WM << 24) | (MRAMCONFIG_NUMOFELEMENTS_RXFIFO0 << 16) | MRAM_OFFSETADDR_RXFIFO0 as u32;
-        Self::generate_write_command(addr, vec![data])
+    pub fn set_rxf0c(layout: &MramLayout) -> Vec<u8> {
+        let rxf0c: McanRxf0c = McanRxf0c {
+            f0om: true,
+            f0wm: RXFIFO0_WM as u8,
+            f0s: layout.num_of_elements(MramSection::RxFifo0) as u8,
+            f0sa: layout.offset_addr(MramSection::RxFifo0),
+            ..McanRxf0c::default()
+        };
+        Self::generate_register_write(&rxf0c)
     }
 
-    pub fn set_rxf1c() -> Vec<u8> {
-        let addr: u16 =  REG_MCAN_RXF1C;
-        let data: u32 = REG_BITS_MCAN_RXF0C_F0OM_OVERWRITE | (RXFIFO1_WM << 24) | (MRAMCONFIG_NUMOFELEMENTS_RXFIFO1 << 16) | MRAM_OFFSETADDR_RXFIFO1 as u32;
-        Self::generate_write_command(addr, vec![data])
+    pub fn set_rxf1c(layout: &MramLayout) -> Vec<u8> {
+        let rxf1c: McanRxf1c = McanRxf1c {
+            f1om: true,
+            f1wm: RXFIFO1_WM as u8,
+            f1s: layout.num_of_elements(MramSection::RxFifo1) as u8,
+            f1sa: layout.offset_addr(MramSection::RxFifo1),
+            ..McanRxf1c::default()
+        };
+        Self::generate_register_write(&rxf1c)
     }
 
-    pub fn set_rxbc() -> Vec<u8> {
+    pub fn set_rxbc(layout: &MramLayout) -> Vec<u8> {
         let addr: u16 =  REG_MCAN_RXBC;
-        let data: u32 = MRAM_OFFSETADDR_RXBC as u32;
+        let data: u32 = layout.offset_addr(MramSection::RxBuffer) as u32;
         Self::generate_write_command(addr, vec![data])
     }
 
-    pub fn set_rxesc() -> Vec<u8> {
+    pub fn set_rxesc(layout: &MramLayout) -> Vec<u8> {
         let addr: u16 =  REG_MCAN_RXESC;
-        let data: u32 = (RXBCDATASIZE.code << 8) | (RXFIFO1DATASIZE.code << 4) | (RXFIFO0DATASIZE.code);
+        let data: u32 = (layout.rx_buffer_data_size().code << 8) | (layout.rx_fifo1_data_size().code << 4) | (layout.rx_fifo0_data_size().code);
         Self::generate_write_command(addr, vec![data])
     }
 
-    pub fn set_txefc() -> Vec<u8> {
+    pub fn set_txefc(layout: &MramLayout) -> Vec<u8> {
         let addr: u16 =  REG_MCAN_TXEFC;
-        let data: u32 = (TXEFC_WM << 24) | (MRAMCONFIG_NUMOFELEMENTS_TXEFC << 16) | MRAM_OFFSETADDR_TXEFC as u32;
+        let num: u32 = layout.num_of_elements(MramSection::TxEvent);
+        let data: u32 = (TXEFC_WM.min(num) << 24) | (num << 16) | layout.offset_addr(MramSection::TxEvent) as u32;
         Self::generate_write_command(addr, vec![data])
     }
 
-    pub fn set_txbc() -> Vec<u8> {
-        let addr: u16 =  REG_MCAN_TXBC;
-        let data: u32 = (MRAMCONFIG_NUMOFELEMENTS_TXBC << 24) | (0 << 16) | MRAM_OFFSETADDR_TXBC as u32;
-        Self::generate_write_command(addr, vec![data])
+    pub fn set_txbc(layout: &MramLayout) -> Vec<u8> {
+        let txbc: McanTxbc = McanTxbc {
+            tfqs: layout.tx_fifo_elements() as u8,
+            ndtb: layout.tx_dedicated_buffers() as u8,
+            tbsa: layout.offset_addr(MramSection::TxBuffer),
+            ..McanTxbc::default()
+        };
+        Self::generate_register_write(&txbc)
     }
 
-    pub fn set_txesc() -> Vec<u8> {
+    pub fn set_txesc(layout: &MramLayout) -> Vec<u8> {
         let addr: u16 =  REG_MCAN_TXESC;
-        let data: u32 = TXFIFODATASIZE.code;
+        let data: u32 = layout.tx_data_size().code;
         Self::generate_write_command(addr, vec![data])
     }
 
     // Filter configuration
-    pub fn set_sid(sidf_config: &[SIDConfig]) -> Vec<u8> {
+    pub fn set_sid(layout: &MramLayout, sidf_config: &[SIDConfig]) -> Vec<u8> {
         let mut reg_data: Vec<u32> = Vec::new();
-        for i in 0..MRAMCONFIG_NUMOFELEMENTS_SID as usize {
-            reg_data.push((sidf_config[i].sft << 30) | (sidf_config[i].sfec << 27) | (sidf_config[i].sidf1 << 16) | sidf_config[i].sidf2);
+        for sidf in sidf_config.iter().take(layout.num_of_elements(MramSection::Sid) as usize) {
+            reg_data.push((sidf.sft << 30) | (sidf.sfec << 27) | (sidf.sidf1 << 16) | sidf.sidf2);
         }
 
-        let addr: u16 =  MRAM_STARTADDR_SID as u16;
+        let addr: u16 =  layout.start_addr(MramSection::Sid);
         let data: Vec<u32> =  reg_data.to_vec();
         Self::generate_write_command(addr, data)
     }
 
-    pub fn set_xid(xidf_config: &[XIDConfig]) -> Vec<u8> {
+    pub fn set_xid(layout: &MramLayout, xidf_config: &[XIDConfig]) -> Vec<u8> {
         let mut reg_data: Vec<u32> = Vec::new();
-        for i in 0..MRAMCONFIG_NUMOFELEMENTS_XID as usize {
-            reg_data.push((xidf_config[i].efec << 29) | xidf_config[i].eidf1);
-            reg_data.push((xidf_config[i].eft << 30) | xidf_config[i].eidf2);
+        for xidf in xidf_config.iter().take(layout.num_of_elements(MramSection::Xid) as usize) {
+            reg_data.push((xidf.efec << 29) | xidf.eidf1);
+            reg_data.push((xidf.eft << 30) | xidf.eidf2);
         }
 
-        let addr: u16 =  MRAM_STARTADDR_XID as u16;
+        let addr: u16 =  layout.start_addr(MramSection::Xid);
 
         Self::generate_write_command(addr, reg_data)
     }
 
-    pub fn get_txdata_start_addr(put_index: u16) -> u16 {
-        MRAM_STARTADDR_TXBC + MRAMCONFIG_BYTESPERELEMENT_TXBC as u16 * put_index
+    pub fn get_txdata_start_addr(layout: &MramLayout, put_index: u16) -> u16 {
+        layout.start_addr(MramSection::TxBuffer) + layout.bytes_per_element(MramSection::TxBuffer) as u16 * put_index
     }
-    
-    pub fn get_rxdata_start_addr(ch: u16, get_index: u16) -> u16 {
+
+    pub fn get_txevent_start_addr(layout: &MramLayout, get_index: u16) -> u16 {
+        layout.start_addr(MramSection::TxEvent) + layout.bytes_per_element(MramSection::TxEvent) as u16 * get_index
+    }
+
+    pub fn get_rxbuffer_start_addr(layout: &MramLayout, index: u16) -> u16 {
+        layout.start_addr(MramSection::RxBuffer) + layout.bytes_per_element(MramSection::RxBuffer) as u16 * index
+    }
+
+    pub fn get_rxdata_start_addr(layout: &MramLayout, ch: u16, get_index: u16) -> u16 {
       if ch == 0 {
-        MRAM_STARTADDR_RXFIFO0 + MRAMCONFIG_BYTESPERELEMENT_RXFIFO0 as u16 * get_index
+        layout.start_addr(MramSection::RxFifo0) + layout.bytes_per_element(MramSection::RxFifo0) as u16 * get_index
       }
       else {
-        MRAM_STARTADDR_RXFIFO1 + MRAMCONFIG_BYTESPERELEMENT_RXFIFO1 as u16 * get_index
+        layout.start_addr(MramSection::RxFifo1) + layout.bytes_per_element(MramSection::RxFifo1) as u16 * get_index
       }
     }
 }
-
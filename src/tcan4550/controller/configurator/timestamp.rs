@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use crate::error::{CandsError, CandsResult};
+use crate::tcan4550::{bitfield::McanTscc, register::*};
+use super::bit_timing::{BitTiming, ClockRef};
+
+// TSCC.TCP holds the prescaler minus one
+const TSCC_TCP_MAX: u8 = 16;
+
+// TSCC.TSS counter sources
+const TSCC_TSS_ALWAYS_0: u8 = REG_BITS_MCAN_TSCC_COUNTER_ALWAYS_0 as u8;
+const TSCC_TSS_TCP: u8 = REG_BITS_MCAN_TSCC_COUNTER_USE_TCP as u8;
+const TSCC_TSS_EXTERNAL: u8 = REG_BITS_MCAN_TSCC_COUNTER_EXTERNAL as u8;
+
+// The external counter is clocked by the CAN clock divided by 8 times TIMESTAMP_PRESCALER
+const EXTERNAL_CLOCK_DIVIDER: u64 = 8;
+
+/// Timestamp counter source (TSCC.TSS) and its prescaler
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimestampConfig {
+    /// Counter stays at 0, frames carry no timestamp
+    Disabled,
+    /// Incremented every `prescaler` nominal bit times (1 to 16)
+    BitTime { prescaler: u8 },
+    /// TCAN455x counter clocked by the CAN clock divided by 8 × `prescaler` (TIMESTAMP_PRESCALER)
+    External { prescaler: u8 },
+}
+
+impl Default for TimestampConfig {
+    fn default() -> Self {
+        TimestampConfig::External { prescaler: 2 }
+    }
+}
+
+impl TimestampConfig {
+    pub fn is_enabled(&self) -> bool {
+        *self != TimestampConfig::Disabled
+    }
+
+    pub fn validate(&self) -> CandsResult<()> {
+        match *self {
+            TimestampConfig::BitTime { prescaler } if prescaler == 0 || prescaler > TSCC_TCP_MAX => {
+                Err(CandsError::InvalidConfig(format!("Timestamp prescaler {} out of range 1..=16", prescaler)))
+            },
+            TimestampConfig::External { prescaler: 0 } => {
+                Err(CandsError::InvalidConfig("Timestamp prescaler must not be 0".to_string()))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// TSCC register
+    pub fn tscc(&self) -> McanTscc {
+        match *self {
+            TimestampConfig::Disabled => McanTscc { tss: TSCC_TSS_ALWAYS_0, ..McanTscc::default() },
+            TimestampConfig::BitTime { prescaler } => {
+                McanTscc { tss: TSCC_TSS_TCP, tcp: prescaler.clamp(1, TSCC_TCP_MAX) - 1, ..McanTscc::default() }
+            },
+            TimestampConfig::External { .. } => McanTscc { tss: TSCC_TSS_EXTERNAL, ..McanTscc::default() },
+        }
+    }
+
+    /// Time per counter tick, None when disabled
+    pub fn tick_duration(&self, clock: ClockRef, timing: &BitTiming) -> Option<Duration> {
+        let (cycles, hz): (u64, u64) = match *self {
+            TimestampConfig::Disabled => return None,
+            TimestampConfig::BitTime { prescaler } => (prescaler as u64, timing.nominal_bitrate(clock).max(1) as u64),
+            TimestampConfig::External { prescaler } => (EXTERNAL_CLOCK_DIVIDER * prescaler as u64, clock.hz() as u64),
+        };
+        Some(Duration::from_nanos(cycles * 1_000_000_000 / hz))
+    }
+}
+
+impl super::super::TCAN455xController {
+    /// TIMESTAMP_PRESCALER, only used by the external counter
+    pub fn set_timestamp_prescaler(config: &TimestampConfig) -> Option<Vec<u8>> {
+        match *config {
+            TimestampConfig::External { prescaler } => {
+                Some(Self::generate_write_command(REG_DEV_TIMESTAMP_PRESCALER, vec![prescaler as u32]))
+            },
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::TimestampConfig;
+    use crate::tcan4550::controller::configurator::bit_timing::{BitTiming, ClockRef};
+
+    #[test]
+    fn external_ticks_divide_the_can_clock_by_8_times_the_prescaler() {
+        let timing: BitTiming = BitTiming::default();
+        let tick = |prescaler: u8, clock: ClockRef| TimestampConfig::External { prescaler }.tick_duration(clock, &timing);
+        assert_eq!(tick(2, ClockRef::Mhz40), Some(Duration::from_nanos(400)));
+        assert_eq!(tick(1, ClockRef::Mhz20), Some(Duration::from_nanos(400)));
+        assert_eq!(tick(5, ClockRef::Mhz40), Some(Duration::from_nanos(1000)));
+    }
+
+    #[test]
+    fn bit_time_ticks_follow_the_nominal_bitrate() {
+        // The default timing runs at 500 kbit/s from 40 MHz
+        let timing: BitTiming = BitTiming::default();
+        let tick = |prescaler: u8| TimestampConfig::BitTime { prescaler }.tick_duration(ClockRef::Mhz40, &timing);
+        assert_eq!(tick(1), Some(Duration::from_micros(2)));
+        assert_eq!(tick(16), Some(Duration::from_micros(32)));
+        assert_eq!(TimestampConfig::Disabled.tick_duration(ClockRef::Mhz40, &timing), None);
+    }
+
+    #[test]
+    fn prescaler_ranges_are_checked() {
+        assert!(TimestampConfig::BitTime { prescaler: 0 }.validate().is_err());
+        assert!(TimestampConfig::BitTime { prescaler: 17 }.validate().is_err());
+        assert!(TimestampConfig::External { prescaler: 0 }.validate().is_err());
+        assert_eq!(TimestampConfig::BitTime { prescaler: 16 }.tscc().tcp, 15);
+    }
+}
@@ -1,7 +1,11 @@
+// Register words are composed from every field in bit order, `<< 0` included
+#[allow(clippy::identity_op)]
 pub mod configurator;
 
 use std::borrow::Borrow;
 
+use super::bitfield::Register;
+
 pub struct TCAN455xController {}
 
 impl TCAN455xController {
@@ -14,8 +18,8 @@ impl TCAN455xController {
         let addr: [u8; 2] = addr.to_be_bytes();
         let len: usize = data.len();
         let mut buf: Vec<u8> = Vec::<u8>::new();
-        for i in 0..len {
-            buf.extend(data[i].to_be_bytes());
+        for word in data.iter() {
+            buf.extend(word.to_be_bytes());
         }
 
         let mut payload: Vec<u8> = Vec::new();
@@ -27,6 +31,11 @@ impl TCAN455xController {
         payload
     }
 
+    /// Write command for a whole register
+    pub(crate) fn generate_register_write<R: Register>(reg: &R) -> Vec<u8> {
+        Self::generate_write_command(R::ADDR, vec![reg.to_u32()])
+    }
+
     pub(crate) fn generate_read_command(addr: u16, len: u8) -> Vec<u8>{
         let addr: [u8; 2] = addr.to_be_bytes();
         /* zero padding in order to send sclk for extracting all MISO data*/
@@ -34,7 +43,7 @@ impl TCAN455xController {
         let mut payload: Vec<u8> = Vec::new(); 
         payload.push(Self::READ_B_FL);
         payload.extend(addr);
-        payload.push(len as u8);
+        payload.push(len);
         payload.extend(&zero_padder);
         
         payload
@@ -0,0 +1,319 @@
+use crate::error::{CandsError, CandsResult};
+use crate::tcan4550::controller::configurator::mram::{MramLayout, MramSection};
+use super::{SIDConfig, XIDConfig};
+
+const CAN_SID_MAX: u32 = 0x7FF;
+const CAN_XID_MAX: u32 = 0x1FFFFFFF;
+
+// Dedicated RX buffer index field of SFID2/EFID2
+const RX_BUFFER_INDEX_MAX: u8 = 0x3F;
+
+/// Filter type (SFT/EFT)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FilterType {
+    /// Accept IDs from `id1` to `id2`. Extended filters apply XIDAM.
+    Range,
+    /// Accept `id1` or `id2`
+    DualId,
+    /// Accept IDs equal to `id1` in the bits set in mask `id2`
+    ClassicMask,
+    /// Extended range from `id1` to `id2` without XIDAM
+    RangeNoMask,
+    /// Element kept in place but never matches
+    Disabled,
+}
+
+/// Filter element configuration (SFEC/EFEC)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FilterAction {
+    Reject,
+    Fifo0,
+    Fifo1,
+    /// Set the high priority message status without storing the frame
+    Priority,
+    PriorityFifo0,
+    PriorityFifo1,
+    /// Store into a dedicated RX buffer. Matches `id1` only, the filter type and `id2` are ignored.
+    RxBuffer(u8),
+}
+
+impl FilterAction {
+    /// SFEC/EFEC value
+    pub fn code(&self) -> u32 {
+        match self {
+            FilterAction::Fifo0 => 1,
+            FilterAction::Fifo1 => 2,
+            FilterAction::Reject => 3,
+            FilterAction::Priority => 4,
+            FilterAction::PriorityFifo0 => 5,
+            FilterAction::PriorityFifo1 => 6,
+            FilterAction::RxBuffer(_) => 7,
+        }
+    }
+}
+
+/// Standard or extended ID filter element
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IdFilter {
+    pub xtd: bool,
+    pub filter_type: FilterType,
+    pub action: FilterAction,
+    pub id1: u32,
+    pub id2: u32,
+}
+
+fn invalid_filter(filter: &IdFilter, msg: &str) -> CandsError {
+    let kind: &str = if filter.xtd { "Extended" } else { "Standard" };
+    CandsError::InvalidConfig(format!("{} filter {:?}: {}", kind, filter, msg))
+}
+
+impl IdFilter {
+    pub fn standard(filter_type: FilterType, action: FilterAction, id1: u32, id2: u32) -> Self {
+        Self { xtd: false, filter_type, action, id1, id2 }
+    }
+
+    pub fn extended(filter_type: FilterType, action: FilterAction, id1: u32, id2: u32) -> Self {
+        Self { xtd: true, filter_type, action, id1, id2 }
+    }
+
+    /// Check the IDs against the identifier width and the type against the ID format
+    pub fn validate(&self) -> CandsResult<()> {
+        let id_max: u32 = if self.xtd { CAN_XID_MAX } else { CAN_SID_MAX };
+        if self.id1 > id_max || self.id2 > id_max {
+            return Err(invalid_filter(self, "ID exceeds the identifier width"));
+        }
+        if let FilterAction::RxBuffer(index) = self.action {
+            if index > RX_BUFFER_INDEX_MAX {
+                return Err(invalid_filter(self, "RX buffer index exceeds 63"));
+            }
+            return Ok(());
+        }
+        match self.filter_type {
+            FilterType::Range | FilterType::RangeNoMask if self.id1 > self.id2 => {
+                Err(invalid_filter(self, "Range start exceeds range end"))
+            },
+            FilterType::RangeNoMask if !self.xtd => {
+                Err(invalid_filter(self, "Range without XIDAM only exists for extended filters"))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Raw standard filter element, None for an extended filter
+    pub fn to_sid_config(&self) -> Option<SIDConfig> {
+        if self.xtd {
+            return None;
+        }
+        let config: SIDConfig = match (self.action, self.filter_type) {
+            (FilterAction::RxBuffer(index), _) => SIDConfig::rx_buffer(self.id1, index),
+            (action, filter_type) => SIDConfig {
+                sft: match filter_type {
+                    FilterType::Range | FilterType::RangeNoMask => 0,
+                    FilterType::DualId => 1,
+                    FilterType::ClassicMask => 2,
+                    FilterType::Disabled => 3,
+                },
+                sfec: action.code(),
+                sidf1: self.id1,
+                sidf2: self.id2,
+            },
+        };
+        Some(config)
+    }
+
+    /// Raw extended filter element, None for a standard filter
+    pub fn to_xid_config(&self) -> Option<XIDConfig> {
+        if !self.xtd {
+            return None;
+        }
+        let config: XIDConfig = match (self.action, self.filter_type) {
+            (FilterAction::RxBuffer(index), _) => XIDConfig::rx_buffer(self.id1, index),
+            // Extended filters have no disabled type, EFEC = 0 disables the element
+            (_, FilterType::Disabled) => XIDConfig { eft: 0, efec: 0, eidf1: self.id1, eidf2: self.id2 },
+            (action, filter_type) => XIDConfig {
+                eft: match filter_type {
+                    FilterType::DualId => 1,
+                    FilterType::ClassicMask => 2,
+                    FilterType::RangeNoMask => 3,
+                    _ => 0,
+                },
+                efec: action.code(),
+                eidf1: self.id1,
+                eidf2: self.id2,
+            },
+        };
+        Some(config)
+    }
+}
+
+/// Validated filter elements ready for `setup` or `configure_filter`
+#[derive(Debug, Clone, Default)]
+pub struct IdFilters {
+    pub sid: Vec<SIDConfig>,
+    pub xid: Vec<XIDConfig>,
+}
+
+/// Builder for `IdFilters`
+///
+/// Filters are matched in the order they are added, standard and extended filters separately.
+#[derive(Debug, Clone, Default)]
+pub struct IdFilterBuilder {
+    filters: Vec<IdFilter>,
+}
+
+impl IdFilterBuilder {
+    pub fn new() -> Self {
+        Self { filters: Vec::new() }
+    }
+
+    pub fn filter(mut self, filter: IdFilter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    pub fn standard(self, filter_type: FilterType, action: FilterAction, id1: u32, id2: u32) -> Self {
+        self.filter(IdFilter::standard(filter_type, action, id1, id2))
+    }
+
+    pub fn extended(self, filter_type: FilterType, action: FilterAction, id1: u32, id2: u32) -> Self {
+        self.filter(IdFilter::extended(filter_type, action, id1, id2))
+    }
+
+    /// Validate every filter and check that they fit the filter sections and RX buffers of `layout`
+    pub fn build(self, layout: &MramLayout) -> CandsResult<IdFilters> {
+        let rx_buffers: u32 = layout.num_of_elements(MramSection::RxBuffer);
+        for filter in self.filters.iter() {
+            filter.validate()?;
+            match filter.action {
+                FilterAction::RxBuffer(index) if index as u32 >= rx_buffers => {
+                    let msg: String = format!("RX buffer {} out of range, {} buffers configured", index, rx_buffers);
+                    return Err(invalid_filter(filter, &msg));
+                },
+                _ => {},
+            }
+        }
+
+        let sid: Vec<SIDConfig> = self.filters.iter().filter_map(IdFilter::to_sid_config).collect();
+        let xid: Vec<XIDConfig> = self.filters.iter().filter_map(IdFilter::to_xid_config).collect();
+        check_filter_capacity(layout, sid.len(), xid.len())?;
+        Ok(IdFilters { sid, xid })
+    }
+}
+
+/// Fails if more filters are given than the MRAM layout has elements for
+pub fn check_filter_capacity(layout: &MramLayout, sid: usize, xid: usize) -> CandsResult<()> {
+    let limits: [(&str, usize, u32); 2] = [
+        ("standard", sid, layout.num_of_elements(MramSection::Sid)),
+        ("extended", xid, layout.num_of_elements(MramSection::Xid)),
+    ];
+    for (name, num, max) in limits {
+        if num > max as usize {
+            let msg: String = format!("Too many {} filters: {} > {}", name, num, max);
+            return Err(CandsError::InvalidConfig(msg));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_filter_capacity, FilterAction, FilterType, IdFilter, IdFilterBuilder, IdFilters, SIDConfig, XIDConfig};
+    use crate::error::{CandsError, CandsResult};
+    use crate::tcan4550::controller::configurator::mram::MramLayout;
+
+    fn layout(sid: u32, xid: u32, rx_buffers: u32) -> MramLayout {
+        MramLayout::builder().sid_filters(sid).xid_filters(xid).rx_buffers(rx_buffers, 8).build().unwrap()
+    }
+
+    fn rejected(filter: IdFilter) -> bool {
+        matches!(filter.validate(), Err(CandsError::InvalidConfig(_)))
+    }
+
+    fn sid(filter: IdFilter) -> (u32, u32, u32, u32) {
+        let config: SIDConfig = filter.to_sid_config().unwrap();
+        (config.sft, config.sfec, config.sidf1, config.sidf2)
+    }
+
+    fn xid(filter: IdFilter) -> (u32, u32, u32, u32) {
+        let config: XIDConfig = filter.to_xid_config().unwrap();
+        (config.eft, config.efec, config.eidf1, config.eidf2)
+    }
+
+    #[test]
+    fn standard_types_and_actions_map_to_sft_and_sfec() {
+        assert_eq!(sid(IdFilter::standard(FilterType::Range, FilterAction::Fifo0, 0x10, 0x20)), (0, 1, 0x10, 0x20));
+        assert_eq!(sid(IdFilter::standard(FilterType::DualId, FilterAction::Fifo1, 0x10, 0x20)), (1, 2, 0x10, 0x20));
+        assert_eq!(sid(IdFilter::standard(FilterType::ClassicMask, FilterAction::Reject, 0x10, 0x7F0)), (2, 3, 0x10, 0x7F0));
+        assert_eq!(sid(IdFilter::standard(FilterType::Disabled, FilterAction::Priority, 0, 0)), (3, 4, 0, 0));
+        assert_eq!(sid(IdFilter::standard(FilterType::DualId, FilterAction::PriorityFifo0, 1, 2)).1, 5);
+        assert_eq!(sid(IdFilter::standard(FilterType::DualId, FilterAction::PriorityFifo1, 1, 2)).1, 6);
+        // The buffer index goes to SFID2, the type and id2 are ignored
+        assert_eq!(sid(IdFilter::standard(FilterType::DualId, FilterAction::RxBuffer(5), 0x123, 0x456)), (0, 7, 0x123, 5));
+        assert!(IdFilter::extended(FilterType::DualId, FilterAction::Fifo0, 1, 2).to_sid_config().is_none());
+    }
+
+    #[test]
+    fn extended_types_and_actions_map_to_eft_and_efec() {
+        assert_eq!(xid(IdFilter::extended(FilterType::Range, FilterAction::Fifo0, 0x10, 0x20)), (0, 1, 0x10, 0x20));
+        assert_eq!(xid(IdFilter::extended(FilterType::DualId, FilterAction::Fifo1, 0x10, 0x20)), (1, 2, 0x10, 0x20));
+        assert_eq!(xid(IdFilter::extended(FilterType::ClassicMask, FilterAction::Reject, 0x10, 0x1FFFFFF0)), (2, 3, 0x10, 0x1FFFFFF0));
+        assert_eq!(xid(IdFilter::extended(FilterType::RangeNoMask, FilterAction::Fifo0, 0x10, 0x20)), (3, 1, 0x10, 0x20));
+        // No disabled type for extended filters, EFEC = 0 disables the element
+        assert_eq!(xid(IdFilter::extended(FilterType::Disabled, FilterAction::Fifo0, 0x10, 0x20)), (0, 0, 0x10, 0x20));
+        assert_eq!(xid(IdFilter::extended(FilterType::Range, FilterAction::RxBuffer(63), 0x1234567, 0)), (0, 7, 0x1234567, 63));
+        assert!(IdFilter::standard(FilterType::DualId, FilterAction::Fifo0, 1, 2).to_xid_config().is_none());
+    }
+
+    #[test]
+    fn ids_are_limited_to_the_identifier_width() {
+        assert!(IdFilter::standard(FilterType::DualId, FilterAction::Fifo0, 0x7FF, 0x7FF).validate().is_ok());
+        assert!(rejected(IdFilter::standard(FilterType::DualId, FilterAction::Fifo0, 0x800, 0)));
+        assert!(rejected(IdFilter::standard(FilterType::DualId, FilterAction::Fifo0, 0, 0x800)));
+        assert!(IdFilter::extended(FilterType::DualId, FilterAction::Fifo0, 0x1FFFFFFF, 0x800).validate().is_ok());
+        assert!(rejected(IdFilter::extended(FilterType::DualId, FilterAction::Fifo0, 0x20000000, 0)));
+        assert!(rejected(IdFilter::standard(FilterType::Range, FilterAction::RxBuffer(64), 0x100, 0)));
+    }
+
+    #[test]
+    fn ranges_must_be_ordered_but_dual_ids_need_not() {
+        assert!(IdFilter::standard(FilterType::Range, FilterAction::Fifo0, 0x20, 0x20).validate().is_ok());
+        assert!(rejected(IdFilter::standard(FilterType::Range, FilterAction::Fifo0, 0x21, 0x20)));
+        assert!(rejected(IdFilter::extended(FilterType::RangeNoMask, FilterAction::Fifo0, 0x21, 0x20)));
+        assert!(IdFilter::standard(FilterType::DualId, FilterAction::Fifo0, 0x21, 0x20).validate().is_ok());
+        // A dedicated buffer filter only matches id1
+        assert!(IdFilter::standard(FilterType::Range, FilterAction::RxBuffer(0), 0x21, 0).validate().is_ok());
+        assert!(rejected(IdFilter::standard(FilterType::RangeNoMask, FilterAction::Fifo0, 0x10, 0x20)));
+    }
+
+    #[test]
+    fn build_keeps_the_order_per_format_and_checks_rx_buffers() {
+        let filters: IdFilters = IdFilterBuilder::new()
+            .standard(FilterType::DualId, FilterAction::Fifo0, 0x100, 0x101)
+            .extended(FilterType::DualId, FilterAction::Fifo1, 0x10000, 0x10001)
+            .standard(FilterType::Range, FilterAction::RxBuffer(1), 0x200, 0)
+            .build(&layout(2, 1, 2))
+            .unwrap();
+        let sidf1: Vec<u32> = filters.sid.iter().map(|config| config.sidf1).collect();
+        assert_eq!(sidf1, vec![0x100, 0x200]);
+        assert_eq!(filters.xid[0].eidf1, 0x10000);
+
+        let out_of_range: CandsResult<IdFilters> = IdFilterBuilder::new()
+            .standard(FilterType::Range, FilterAction::RxBuffer(2), 0x200, 0)
+            .build(&layout(2, 1, 2));
+        assert!(matches!(out_of_range, Err(CandsError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn filter_capacity_is_checked_per_section() {
+        let layout: MramLayout = layout(2, 1, 0);
+        assert!(check_filter_capacity(&layout, 2, 1).is_ok());
+        assert!(matches!(check_filter_capacity(&layout, 3, 0), Err(CandsError::InvalidConfig(_))));
+        assert!(matches!(check_filter_capacity(&layout, 0, 2), Err(CandsError::InvalidConfig(_))));
+
+        let too_many: CandsResult<IdFilters> = IdFilterBuilder::new()
+            .extended(FilterType::DualId, FilterAction::Fifo0, 1, 2)
+            .extended(FilterType::DualId, FilterAction::Fifo0, 3, 4)
+            .build(&layout);
+        assert!(matches!(too_many, Err(CandsError::InvalidConfig(_))));
+    }
+}
@@ -1,4 +1,6 @@
 mod sid;
 mod xid;
+mod builder;
 
-pub use {sid::SIDConfig, xid::XIDConfig};
\ No newline at end of file
+pub use {sid::SIDConfig, xid::XIDConfig};
+pub use builder::{FilterType, FilterAction, IdFilter, IdFilters, IdFilterBuilder, check_filter_capacity};
\ No newline at end of file
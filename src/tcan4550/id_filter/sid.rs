@@ -1,5 +1,5 @@
 /// Service ID Filter
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone)]
 pub struct SIDConfig {
     pub sft: u32,
     pub sfec: u32,
@@ -7,6 +7,12 @@ pub struct SIDConfig {
     pub sidf2: u32
 }
 
+impl Default for SIDConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SIDConfig {
     pub fn new() -> Self {
         Self {
@@ -16,4 +22,14 @@ impl SIDConfig {
             sidf2: 0
         }
     }
+
+    /// Store frames with standard ID `id` into dedicated RX buffer `index` (SFEC = 7)
+    pub fn rx_buffer(id: u32, index: u8) -> Self {
+        Self {
+            sft: 0,
+            sfec: 7,
+            sidf1: id & 0x7FF,
+            sidf2: index as u32 & 0x3F
+        }
+    }
 }
\ No newline at end of file
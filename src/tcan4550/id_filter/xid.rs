@@ -1,5 +1,5 @@
 /// Extended ID Filter
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone)]
 pub struct XIDConfig {
     pub eft: u32,
     pub efec: u32,
@@ -7,6 +7,12 @@ pub struct XIDConfig {
     pub eidf2: u32
 }
 
+impl Default for XIDConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl XIDConfig {
     pub fn new() -> Self {
         Self {
@@ -16,4 +22,14 @@ impl XIDConfig {
             eidf2: 0
         }
     }
+
+    /// Store frames with extended ID `id` into dedicated RX buffer `index` (EFEC = 7)
+    pub fn rx_buffer(id: u32, index: u8) -> Self {
+        Self {
+            eft: 0,
+            efec: 7,
+            eidf1: id & 0x1FFFFFFF,
+            eidf2: index as u32 & 0x3F
+        }
+    }
 }
\ No newline at end of file
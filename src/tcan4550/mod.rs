@@ -1,3 +1,5 @@
 pub mod id_filter;
 pub mod register;
+pub mod register_map;
+pub mod bitfield;
 pub mod controller;
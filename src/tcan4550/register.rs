@@ -97,6 +97,15 @@ pub const REG_BITS_MCAN_TEST_TX_SP: u32 = 0x00000020;
 pub const REG_BITS_MCAN_TEST_TX_DOM: u32 = 0x00000040;
 pub const REG_BITS_MCAN_TEST_TX_REC: u32 = 0x00000060;
 pub const REG_BITS_MCAN_TEST_LOOP_BACK: u32 = 0x00000010;
+
+// PSR
+pub const REG_BITS_MCAN_PSR_PXE: u32 = 0x00004000;
+pub const REG_BITS_MCAN_PSR_DLEC_MASK: u32 = 0x00000700;
+pub const REG_BITS_MCAN_PSR_BO: u32 = 0x00000080;
+pub const REG_BITS_MCAN_PSR_EW: u32 = 0x00000040;
+pub const REG_BITS_MCAN_PSR_EP: u32 = 0x00000020;
+pub const REG_BITS_MCAN_PSR_ACT_MASK: u32 = 0x00000018;
+pub const REG_BITS_MCAN_PSR_LEC_MASK: u32 = 0x00000007;
   
 // CCCR
 pub const REG_BITS_MCAN_CCCR_RESERVED_MASK: u32 = 0xFFFF0C00;
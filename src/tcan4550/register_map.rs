@@ -0,0 +1,439 @@
+use std::fmt;
+
+use crate::error::{CandsError, CandsResult};
+use super::controller::configurator::bit_timing::{BitTiming, ClockRef};
+use super::register::*;
+
+/// Bit field of a register
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub name: &'static str,
+    pub lsb: u8,
+    pub width: u8,
+}
+
+impl FieldInfo {
+    pub fn get(&self, value: u32) -> u32 {
+        let mask: u32 = if self.width >= 32 { u32::MAX } else { (1 << self.width) - 1 };
+        (value >> self.lsb) & mask
+    }
+}
+
+const fn field(name: &'static str, lsb: u8, width: u8) -> FieldInfo {
+    FieldInfo { name, lsb, width }
+}
+
+const fn flag(name: &'static str, bit: u8) -> FieldInfo {
+    FieldInfo { name, lsb: bit, width: 1 }
+}
+
+/// Register name, address and bit fields. Registers without fields hold a plain value or one flag per bit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RegisterInfo {
+    pub name: &'static str,
+    pub addr: u16,
+    pub fields: &'static [FieldInfo],
+}
+
+const fn reg(name: &'static str, addr: u16, fields: &'static [FieldInfo]) -> RegisterInfo {
+    RegisterInfo { name, addr, fields }
+}
+
+const SPI_STATUS_FIELDS: [FieldInfo; 18] = [
+    flag("INTERRUPT", 0), flag("SPI_ERROR_INTERRUPT", 1), flag("INTERNAL_ERROR_INTERRUPT", 2),
+    flag("INTERNAL_ACCESS_ACTIVE", 3), flag("READ_FIFO_AVAILABLE", 4), flag("WRITE_FIFO_AVAILABLE", 5),
+    flag("READ_UNDERFLOW", 16), flag("READ_OVERFLOW", 17), flag("WRITE_UNDERFLOW", 18),
+    flag("WRITE_OVERFLOW", 19), flag("INVALID_COMMAND", 20), flag("SPI_END_ERROR", 21),
+    flag("WRITE_FIFO_OVERFLOW", 24), flag("READ_FIFO_EMPTY", 25), flag("READ_FIFO_UNDERFLOW", 26),
+    flag("INTERNAL_ERROR_LOG_WRITE", 27), flag("INTERNAL_WRITE_ERROR", 28), flag("INTERNAL_READ_ERROR", 29),
+];
+
+const MCAN_IR_FIELDS: [FieldInfo; 30] = [
+    flag("RF0N", 0), flag("RF0W", 1), flag("RF0F", 2), flag("RF0L", 3),
+    flag("RF1N", 4), flag("RF1W", 5), flag("RF1F", 6), flag("RF1L", 7),
+    flag("HPM", 8), flag("TC", 9), flag("TCF", 10), flag("TFE", 11),
+    flag("TEFN", 12), flag("TEFW", 13), flag("TEFF", 14), flag("TEFL", 15),
+    flag("TSW", 16), flag("MRAF", 17), flag("TOO", 18), flag("DRX", 19),
+    flag("BEC", 20), flag("BEU", 21), flag("ELO", 22), flag("EP", 23),
+    flag("EW", 24), flag("BO", 25), flag("WDI", 26), flag("PEA", 27),
+    flag("PED", 28), flag("ARA", 29),
+];
+
+const MCAN_IE_FIELDS: [FieldInfo; 30] = [
+    flag("RF0NE", 0), flag("RF0WE", 1), flag("RF0FE", 2), flag("RF0LE", 3),
+    flag("RF1NE", 4), flag("RF1WE", 5), flag("RF1FE", 6), flag("RF1LE", 7),
+    flag("HPME", 8), flag("TCE", 9), flag("TCFE", 10), flag("TFEE", 11),
+    flag("TEFNE", 12), flag("TEFWE", 13), flag("TEFFE", 14), flag("TEFLE", 15),
+    flag("TSWE", 16), flag("MRAFE", 17), flag("TOOE", 18), flag("DRXE", 19),
+    flag("BECE", 20), flag("BEUE", 21), flag("ELOE", 22), flag("EPE", 23),
+    flag("EWE", 24), flag("BOE", 25), flag("WDIE", 26), flag("PEAE", 27),
+    flag("PEDE", 28), flag("ARAE", 29),
+];
+
+const MCAN_ILS_FIELDS: [FieldInfo; 30] = [
+    flag("RF0NL", 0), flag("RF0WL", 1), flag("RF0FL", 2), flag("RF0LL", 3),
+    flag("RF1NL", 4), flag("RF1WL", 5), flag("RF1FL", 6), flag("RF1LL", 7),
+    flag("HPML", 8), flag("TCL", 9), flag("TCFL", 10), flag("TFEL", 11),
+    flag("TEFNL", 12), flag("TEFWL", 13), flag("TEFFL", 14), flag("TEFLL", 15),
+    flag("TSWL", 16), flag("MRAFL", 17), flag("TOOL", 18), flag("DRXL", 19),
+    flag("BECL", 20), flag("BEUL", 21), flag("ELOL", 22), flag("EPL", 23),
+    flag("EWL", 24), flag("BOL", 25), flag("WDIL", 26), flag("PEAL", 27),
+    flag("PEDL", 28), flag("ARAL", 29),
+];
+
+/// SPI, device and M_CAN registers in address order
+pub const REGISTER_MAP: &[RegisterInfo] = &[
+    reg("SPI_DEVICE_ID0", REG_SPI_DEVICE_ID0, &[]),
+    reg("SPI_DEVICE_ID1", REG_SPI_DEVICE_ID1, &[]),
+    reg("SPI_REVISION", REG_SPI_REVISION, &[
+        field("REV_ID_MINOR", 0, 8), field("REV_ID_MAJOR", 8, 8), field("SPI_2_REVISION", 24, 8),
+    ]),
+    reg("SPI_STATUS", REG_SPI_STATUS, &SPI_STATUS_FIELDS),
+    reg("SPI_ERROR_STATUS_MASK", REG_SPI_ERROR_STATUS_MASK, &SPI_STATUS_FIELDS),
+    reg("DEV_MODES_AND_PINS", REG_DEV_MODES_AND_PINS, &[
+        flag("TEST_MODE_CONFIG", 0), flag("SWE_DIS", 1), flag("DEVICE_RESET", 2), flag("WD_EN", 3),
+        field("MODE_SEL", 6, 2), flag("NWKRQ_CONFIG", 8), flag("INH_DIS", 9), field("GPIO1_GPO_CONFIG", 10, 2),
+        flag("CLKOUT_PRESCALER", 12), flag("FAIL_SAFE_EN", 13), field("GPIO1_CONFIG", 14, 2), field("WD_ACTION", 16, 2),
+        flag("WD_BIT_SET", 18), flag("NWKRQ_VOLTAGE", 19), flag("TEST_MODE_EN", 21), field("GPO2_CONFIG", 22, 2),
+        flag("CLK_REF", 27), field("WD_TIMER", 28, 2), field("WAKE_CONFIG", 30, 2),
+    ]),
+    reg("DEV_TIMESTAMP_PRESCALER", REG_DEV_TIMESTAMP_PRESCALER, &[field("PRESCALER", 0, 8)]),
+    reg("DEV_TEST_REGISTERS", REG_DEV_TEST_REGISTERS, &[]),
+    reg("DEV_IR", REG_DEV_IR, &[
+        flag("VTWD", 0), flag("M_CAN_INT", 1), flag("SWERR", 2), flag("SPIERR", 3),
+        flag("CANBUSFAULT", 4), flag("CANERR", 5), flag("NWKRQ", 6), flag("GLOBALERR", 7),
+        flag("CANDOM", 8), flag("CANSLNT", 10), flag("FRAME_OVF", 12), flag("WKERR", 13),
+        flag("LWU", 14), flag("CANINT", 15), flag("ECCERR", 16), flag("WDTO", 18),
+        flag("TSD", 19), flag("PWRON", 20), flag("UVIO", 21), flag("UVSUP", 22),
+        flag("CANBUSBAT", 24), flag("CANBUSGND", 25), flag("CANBUSOPEN", 26), flag("CANLGND", 27),
+    ]),
+    reg("DEV_IE", REG_DEV_IE, &[
+        flag("CANDOM", 8), flag("CANSLNT", 10), flag("FRAME_OVF", 12), flag("WKERR", 13),
+        flag("LWU", 14), flag("CANINT", 15), flag("ECCERR", 16), flag("WDTO", 18),
+        flag("TSD", 19), flag("PWRON", 20), flag("UVIO", 21), flag("UVSUP", 22), flag("UVCCOUT", 23),
+    ]),
+    reg("MCAN_CREL", REG_MCAN_CREL, &[
+        field("DAY", 0, 8), field("MON", 8, 8), field("YEAR", 16, 4),
+        field("SUBSTEP", 20, 4), field("STEP", 24, 4), field("REL", 28, 4),
+    ]),
+    reg("MCAN_ENDN", REG_MCAN_ENDN, &[]),
+    reg("MCAN_CUST", REG_MCAN_CUST, &[]),
+    reg("MCAN_DBTP", REG_MCAN_DBTP, &[
+        field("DSJW", 0, 4), field("DTSEG2", 4, 4), field("DTSEG1", 8, 5), field("DBRP", 16, 5), flag("TDC", 23),
+    ]),
+    reg("MCAN_TEST", REG_MCAN_TEST, &[flag("LBCK", 4), field("TX", 5, 2), flag("RX", 7)]),
+    reg("MCAN_RWD", REG_MCAN_RWD, &[field("WDC", 0, 8), field("WDV", 8, 8)]),
+    reg("MCAN_CCCR", REG_MCAN_CCCR, &[
+        flag("INIT", 0), flag("CCE", 1), flag("ASM", 2), flag("CSA", 3), flag("CSR", 4),
+        flag("MON", 5), flag("DAR", 6), flag("TEST", 7), flag("FDOE", 8), flag("BRSE", 9),
+        flag("PXHD", 12), flag("EFBI", 13), flag("TXP", 14), flag("NISO", 15),
+    ]),
+    reg("MCAN_NBTP", REG_MCAN_NBTP, &[
+        field("NTSEG2", 0, 7), field("NTSEG1", 8, 8), field("NBRP", 16, 9), field("NSJW", 25, 7),
+    ]),
+    reg("MCAN_TSCC", REG_MCAN_TSCC, &[field("TSS", 0, 2), field("TCP", 16, 4)]),
+    reg("MCAN_TSCV", REG_MCAN_TSCV, &[field("TSC", 0, 16)]),
+    reg("MCAN_TOCC", REG_MCAN_TOCC, &[flag("ETOC", 0), field("TOS", 1, 2), field("TOP", 16, 16)]),
+    reg("MCAN_TOCV", REG_MCAN_TOCV, &[field("TOC", 0, 16)]),
+    reg("MCAN_ECR", REG_MCAN_ECR, &[field("TEC", 0, 8), field("REC", 8, 7), flag("RP", 15), field("CEL", 16, 8)]),
+    reg("MCAN_PSR", REG_MCAN_PSR, &[
+        field("LEC", 0, 3), field("ACT", 3, 2), flag("EP", 5), flag("EW", 6), flag("BO", 7),
+        field("DLEC", 8, 3), flag("RESI", 11), flag("RBRS", 12), flag("RFDF", 13), flag("PXE", 14),
+        field("TDCV", 16, 7),
+    ]),
+    reg("MCAN_TDCR", REG_MCAN_TDCR, &[field("TDCF", 0, 7), field("TDCO", 8, 7)]),
+    reg("MCAN_IR", REG_MCAN_IR, &MCAN_IR_FIELDS),
+    reg("MCAN_IE", REG_MCAN_IE, &MCAN_IE_FIELDS),
+    reg("MCAN_ILS", REG_MCAN_ILS, &MCAN_ILS_FIELDS),
+    reg("MCAN_ILE", REG_MCAN_ILE, &[flag("EINT0", 0), flag("EINT1", 1)]),
+    reg("MCAN_GFC", REG_MCAN_GFC, &[flag("RRFE", 0), flag("RRFS", 1), field("ANFE", 2, 2), field("ANFS", 4, 2)]),
+    reg("MCAN_SIDFC", REG_MCAN_SIDFC, &[field("FLSSA", 0, 16), field("LSS", 16, 8)]),
+    reg("MCAN_XIDFC", REG_MCAN_XIDFC, &[field("FLESA", 0, 16), field("LSE", 16, 7)]),
+    reg("MCAN_XIDAM", REG_MCAN_XIDAM, &[field("EIDM", 0, 29)]),
+    reg("MCAN_HPMS", REG_MCAN_HPMS, &[field("BIDX", 0, 6), field("MSI", 6, 2), field("FIDX", 8, 7), flag("FLST", 15)]),
+    reg("MCAN_NDAT1", REG_MCAN_NDAT1, &[]),
+    reg("MCAN_NDAT2", REG_MCAN_NDAT2, &[]),
+    reg("MCAN_RXF0C", REG_MCAN_RXF0C, &[field("F0SA", 0, 16), field("F0S", 16, 7), field("F0WM", 24, 7), flag("F0OM", 31)]),
+    reg("MCAN_RXF0S", REG_MCAN_RXF0S, &[
+        field("F0FL", 0, 7), field("F0GI", 8, 6), field("F0PI", 16, 6), flag("F0F", 24), flag("RF0L", 25),
+    ]),
+    reg("MCAN_RXF0A", REG_MCAN_RXF0A, &[field("F0AI", 0, 6)]),
+    reg("MCAN_RXBC", REG_MCAN_RXBC, &[field("RBSA", 0, 16)]),
+    reg("MCAN_RXF1C", REG_MCAN_RXF1C, &[field("F1SA", 0, 16), field("F1S", 16, 7), field("F1WM", 24, 7), flag("F1OM", 31)]),
+    reg("MCAN_RXF1S", REG_MCAN_RXF1S, &[
+        field("F1FL", 0, 7), field("F1GI", 8, 6), field("F1PI", 16, 6), flag("F1F", 24), flag("RF1L", 25),
+        field("DMS", 30, 2),
+    ]),
+    reg("MCAN_RXF1A", REG_MCAN_RXF1A, &[field("F1AI", 0, 6)]),
+    reg("MCAN_RXESC", REG_MCAN_RXESC, &[field("F0DS", 0, 3), field("F1DS", 4, 3), field("RBDS", 8, 3)]),
+    reg("MCAN_TXBC", REG_MCAN_TXBC, &[field("TBSA", 0, 16), field("NDTB", 16, 6), field("TFQS", 24, 6), flag("TFQM", 30)]),
+    reg("MCAN_TXFQS", REG_MCAN_TXFQS, &[field("TFFL", 0, 6), field("TFGI", 8, 5), field("TFQPI", 16, 5), flag("TFQF", 21)]),
+    reg("MCAN_TXESC", REG_MCAN_TXESC, &[field("TBDS", 0, 3)]),
+    reg("MCAN_TXBRP", REG_MCAN_TXBRP, &[]),
+    reg("MCAN_TXBAR", REG_MCAN_TXBAR, &[]),
+    reg("MCAN_TXBCR", REG_MCAN_TXBCR, &[]),
+    reg("MCAN_TXBTO", REG_MCAN_TXBTO, &[]),
+    reg("MCAN_TXBCF", REG_MCAN_TXBCF, &[]),
+    reg("MCAN_TXBTIE", REG_MCAN_TXBTIE, &[]),
+    reg("MCAN_TXBCIE", REG_MCAN_TXBCIE, &[]),
+    reg("MCAN_TXEFC", REG_MCAN_TXEFC, &[field("EFSA", 0, 16), field("EFS", 16, 6), field("EFWM", 24, 6)]),
+    reg("MCAN_TXEFS", REG_MCAN_TXEFS, &[
+        field("EFFL", 0, 6), field("EFGI", 8, 5), field("EFPI", 16, 5), flag("EFF", 24), flag("TEFL", 25),
+    ]),
+    reg("MCAN_TXEFA", REG_MCAN_TXEFA, &[field("EFAI", 0, 5)]),
+];
+
+/// Register value with its decoded fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedRegister {
+    pub info: RegisterInfo,
+    pub value: u32,
+    /// Interpretation spanning several fields or registers, e.g. the bit rate of NBTP
+    pub summary: Option<String>,
+}
+
+impl DecodedRegister {
+    pub fn fields(&self) -> Vec<(&'static str, u32)> {
+        self.info.fields.iter().map(|field| (field.name, field.get(self.value))).collect()
+    }
+
+    /// Multi-bit fields and the flags that are set
+    fn describe(&self) -> String {
+        let mut parts: Vec<String> = self.info.fields
+            .iter()
+            .filter(|field| field.width > 1 || field.get(self.value) != 0)
+            .map(|field| format!("{}={}", field.name, field.get(self.value)))
+            .collect();
+        if let Some(summary) = &self.summary {
+            parts.push(format!("({})", summary));
+        }
+        parts.join(" ")
+    }
+}
+
+/// Field level change of one register between two dumps
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterDiff {
+    pub info: RegisterInfo,
+    pub before: u32,
+    pub after: u32,
+    /// Changed fields with their value before and after
+    pub fields: Vec<(&'static str, u32, u32)>,
+}
+
+impl fmt::Display for RegisterDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#06x} {:<24} {:#010x} -> {:#010x}", self.info.addr, self.info.name, self.before, self.after)?;
+        for (name, before, after) in self.fields.iter() {
+            write!(f, " {}={}->{}", name, before, after)?;
+        }
+        Ok(())
+    }
+}
+
+/// Snapshot of every register in `REGISTER_MAP`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterDump {
+    pub registers: Vec<DecodedRegister>,
+}
+
+fn timing_summary(timing: &BitTiming, clock: ClockRef, data: bool) -> String {
+    let (brp, tseg1, tseg2, sjw, bitrate) = match data {
+        false => (timing.nbrp, timing.ntseg1, timing.ntseg2, timing.nsjw, timing.nominal_bitrate(clock)),
+        true => (timing.dbrp, timing.dtseg1, timing.dtseg2, timing.dsjw, timing.data_bitrate(clock)),
+    };
+    let sample_point: f64 = 100.0 * (1 + tseg1) as f64 / (1 + tseg1 + tseg2) as f64;
+    format!(
+        "prescaler {}, tseg1 {} tq, tseg2 {} tq, sjw {} tq, {} bit/s at {} MHz, sample point {:.1} %",
+        brp, tseg1, tseg2, sjw, bitrate, clock.hz() / 1_000_000, sample_point
+    )
+}
+
+impl RegisterDump {
+    /// Decode `(address, value)` pairs. Addresses missing from `REGISTER_MAP` are ignored.
+    pub fn from_values(values: &[(u16, u32)]) -> Self {
+        let value_of = |addr: u16| values.iter().find(|(a, _)| *a == addr).map(|(_, v)| *v);
+
+        let clock: ClockRef = match value_of(REG_DEV_MODES_AND_PINS) {
+            Some(modes) if modes & REG_BITS_DEVICE_MODE_WD_CLK_40MHZ == 0 => ClockRef::Mhz20,
+            _ => ClockRef::Mhz40,
+        };
+        let timing: BitTiming = BitTiming::from_registers(
+            clock,
+            value_of(REG_MCAN_NBTP).unwrap_or(0),
+            value_of(REG_MCAN_DBTP).unwrap_or(0),
+            value_of(REG_MCAN_TDCR).unwrap_or(0),
+        );
+
+        let registers: Vec<DecodedRegister> = REGISTER_MAP
+            .iter()
+            .filter_map(|info| value_of(info.addr).map(|value| (info, value)))
+            .map(|(info, value)| {
+                let summary: Option<String> = match info.addr {
+                    REG_SPI_DEVICE_ID0 | REG_SPI_DEVICE_ID1 => {
+                        Some(format!("\"{}\"", String::from_utf8_lossy(&value.to_le_bytes())))
+                    },
+                    REG_DEV_MODES_AND_PINS => {
+                        let mode: &str = match value & REG_BITS_DEVICE_MODE_DEVICEMODE_MASK {
+                            REG_BITS_DEVICE_MODE_DEVICEMODE_SLEEP => "sleep",
+                            REG_BITS_DEVICE_MODE_DEVICEMODE_STANDBY => "standby",
+                            REG_BITS_DEVICE_MODE_DEVICEMODE_NORMAL => "normal",
+                            _ => "reserved",
+                        };
+                        Some(format!("{} mode, {} MHz reference", mode, clock.hz() / 1_000_000))
+                    },
+                    REG_MCAN_NBTP => Some(timing_summary(&timing, clock, false)),
+                    REG_MCAN_DBTP => Some(timing_summary(&timing, clock, true)),
+                    _ => None,
+                };
+                DecodedRegister { info: *info, value, summary }
+            })
+            .collect();
+        Self { registers }
+    }
+
+    pub fn get(&self, addr: u16) -> Option<&DecodedRegister> {
+        self.registers.iter().find(|register| register.info.addr == addr)
+    }
+
+    /// One line per register: address, name, value and decoded fields
+    pub fn to_table(&self) -> String {
+        self.registers
+            .iter()
+            .map(|register| {
+                let line: String = format!("{:#06x} {:<24} {:#010x}  {}", register.info.addr, register.info.name, register.value, register.describe());
+                line.trim_end().to_string() + "\n"
+            })
+            .collect()
+    }
+
+    /// JSON object with every register and all of its fields
+    pub fn to_json(&self) -> String {
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let registers: Vec<String> = self.registers
+            .iter()
+            .map(|register| {
+                let fields: Vec<String> = register.fields()
+                    .iter()
+                    .map(|(name, value)| format!("\"{}\": {}", name, value))
+                    .collect();
+                let summary: String = match &register.summary {
+                    Some(summary) => format!("\"{}\"", escape(summary)),
+                    None => "null".to_string(),
+                };
+                format!(
+                    "    {{\"name\": \"{}\", \"address\": \"{:#06x}\", \"value\": \"{:#010x}\", \"fields\": {{{}}}, \"summary\": {}}}",
+                    register.info.name, register.info.addr, register.value, fields.join(", "), summary
+                )
+            })
+            .collect();
+        format!("{{\n  \"registers\": [\n{}\n  ]\n}}\n", registers.join(",\n"))
+    }
+
+    /// Read back a dump written by `to_json`. Only the addresses and values are used, the fields are decoded again.
+    pub fn from_json(json: &str) -> CandsResult<Self> {
+        let hex_after = |key: &str, from: usize| -> Option<(u32, usize)> {
+            let start: usize = from + json[from..].find(&format!("\"{}\": \"0x", key))? + key.len() + 7;
+            let len: usize = json[start..].find('"')?;
+            let value: u32 = u32::from_str_radix(&json[start..start + len], 16).ok()?;
+            Some((value, start + len))
+        };
+
+        let mut values: Vec<(u16, u32)> = Vec::new();
+        let mut pos: usize = 0;
+        while json[pos..].contains("\"address\"") {
+            let invalid = || CandsError::InvalidConfig(format!("Malformed register dump at byte {}", pos));
+            let (addr, next) = hex_after("address", pos).ok_or_else(invalid)?;
+            let (value, next) = hex_after("value", next).ok_or_else(invalid)?;
+            values.push((addr as u16, value));
+            pos = next;
+        }
+        Ok(Self::from_values(&values))
+    }
+
+    /// Registers whose value differs from `other`, which is taken as the later dump
+    pub fn diff(&self, other: &RegisterDump) -> Vec<RegisterDiff> {
+        self.registers
+            .iter()
+            .filter_map(|before| other.get(before.info.addr).map(|after| (before, after)))
+            .filter(|(before, after)| before.value != after.value)
+            .map(|(before, after)| {
+                let fields: Vec<(&'static str, u32, u32)> = before.info.fields
+                    .iter()
+                    .map(|field| (field.name, field.get(before.value), field.get(after.value)))
+                    .filter(|(_, before, after)| before != after)
+                    .collect();
+                RegisterDiff { info: before.info, before: before.value, after: after.value, fields }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for RegisterDump {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_table())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RegisterDiff, RegisterDump, REGISTER_MAP};
+    use crate::error::CandsError;
+    use crate::tcan4550::register::*;
+
+    // NBTP of the default timing: 500 kbit/s at 40 MHz
+    const NBTP: u32 = (30 << 25) | (1 << 16) | (30 << 8) | 7;
+
+    #[test]
+    fn json_round_trip_keeps_every_register() {
+        let values: Vec<(u16, u32)> = REGISTER_MAP
+            .iter()
+            .enumerate()
+            .map(|(i, info)| (info.addr, (i as u32).wrapping_mul(0x9E3779B9)))
+            .collect();
+        let dump: RegisterDump = RegisterDump::from_values(&values);
+        assert_eq!(dump.registers.len(), REGISTER_MAP.len());
+        assert_eq!(RegisterDump::from_json(&dump.to_json()).unwrap(), dump);
+    }
+
+    #[test]
+    fn json_round_trip_keeps_the_summaries() {
+        let dump: RegisterDump = RegisterDump::from_values(&[
+            (REG_SPI_DEVICE_ID0, u32::from_le_bytes(*b"TCAN")),
+            (REG_DEV_MODES_AND_PINS, REG_BITS_DEVICE_MODE_DEVICEMODE_NORMAL | REG_BITS_DEVICE_MODE_WD_CLK_40MHZ),
+            (REG_MCAN_NBTP, NBTP),
+        ]);
+        let json: String = dump.to_json();
+        assert!(json.contains("\\\"TCAN\\\""));
+        assert!(json.contains("500000 bit/s at 40 MHz"));
+        assert_eq!(RegisterDump::from_json(&json).unwrap(), dump);
+    }
+
+    #[test]
+    fn malformed_json_is_rejected() {
+        let json: &str = "{\"registers\": [{\"name\": \"MCAN_NBTP\", \"address\": \"0x101c\", \"value\": \"0xzz\"}]}";
+        assert!(matches!(RegisterDump::from_json(json), Err(CandsError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn diff_lists_changed_fields_of_changed_registers() {
+        let before: RegisterDump = RegisterDump::from_values(&[
+            (REG_MCAN_CCCR, REG_BITS_MCAN_CCCR_INIT | REG_BITS_MCAN_CCCR_CCE),
+            (REG_MCAN_NBTP, NBTP),
+            (REG_MCAN_GFC, 0),
+        ]);
+        let after: RegisterDump = RegisterDump::from_values(&[
+            (REG_MCAN_CCCR, REG_BITS_MCAN_CCCR_FDOE),
+            (REG_MCAN_NBTP, NBTP),
+            (REG_MCAN_IE, 1),
+        ]);
+
+        let diff: Vec<RegisterDiff> = before.diff(&after);
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].info.name, "MCAN_CCCR");
+        assert_eq!((diff[0].before, diff[0].after), (0x03, 0x100));
+        assert_eq!(diff[0].fields, vec![("INIT", 1, 0), ("CCE", 1, 0), ("FDOE", 0, 1)]);
+        assert!(diff[0].to_string().ends_with("INIT=1->0 CCE=1->0 FDOE=0->1"));
+        assert!(after.diff(&after).is_empty());
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use async_io::Timer;
+use futures_lite::{stream, FutureExt, Stream};
+
+use crate::error::{CandsError, CandsResult};
+use super::rx_buffer::CanFrame;
+use super::tx_dedicated::TxBufferStatus;
+use super::TCAN455xTranceiver;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(500);
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+// Longest a single wait for nINT holds the worker, bounding the delay of operations queued meanwhile
+const RECV_WAIT_SLICE: Duration = Duration::from_millis(10);
+
+type Job = Box<dyn FnOnce(&mut TCAN455xTranceiver) + Send>;
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+fn worker_stopped() -> CandsError {
+    io::Error::new(io::ErrorKind::BrokenPipe, "Device worker stopped").into()
+}
+
+struct OneshotState<T> {
+    value: Option<T>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+/// Sending half of a single value channel, completed on the worker thread
+struct OneshotSender<T> {
+    state: Arc<Mutex<OneshotState<T>>>,
+}
+
+/// Receiving half, resolves to None if the sender is dropped without a value
+struct OneshotReceiver<T> {
+    state: Arc<Mutex<OneshotState<T>>>,
+}
+
+fn oneshot<T>() -> (OneshotSender<T>, OneshotReceiver<T>) {
+    let state = Arc::new(Mutex::new(OneshotState { value: None, waker: None, closed: false }));
+    (OneshotSender { state: state.clone() }, OneshotReceiver { state })
+}
+
+impl<T> OneshotSender<T> {
+    fn send(self, value: T) {
+        lock(&self.state).value = Some(value);
+    }
+}
+
+impl<T> Drop for OneshotSender<T> {
+    fn drop(&mut self) {
+        let mut state = lock(&self.state);
+        state.closed = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Future for OneshotReceiver<T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = lock(&self.state);
+        if let Some(value) = state.value.take() {
+            return Poll::Ready(Some(value));
+        }
+        if state.closed {
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Asynchronous front end of `TCAN455xTranceiver`
+///
+/// The tranceiver is moved onto a worker thread that performs the blocking SPI transfers,
+/// so awaiting a device operation never blocks the executor and the timeout can fire.
+/// An operation that times out may still complete on the worker afterwards.
+pub struct AsyncTCAN455x {
+    jobs: mpsc::Sender<Job>,
+    worker: JoinHandle<TCAN455xTranceiver>,
+    rx_queue: Arc<Mutex<VecDeque<CanFrame>>>,
+    timeout: Duration,
+    recv_timeout: Option<Duration>,
+    poll_interval: Duration,
+}
+
+impl AsyncTCAN455x {
+    /// Take over a tranceiver, usually after `setup`
+    pub fn new(tranceiver: TCAN455xTranceiver) -> CandsResult<Self> {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let worker = thread::Builder::new()
+            .name("cands-tcan455x".to_string())
+            .spawn(move || {
+                let mut tranceiver: TCAN455xTranceiver = tranceiver;
+                while let Ok(job) = queue.recv() {
+                    job(&mut tranceiver);
+                }
+                tranceiver
+            })?;
+
+        Ok(Self {
+            jobs,
+            worker,
+            rx_queue: Arc::new(Mutex::new(VecDeque::new())),
+            timeout: DEFAULT_TIMEOUT,
+            recv_timeout: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        })
+    }
+
+    /// Stop the worker and hand back the tranceiver
+    pub fn into_inner(self) -> CandsResult<TCAN455xTranceiver> {
+        let Self { jobs, worker, .. } = self;
+        drop(jobs);
+        worker.join().map_err(|_| worker_stopped())
+    }
+
+    /// Timeout of a single device operation
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    pub fn get_timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Time `recv` waits for a frame. None waits forever.
+    pub fn set_recv_timeout(&mut self, timeout: Option<Duration>) {
+        self.recv_timeout = timeout;
+    }
+
+    pub fn get_recv_timeout(&self) -> Option<Duration> {
+        self.recv_timeout
+    }
+
+    /// Interval between retries while the TX FIFO is full, and between polls of a dedicated TX buffer
+    pub fn set_poll_interval(&mut self, interval: Duration) {
+        self.poll_interval = interval;
+    }
+
+    pub fn get_poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    /// Run `f` on the worker thread, failing with `CandsError::Timeout` after the operation timeout
+    pub async fn call<T, F>(&self, f: F) -> CandsResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut TCAN455xTranceiver) -> CandsResult<T> + Send + 'static,
+    {
+        let (sender, receiver) = oneshot::<CandsResult<T>>();
+        let job: Job = Box::new(move |tranceiver| sender.send(f(tranceiver)));
+        self.jobs.send(job).map_err(|_| worker_stopped())?;
+
+        let result = async {
+            match receiver.await {
+                Some(result) => result,
+                None => Err(worker_stopped()),
+            }
+        };
+        let timeout = async {
+            Timer::after(self.timeout).await;
+            Err(CandsError::Timeout)
+        };
+        result.or(timeout).await
+    }
+
+    /// Queue a frame for transmission, waiting for a free TX FIFO element within the operation timeout
+    pub async fn send(&self, frame: &CanFrame) -> CandsResult<()> {
+        let transmit = async {
+            loop {
+                let frame: CanFrame = frame.clone();
+                match self.call(move |t| t.transmit_frame(&frame)).await {
+                    Err(CandsError::TxFifoFull) => { Timer::after(self.poll_interval).await; },
+                    result => return result,
+                }
+            }
+        };
+        let timeout = async {
+            Timer::after(self.timeout).await;
+            Err(CandsError::Timeout)
+        };
+        transmit.or(timeout).await
+    }
+
+    /// Wait until the request of dedicated TX buffer `index` completes, within the operation timeout
+    pub async fn wait_tx_buffer(&self, index: u8) -> CandsResult<TxBufferStatus> {
+        let complete = async {
+            loop {
+                let status: TxBufferStatus = self.call(move |t| t.read_tx_buffer_status(index)).await?;
+                if status.is_complete() {
+                    return Ok(status);
+                }
+                Timer::after(self.poll_interval).await;
+            }
+        };
+        let timeout = async {
+            Timer::after(self.timeout).await;
+            Err(CandsError::Timeout)
+        };
+        complete.or(timeout).await
+    }
+
+    /// Next received frame, in FIFO0 then FIFO1 order per read.
+    ///
+    /// The worker sleeps on nINT while no frame is pending. Frames it reads are queued on this
+    /// front end, so a `recv` that times out leaves them for the next call.
+    pub async fn recv(&self) -> CandsResult<CanFrame> {
+        let receive = async {
+            loop {
+                if let Some(frame) = lock(&self.rx_queue).pop_front() {
+                    return Ok(frame);
+                }
+                let rx_queue: Arc<Mutex<VecDeque<CanFrame>>> = self.rx_queue.clone();
+                self.call(move |t| {
+                    match t.wait_for_frame(Some(RECV_WAIT_SLICE)) {
+                        Ok(frames) => lock(&rx_queue).extend(frames),
+                        Err(CandsError::Timeout) => {},
+                        Err(e) => return Err(e),
+                    }
+                    Ok(())
+                }).await?;
+            }
+        };
+        match self.recv_timeout {
+            Some(timeout) => {
+                let timeout = async {
+                    Timer::after(timeout).await;
+                    Err(CandsError::Timeout)
+                };
+                receive.or(timeout).await
+            },
+            None => receive.await,
+        }
+    }
+
+    /// Endless stream of received frames. Errors are yielded and do not end the stream.
+    pub fn frames(&self) -> impl Stream<Item = CandsResult<CanFrame>> + '_ {
+        stream::unfold(self, |s| async move { Some((s.recv().await, s)) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use async_io::block_on;
+
+    use crate::device_driver::simulator::SimulatedTCAN4550;
+    use crate::error::CandsError;
+    use crate::tranceiver::TCAN455xTranceiver;
+    use crate::tranceiver::rx_buffer::CanFrame;
+    use super::AsyncTCAN455x;
+
+    fn open(sim: &SimulatedTCAN4550) -> AsyncTCAN455x {
+        let mut tranceiver: TCAN455xTranceiver = TCAN455xTranceiver::new_simulated(sim.clone());
+        tranceiver.setup(&[], &[]).unwrap();
+        let mut device: AsyncTCAN455x = AsyncTCAN455x::new(tranceiver).unwrap();
+        device.set_recv_timeout(Some(Duration::from_millis(50)));
+        device
+    }
+
+    #[test]
+    fn recv_returns_frames_sent_in_loopback() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        sim.set_loopback(true);
+        let device: AsyncTCAN455x = open(&sim);
+
+        block_on(async {
+            device.send(&CanFrame::new(0x123, false, &[1, 2])).await.unwrap();
+            device.send(&CanFrame::new(0x124, false, &[3])).await.unwrap();
+            assert_eq!(device.recv().await.unwrap().data, vec![1, 2]);
+            assert_eq!(device.recv().await.unwrap().data, vec![3]);
+        });
+    }
+
+    #[test]
+    fn recv_timeout_keeps_frames_for_the_next_call() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let device: AsyncTCAN455x = open(&sim);
+
+        block_on(async {
+            // Waiting on an idle bus does not touch SPI
+            let transactions: usize = sim.spi_transactions();
+            assert!(matches!(device.recv().await, Err(CandsError::Timeout)));
+            assert_eq!(sim.spi_transactions(), transactions);
+
+            sim.inject_frame(&CanFrame::new(0x200, false, &[7]));
+            sim.inject_frame(&CanFrame::new(0x201, false, &[8]));
+            assert_eq!(device.recv().await.unwrap().id, 0x200);
+            assert_eq!(device.recv().await.unwrap().id, 0x201);
+            assert!(matches!(device.recv().await, Err(CandsError::Timeout)));
+        });
+    }
+}
@@ -0,0 +1,183 @@
+use crate::error::CandsResult;
+use crate::tcan4550::{bitfield::Register, controller::TCAN455xController};
+
+// The length field of a read or write command counts words in a single byte
+const MAX_BURST_WORDS: usize = 255;
+
+// Opcode and address of a write command
+const WRITE_B_FL: u8 = 0x61;
+const COMMAND_HEADER_LEN: usize = 4;
+
+/// (address, word) pairs of concatenated write commands
+pub(crate) fn command_words(data: &[u8]) -> Vec<(u16, u32)> {
+    let mut words: Vec<(u16, u32)> = Vec::new();
+    let mut pos: usize = 0;
+    while pos + COMMAND_HEADER_LEN <= data.len() && data[pos] == WRITE_B_FL {
+        let addr: u16 = u16::from_be_bytes([data[pos + 1], data[pos + 2]]);
+        let len: usize = data[pos + 3] as usize;
+        pos += COMMAND_HEADER_LEN;
+        for (i, word) in data[pos..].chunks_exact(4).take(len).enumerate() {
+            words.push((addr.wrapping_add(4 * i as u16), u32::from_be_bytes([word[0], word[1], word[2], word[3]])));
+        }
+        pos += 4 * len;
+    }
+    words
+}
+
+/// Register and MRAM writes gathered for `TCAN455xTranceiver::write_batch`
+///
+/// Writes keep their order. Consecutive writes to adjacent addresses become one burst.
+/// The TCAN4550 ends a command when nCS goes high, so each burst is sent as its own SPI transaction.
+#[derive(Debug, Clone, Default)]
+pub struct WriteBatch {
+    words: Vec<(u16, u32)>,
+}
+
+impl WriteBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write(&mut self, addr: u16, word: u32) {
+        self.words.push((addr, word));
+    }
+
+    /// Write `words` to consecutive addresses starting at `addr`
+    pub fn write_words(&mut self, addr: u16, words: &[u32]) {
+        for (i, word) in words.iter().enumerate() {
+            self.words.push((addr.wrapping_add(4 * i as u16), *word));
+        }
+    }
+
+    pub fn push_register<R: Register>(&mut self, reg: &R) {
+        self.write(R::ADDR, reg.to_u32());
+    }
+
+    /// Add the words of a write command built by `TCAN455xController`
+    pub fn push_command(&mut self, cmd: &[u8]) {
+        self.words.extend(command_words(cmd));
+    }
+
+    /// Number of words
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Write commands with runs of adjacent addresses merged into bursts of at most `max_words`
+    pub fn commands(&self, max_words: usize) -> Vec<Vec<u8>> {
+        let max_words: usize = max_words.clamp(1, MAX_BURST_WORDS);
+        let mut commands: Vec<Vec<u8>> = Vec::new();
+        let mut start: u16 = 0;
+        let mut burst: Vec<u32> = Vec::new();
+        for &(addr, word) in &self.words {
+            let adjacent: bool = !burst.is_empty() && addr == start.wrapping_add(4 * burst.len() as u16);
+            if !adjacent || burst.len() >= max_words {
+                if !burst.is_empty() {
+                    commands.push(TCAN455xController::generate_write_command(start, std::mem::take(&mut burst)));
+                }
+                start = addr;
+            }
+            burst.push(word);
+        }
+        if !burst.is_empty() {
+            commands.push(TCAN455xController::generate_write_command(start, burst));
+        }
+        commands
+    }
+}
+
+impl super::TCAN455xTranceiver {
+
+    /// Words per read or write burst within the driver's transfer limit
+    pub(super) fn max_burst_words(&self) -> usize {
+        let max_transfer: usize = self.driver.tcan455x_max_transfer();
+        (max_transfer.saturating_sub(COMMAND_HEADER_LEN) / 4).clamp(1, MAX_BURST_WORDS)
+    }
+
+    /// Send `batch` with one SPI transaction per run of adjacent addresses, split at the driver's transfer limit
+    pub fn write_batch(&mut self, batch: &WriteBatch) -> CandsResult<()> {
+        for cmd in batch.commands(self.max_burst_words()) {
+            self.write(&cmd)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{command_words, WriteBatch};
+    use crate::tcan4550::controller::TCAN455xController;
+
+    fn write_command(addr: u16, words: &[u32]) -> Vec<u8> {
+        TCAN455xController::generate_write_command(addr, words.to_vec())
+    }
+
+    #[test]
+    fn adjacent_addresses_are_merged_into_one_burst() {
+        let mut batch: WriteBatch = WriteBatch::new();
+        batch.write(0x1000, 1);
+        batch.write(0x1004, 2);
+        batch.write_words(0x1008, &[3, 4]);
+        assert_eq!(batch.len(), 4);
+        assert_eq!(batch.commands(255), vec![write_command(0x1000, &[1, 2, 3, 4])]);
+    }
+
+    #[test]
+    fn bursts_split_at_non_adjacent_addresses_and_keep_the_order() {
+        let mut batch: WriteBatch = WriteBatch::new();
+        batch.write(0x1000, 1);
+        batch.write(0x1004, 2);
+        batch.write(0x1010, 3);
+        batch.write(0x100C, 4);
+        batch.write(0x1010, 5);
+        assert_eq!(batch.commands(255), vec![
+            write_command(0x1000, &[1, 2]),
+            write_command(0x1010, &[3]),
+            write_command(0x100C, &[4, 5]),
+        ]);
+    }
+
+    #[test]
+    fn bursts_split_at_the_word_limit() {
+        let words: Vec<u32> = (0..10).collect();
+        let mut batch: WriteBatch = WriteBatch::new();
+        batch.write_words(0x8000, &words);
+        assert_eq!(batch.commands(4), vec![
+            write_command(0x8000, &words[0..4]),
+            write_command(0x8010, &words[4..8]),
+            write_command(0x8020, &words[8..10]),
+        ]);
+        assert_eq!(batch.commands(0).len(), 10);
+    }
+
+    #[test]
+    fn bursts_never_exceed_the_one_byte_length_field() {
+        let words: Vec<u32> = (0..300).collect();
+        let mut batch: WriteBatch = WriteBatch::new();
+        batch.write_words(0x8000, &words);
+        let commands: Vec<Vec<u8>> = batch.commands(1000);
+        assert_eq!(commands, vec![
+            write_command(0x8000, &words[0..255]),
+            write_command(0x8000 + 4 * 255, &words[255..300]),
+        ]);
+        assert_eq!(commands[0][3], 255);
+    }
+
+    #[test]
+    fn command_words_reads_back_generated_write_commands() {
+        let mut data: Vec<u8> = write_command(0x1000, &[0x11223344, 0x55667788]);
+        data.extend(write_command(0x8000, &[0xDEADBEEF]));
+        assert_eq!(command_words(&data), vec![(0x1000, 0x11223344), (0x1004, 0x55667788), (0x8000, 0xDEADBEEF)]);
+
+        let mut batch: WriteBatch = WriteBatch::new();
+        batch.push_command(&data);
+        assert_eq!(batch.commands(255), vec![write_command(0x1000, &[0x11223344, 0x55667788]), write_command(0x8000, &[0xDEADBEEF])]);
+
+        // A read command is not a write
+        assert!(command_words(&[0x41, 0x10, 0x00, 0x01]).is_empty());
+    }
+}
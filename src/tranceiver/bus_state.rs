@@ -0,0 +1,234 @@
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::error::CandsResult;
+use crate::tcan4550::{controller::TCAN455xController, register::*};
+
+// MCAN interrupts reporting error counter and protocol state changes
+pub(crate) const MCAN_IR_ERROR_FLAGS: u32 = REG_BITS_MCAN_IR_BO
+    | REG_BITS_MCAN_IR_EW
+    | REG_BITS_MCAN_IR_EP
+    | REG_BITS_MCAN_IR_PEA
+    | REG_BITS_MCAN_IR_PED
+    | REG_BITS_MCAN_IR_ELO;
+
+pub(crate) const MCAN_IE_ERROR_FLAGS: u32 = REG_BITS_MCAN_IE_BOE
+    | REG_BITS_MCAN_IE_EWE
+    | REG_BITS_MCAN_IE_EPE
+    | REG_BITS_MCAN_IE_PEAE
+    | REG_BITS_MCAN_IE_PEDE
+    | REG_BITS_MCAN_IE_ELOE;
+
+/// Fault confinement state of the node
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BusState {
+    ErrorActive,
+    /// Error active with a counter at or above the warning limit of 96
+    ErrorWarning,
+    ErrorPassive,
+    BusOff,
+}
+
+/// Protocol error type of LEC/DLEC
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LastErrorCode {
+    NoError,
+    StuffError,
+    FormError,
+    AckError,
+    Bit1Error,
+    Bit0Error,
+    CrcError,
+    /// No CAN bus event since the last read of PSR
+    NoChange,
+}
+
+impl LastErrorCode {
+    pub fn from_u32(code: u32) -> Self {
+        match code & 0x07 {
+            0 => LastErrorCode::NoError,
+            1 => LastErrorCode::StuffError,
+            2 => LastErrorCode::FormError,
+            3 => LastErrorCode::AckError,
+            4 => LastErrorCode::Bit1Error,
+            5 => LastErrorCode::Bit0Error,
+            6 => LastErrorCode::CrcError,
+            _ => LastErrorCode::NoChange,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        !matches!(self, LastErrorCode::NoError | LastErrorCode::NoChange)
+    }
+}
+
+/// Communication state of PSR.ACT
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Activity {
+    Synchronizing,
+    Idle,
+    Receiver,
+    Transmitter,
+}
+
+impl Activity {
+    pub fn from_u32(code: u32) -> Self {
+        match code & 0x03 {
+            0 => Activity::Synchronizing,
+            1 => Activity::Idle,
+            2 => Activity::Receiver,
+            _ => Activity::Transmitter,
+        }
+    }
+}
+
+/// Snapshot of ECR and PSR
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ErrorState {
+    /// Transmit error counter
+    pub tec: u8,
+    /// Receive error counter
+    pub rec: u8,
+    /// Receive error passive
+    pub rp: bool,
+    /// CAN error logging counter
+    pub cel: u8,
+    /// Last error code in the arbitration phase
+    pub lec: LastErrorCode,
+    /// Last error code in the data phase
+    pub dlec: LastErrorCode,
+    pub act: Activity,
+    /// Error passive
+    pub ep: bool,
+    /// Warning status
+    pub ew: bool,
+    /// Bus off status
+    pub bo: bool,
+    /// Protocol exception event
+    pub pxe: bool,
+}
+
+impl ErrorState {
+    pub fn from_registers(ecr: u32, psr: u32) -> Self {
+        Self {
+            tec: (ecr & 0xFF) as u8,
+            rec: ((ecr >> 8) & 0x7F) as u8,
+            rp: (ecr >> 15) & 0x01 != 0,
+            cel: ((ecr >> 16) & 0xFF) as u8,
+            lec: LastErrorCode::from_u32(psr),
+            act: Activity::from_u32(psr >> 3),
+            ep: (psr >> 5) & 0x01 != 0,
+            ew: (psr >> 6) & 0x01 != 0,
+            bo: (psr >> 7) & 0x01 != 0,
+            dlec: LastErrorCode::from_u32(psr >> 8),
+            pxe: (psr >> 14) & 0x01 != 0,
+        }
+    }
+
+    pub fn bus_state(&self) -> BusState {
+        if self.bo {
+            BusState::BusOff
+        } else if self.ep {
+            BusState::ErrorPassive
+        } else if self.ew {
+            BusState::ErrorWarning
+        } else {
+            BusState::ErrorActive
+        }
+    }
+}
+
+/// Error event raised from the MCAN error interrupts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BusEvent {
+    StateChanged { from: BusState, to: BusState, state: ErrorState },
+    /// Protocol error in the arbitration phase (PEA) or data phase (PED)
+    ProtocolError { data_phase: bool, code: LastErrorCode, state: ErrorState },
+    /// CAN error logging counter overflowed
+    ErrorLogOverflow { state: ErrorState },
+    /// Automatic bus off recovery attempt planned after `delay`
+    RecoveryScheduled { attempt: u32, delay: Duration },
+    /// INIT cleared, waiting for the recovery sequence
+    RecoveryStarted { attempt: u32 },
+    Recovered { attempt: u32 },
+    /// Still bus off after the recovery sequence
+    RecoveryFailed { attempt: u32 },
+    /// Retry limit of the backoff policy reached, recovery is left to `recover_bus_off`
+    RecoveryAbandoned { attempts: u32 },
+}
+
+impl super::TCAN455xTranceiver {
+
+    /// Read ECR and PSR. Reading clears LEC, DLEC, PXE and CEL on the device.
+    pub fn read_error_state(&mut self) -> CandsResult<ErrorState> {
+        let ecr: u32 = self.read_device(REG_MCAN_ECR)?;
+        let psr: u32 = self.read_device(REG_MCAN_PSR)?;
+        Ok(ErrorState::from_registers(ecr, psr))
+    }
+
+    /// Read the error state and report a bus state transition, for use without error interrupts
+    pub fn poll_bus_state(&mut self) -> CandsResult<ErrorState> {
+        let state: ErrorState = self.read_error_state()?;
+        self.update_bus_state(state);
+        self.poll_bus_off_recovery()?;
+        Ok(state)
+    }
+
+    /// Last bus state seen by `poll_bus_state` or the error interrupts
+    pub fn get_bus_state(&self) -> BusState {
+        self.bus_state
+    }
+
+    /// Enable BO, EW, EP, PEA, PED and ELO interrupts on the next `setup` or `configure_mcan_irq`
+    pub fn set_error_interrupts(&mut self, enable: bool) {
+        self.error_interrupts = enable;
+    }
+
+    /// Channel of bus events, replacing any previous subscriber. Events are raised by `receive`.
+    pub fn bus_events(&mut self) -> mpsc::Receiver<BusEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.bus_event_sender = Some(sender);
+        receiver
+    }
+
+    pub(crate) fn emit_bus_event(&mut self, event: BusEvent) {
+        // Drop the sender once the receiver is gone
+        if self.bus_event_sender.as_ref().is_some_and(|sender| sender.send(event).is_err()) {
+            self.bus_event_sender = None;
+        }
+    }
+
+    pub(crate) fn update_bus_state(&mut self, state: ErrorState) {
+        let bus_state: BusState = state.bus_state();
+        if bus_state != self.bus_state {
+            let from: BusState = self.bus_state;
+            self.bus_state = bus_state;
+            self.emit_bus_event(BusEvent::StateChanged { from, to: bus_state, state });
+            self.on_bus_state_changed(from, bus_state);
+        }
+    }
+
+    /// Clear the error interrupts in `mcan_ir` and report what they indicate
+    pub(crate) fn handle_error_interrupts(&mut self, mcan_ir: u32) -> CandsResult<Option<ErrorState>> {
+        let flags: u32 = mcan_ir & MCAN_IR_ERROR_FLAGS;
+        if flags == 0 {
+            return Ok(None);
+        }
+
+        let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_IR, vec![flags]);
+        self.write(&cmd)?;
+
+        let state: ErrorState = self.read_error_state()?;
+        self.update_bus_state(state);
+        if flags & REG_BITS_MCAN_IR_PEA != 0 {
+            self.emit_bus_event(BusEvent::ProtocolError { data_phase: false, code: state.lec, state });
+        }
+        if flags & REG_BITS_MCAN_IR_PED != 0 {
+            self.emit_bus_event(BusEvent::ProtocolError { data_phase: true, code: state.dlec, state });
+        }
+        if flags & REG_BITS_MCAN_IR_ELO != 0 {
+            self.emit_bus_event(BusEvent::ErrorLogOverflow { state });
+        }
+        Ok(Some(state))
+    }
+}
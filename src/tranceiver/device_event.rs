@@ -0,0 +1,98 @@
+use std::sync::mpsc;
+
+use crate::error::CandsResult;
+use crate::tcan4550::{bitfield::{DevIr, SpiStatus}, register::*};
+
+// M_CAN_INT follows MCAN_IR and SPIERR follows SPI_STATUS, both clear with their source
+const DEV_IR_SUMMARY_FLAGS: u32 = REG_BITS_DEVICE_IR_M_CAN_INT | REG_BITS_DEVICE_IR_SPIERR;
+
+// Flags with their own reader, kept for `take_watchdog_timeout`, `read_wake_reason` and `wait_for_wake` once cleared here
+const DEV_IR_PENDING_FLAGS: u32 = REG_BITS_DEVICE_IR_WDTO | REG_BITS_DEVICE_IR_CANINT | REG_BITS_DEVICE_IR_LWU;
+
+// Internal access active, read FIFO available and write FIFO available describe the current state, not an event
+const SPI_STATUS_STATE_FLAGS: u32 = 0x00000038;
+
+/// Flags raised in DEV_IR and SPI_STATUS since the previous poll
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DeviceEvent {
+    pub dev_ir: DevIr,
+    pub spi_status: SpiStatus,
+}
+
+impl DevIr {
+    pub fn is_empty(&self) -> bool {
+        self.to_u32() == 0
+    }
+
+    /// Undervoltage on VSUP or VIO, or thermal shutdown
+    pub fn supply_fault(&self) -> bool {
+        self.uvsup || self.uvio || self.tsd
+    }
+
+    /// CANH/CANL shorted to battery or ground, or open
+    pub fn can_bus_fault(&self) -> bool {
+        self.canbusbat || self.canbusgnd || self.canbusopen || self.canlgnd || self.candom
+    }
+}
+
+impl SpiStatus {
+    pub fn is_empty(&self) -> bool {
+        self.to_u32() == 0
+    }
+
+    /// Invalid command or a transfer of the wrong length
+    pub fn spi_fault(&self) -> bool {
+        self.invalid_command || self.spi_end_error
+            || self.read_underflow || self.read_overflow || self.write_underflow || self.write_overflow
+    }
+}
+
+impl super::TCAN455xTranceiver {
+
+    /// Channel of device events, replacing any previous subscriber.
+    ///
+    /// While subscribed, `receive` reports and clears DEV_IR and SPI_STATUS on every poll.
+    /// `wait_for_frame` always does, since the flags hold nINT low.
+    pub fn device_events(&mut self) -> mpsc::Receiver<DeviceEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.device_event_sender = Some(sender);
+        receiver
+    }
+
+    fn emit_device_event(&mut self, event: DeviceEvent) {
+        // Drop the sender once the receiver is gone
+        if self.device_event_sender.as_ref().is_some_and(|sender| sender.send(event).is_err()) {
+            self.device_event_sender = None;
+        }
+    }
+
+    /// Clear the flags of `dev_ir` and SPI_STATUS that were set and report them.
+    ///
+    /// Only the flags read are written back, so one raised in between is reported by the next poll.
+    /// WDTO, CANINT and LWU stay pending on the tranceiver until their reader takes them.
+    pub(crate) fn handle_device_events(&mut self, dev_ir: u32) -> CandsResult<Option<DeviceEvent>> {
+        let dev_ir: u32 = dev_ir & !DEV_IR_SUMMARY_FLAGS;
+        if dev_ir != 0 {
+            self.clear_device_irq_flags(dev_ir)?;
+            self.pending_dev_ir |= dev_ir & DEV_IR_PENDING_FLAGS;
+        }
+
+        let spi_status: u32 = self.read_spi_status()? & !SPI_STATUS_STATE_FLAGS;
+        if spi_status != 0 {
+            self.write_reg(SpiStatus::from_u32(spi_status))?;
+        }
+
+        if dev_ir == 0 && spi_status == 0 {
+            return Ok(None);
+        }
+        let event: DeviceEvent = DeviceEvent { dev_ir: DevIr::from_u32(dev_ir), spi_status: SpiStatus::from_u32(spi_status) };
+        self.emit_device_event(event);
+        Ok(Some(event))
+    }
+
+    /// Read DEV_IR and SPI_STATUS, clear and report what was set
+    pub fn poll_device_events(&mut self) -> CandsResult<Option<DeviceEvent>> {
+        let dev_ir: u32 = self.read_device_irq()?;
+        self.handle_device_events(dev_ir)
+    }
+}
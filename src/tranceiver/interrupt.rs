@@ -0,0 +1,89 @@
+use std::time::{Duration, Instant};
+
+use crate::error::{CandsError, CandsResult};
+use crate::tcan4550::{controller::TCAN455xController, register::*};
+use super::bus_state::MCAN_IR_ERROR_FLAGS;
+use super::rx_buffer::CanFrame;
+use super::tx_event::MCAN_IR_TX_EVENT_FLAGS;
+
+// MCAN interrupts cleared by the handlers `receive` runs
+const MCAN_IR_HANDLED_FLAGS: u32 = MCAN_IR_ERROR_FLAGS
+    | MCAN_IR_TX_EVENT_FLAGS
+    | REG_BITS_MCAN_IR_RF0N
+    | REG_BITS_MCAN_IR_RF1N
+    | REG_BITS_MCAN_IR_DRX
+    | REG_BITS_MCAN_IR_HPM
+    | REG_BITS_MCAN_IR_TSW;
+
+impl super::TCAN455xTranceiver {
+
+    /// Sleep until nINT asserts and return the received frames.
+    ///
+    /// No SPI access happens while the line is idle. A driver without nINT wakes at a fixed
+    /// interval and polls over SPI instead. Fails with `CandsError::Timeout` if no frame arrives
+    /// within `timeout`; None waits forever.
+    pub fn wait_for_frame(&mut self, timeout: Option<Duration>) -> CandsResult<Vec<CanFrame>> {
+        let deadline: Option<Instant> = timeout.map(|t| Instant::now() + t);
+        loop {
+            let remaining: Option<Duration> = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+            // A bus off node raises no interrupt, so wake up for a scheduled recovery attempt
+            let recovery: Option<Duration> = self.bus_off_recovery_pending();
+            let wait: Option<Duration> = match (remaining, recovery) {
+                (Some(remaining), Some(recovery)) => Some(remaining.min(recovery)),
+                (remaining, recovery) => remaining.or(recovery),
+            };
+            if !self.driver.wait_for_interrupt(wait)? {
+                if self.bus_off_recovery_pending().is_some_and(|recovery| recovery.is_zero()) {
+                    self.poll_bus_off_recovery()?;
+                    continue;
+                }
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    return Err(CandsError::Timeout);
+                }
+            }
+
+            let frames: Vec<CanFrame> = self.receive_frames()?;
+            self.clear_unhandled_interrupts()?;
+            if !frames.is_empty() {
+                return Ok(frames);
+            }
+
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Err(CandsError::Timeout);
+            }
+        }
+    }
+
+    /// Clear the device flags and the MCAN interrupts no handler takes, such as RF0F or RF0L.
+    ///
+    /// Any of them left set holds nINT low and would wake every following wait at once.
+    fn clear_unhandled_interrupts(&mut self) -> CandsResult<()> {
+        let dev_ir: u32 = self.read_device_irq()?;
+        self.handle_device_events(dev_ir)?;
+        if dev_ir & REG_BITS_DEVICE_IR_M_CAN_INT == 0 {
+            return Ok(());
+        }
+
+        let unhandled: u32 = self.read_device(REG_MCAN_IR)? & !MCAN_IR_HANDLED_FLAGS;
+        if unhandled != 0 {
+            let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_IR, vec![unhandled]);
+            self.write(&cmd)?;
+        }
+        Ok(())
+    }
+
+    /// Call `callback` each time nINT asserts.
+    ///
+    /// It runs on the GPIO interrupt thread, so it should only signal the thread owning the tranceiver.
+    pub fn set_event_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut() + Send + 'static,
+    {
+        self.driver.set_interrupt_callback(Some(Box::new(callback)));
+    }
+
+    pub fn clear_event_callback(&mut self) {
+        self.driver.set_interrupt_callback(None);
+    }
+}
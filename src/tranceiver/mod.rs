@@ -7,7 +7,10 @@ pub mod raspberrypi;
 #[cfg(feature="raspberrypi_cm")]
 pub mod raspberrypi_cm;
 
-use std::{io, time::Duration};
+#[cfg(any(test, feature="simulator"))]
+pub mod simulator;
+
+use std::{io, sync::mpsc, time::Duration};
 use futures_lite::FutureExt;
 use async_io::{block_on, Timer};
 
@@ -17,162 +20,285 @@ use crate::device_driver::DeviceDriver;
 #[cfg(any(feature="raspberrypi", feature="raspberrypi_cm"))]
 use crate::device_driver::RaspiDeviceDriver;
 
+use crate::error::{CandsError, CandsResult};
 use crate::tcan4550::{
-    controller::{configurator::mram::*, TCAN455xController}, id_filter::{SIDConfig, XIDConfig}, register::*
+    bitfield::{DevModesAndPins, McanCccr, McanTxfqs}, controller::{configurator::{mram::*, bit_timing::{BitTiming, ClockRef}, global_filter::GlobalFilterConfig, modes_and_pins::DeviceConfig, timestamp::TimestampConfig}, TCAN455xController}, id_filter::{check_filter_capacity, SIDConfig, XIDConfig}, register::*
 };
 
 pub mod rx_buffer;
-use rx_buffer::RxData;
+use rx_buffer::{RxData, CanFrame};
+
+pub mod tx_buffer;
+
+pub mod rx_dedicated;
+
+pub mod asynchronous;
+
+pub mod interrupt;
+
+pub mod bus_state;
+use bus_state::{BusEvent, BusState, MCAN_IE_ERROR_FLAGS};
+
+pub mod recovery;
+use recovery::{BusOffRecovery, RecoveryState};
+
+pub mod tx_dedicated;
+
+pub mod priority;
+use priority::HighPriorityMessage;
+
+pub mod tx_event;
+use tx_event::{TxEvent, MCAN_IE_TX_EVENT_FLAGS};
+
+pub mod timestamp;
+use timestamp::TimestampSync;
+
+pub mod register_dump;
+
+pub mod register_access;
+
+pub mod verify;
+
+pub mod watchdog;
+
+pub mod power;
+
+pub mod device_event;
+use device_event::DeviceEvent;
+
+pub mod batch;
+use batch::WriteBatch;
+
+#[cfg(not(any(feature="raspberrypi", feature="raspberrypi_cm")))]
+type BoxedDriver = Box<dyn DeviceDriver + Send>;
+
+#[cfg(any(feature="raspberrypi", feature="raspberrypi_cm"))]
+type BoxedDriver = Box<dyn RaspiDeviceDriver + Send>;
 
 /// CAN Tranceiver
 pub struct TCAN455xTranceiver {
-    #[cfg(not(any(feature="raspberrypi", feature="raspberrypi_cm")))]
-    driver: Box<dyn DeviceDriver + Send>,
-    #[cfg(any(feature="raspberrypi", feature="raspberrypi_cm"))]
-    driver: Box<dyn RaspiDeviceDriver + Send>,
+    driver: BoxedDriver,
+    bit_timing: BitTiming,
+    mram_layout: MramLayout,
+    pending_mram_layout: MramLayout,
+    global_filter: GlobalFilterConfig,
+    error_interrupts: bool,
+    bus_state: BusState,
+    bus_event_sender: Option<mpsc::Sender<BusEvent>>,
+    bus_off_recovery: BusOffRecovery,
+    recovery: RecoveryState,
+    tx_event_interrupts: bool,
+    tx_event_sender: Option<mpsc::Sender<TxEvent>>,
+    high_priority_interrupts: bool,
+    high_priority_sender: Option<mpsc::Sender<HighPriorityMessage>>,
+    timestamp: TimestampConfig,
+    timestamp_interrupts: bool,
+    timestamp_sync: Option<TimestampSync>,
+    verify_setup: bool,
+    device_config: DeviceConfig,
+    watchdog_expired: bool,
+    sid_filters: Vec<SIDConfig>,
+    xid_filters: Vec<XIDConfig>,
+    device_event_sender: Option<mpsc::Sender<DeviceEvent>>,
+    pending_dev_ir: u32,
 }
 
 impl TCAN455xTranceiver {
 
-    pub fn write(&mut self, data: &[u8]) -> io::Result<usize> {
-        match self.driver.tcan455x_write(data) {
-            Ok(size) => Ok(size),
-            Err(_) => Err(io::ErrorKind::ConnectionRefused.into())
+    #[cfg(any(test, feature="simulator", feature="usb-ftdi", feature="raspberrypi", feature="raspberrypi_cm"))]
+    fn from_driver(driver: BoxedDriver) -> Self {
+        Self {
+            driver,
+            bit_timing: BitTiming::default(),
+            mram_layout: MramLayout::default(),
+            pending_mram_layout: MramLayout::default(),
+            global_filter: GlobalFilterConfig::default(),
+            error_interrupts: false,
+            bus_state: BusState::ErrorActive,
+            bus_event_sender: None,
+            bus_off_recovery: BusOffRecovery::default(),
+            recovery: RecoveryState::default(),
+            tx_event_interrupts: false,
+            tx_event_sender: None,
+            high_priority_interrupts: false,
+            high_priority_sender: None,
+            timestamp: TimestampConfig::default(),
+            timestamp_interrupts: false,
+            timestamp_sync: None,
+            verify_setup: false,
+            device_config: DeviceConfig::default(),
+            watchdog_expired: false,
+            sid_filters: Vec::new(),
+            xid_filters: Vec::new(),
+            device_event_sender: None,
+            pending_dev_ir: 0,
         }
     }
 
-    pub fn read(&mut self, addr: u16, len: u8) -> io::Result<Vec<u8>> {
+    pub fn write(&mut self, data: &[u8]) -> CandsResult<usize> {
+        self.driver.tcan455x_write(data)
+    }
+
+    pub fn read(&mut self, addr: u16, len: u8) -> CandsResult<Vec<u8>> {
         let mut req: Vec<u8> = TCAN455xController::generate_read_command(addr, len);
-        match self.driver.tcan455x_transfer_in_place(&mut req) {
-            Ok(_) => Ok(req),
-            Err(_) => Err(io::ErrorKind::ConnectionRefused.into())
-        }
+        self.driver.tcan455x_transfer_in_place(&mut req)?;
+        Ok(req)
+    }
+
+    pub fn read_bytes(&mut self, addr: u16, len: u8) -> CandsResult<Vec<u8>> {
+        let ret: Vec<u8> = self.read(addr, len)?;
+        let v = ret[4..]
+            .chunks(4)
+            .flat_map(|x| vec![x[3], x[2], x[1], x[0]])
+            .collect();
+        Ok(v)
     }
 
-    pub fn read_bytes(&mut self, addr: u16, len: u8) -> io::Result<Vec<u8>> {
-        match self.read(addr, len) {
-            Ok(ret) => {
-                let v = ret[4..]
-                    .chunks(4)
-                    .map(|x| vec![x[3], x[2], x[1], x[0]])
-                    .flatten()
-                    .collect();
-                Ok(v)
-            },
-            Err(_) => Err(io::ErrorKind::InvalidData.into())
+    /// Read `len` words starting at `addr`, split into bursts the SPI driver can handle
+    pub fn read_mram(&mut self, addr: u16, len: usize) -> CandsResult<Vec<u8>> {
+        let max_words: usize = self.max_burst_words();
+        let mut ret: Vec<u8> = Vec::with_capacity(4 * len);
+        let mut addr: u16 = addr;
+        let mut remaining: usize = len;
+        while remaining > 0 {
+            let burst: usize = remaining.min(max_words);
+            ret.extend(self.read_bytes(addr, burst as u8)?);
+            addr += 4 * burst as u16;
+            remaining -= burst;
         }
+        Ok(ret)
     }
 
-    pub fn read_device(&mut self, addr: u16) -> io::Result<u32> {
-        let status: u32 = match self.read(addr, 1) {
-            Ok(ret) => {
-                match &ret[4..].try_into() {
-                    Ok(x) => u32::from_be_bytes(*x),
-                    Err(_) => return Err(io::ErrorKind::InvalidData.into())
-                }
-            },
-            Err(_) => return Err(io::ErrorKind::InvalidData.into())
+    pub fn read_device(&mut self, addr: u16) -> CandsResult<u32> {
+        let ret: Vec<u8> = self.read(addr, 1)?;
+        let status: u32 = match &ret[4..].try_into() {
+            Ok(x) => u32::from_be_bytes(*x),
+            Err(_) => return Err(io::Error::from(io::ErrorKind::InvalidData).into())
         };
         Ok(status)
     }
 
-    pub fn reset(&mut self) -> Result<(), Box<dyn std::error::Error>>{
-        self.driver.tcan455x_reset().map_err(|e| e.into())
+    pub fn reset(&mut self) -> CandsResult<()> {
+        self.driver.tcan455x_reset()
     }
 
-    async fn timeout<T>() -> std::io::Result<T> {
+    async fn timeout<T>() -> CandsResult<T> {
         Timer::after(Duration::from_millis(500)).await;
-        Err(io::ErrorKind::TimedOut.into())
+        Err(CandsError::Timeout)
     }
     
-    pub fn setup(&mut self, sidf: &[SIDConfig], xidf: &[XIDConfig]) -> Result<(), String>{
+    pub fn setup(&mut self, sidf: &[SIDConfig], xidf: &[XIDConfig]) -> CandsResult<()> {
+        Self::check_bit_timing_clock(self, &self.bit_timing)?;
 
-        Self::reset(self).expect("Cannot reset TCAN4550");
+        // A watchdog timeout of the previous session is only visible until the reset clears DEV_IR
+        self.watchdog_expired = Self::read_watchdog_expired(self)?;
 
-        Self::switch_standby_mode(self).expect("Cannot shift into standby mode");
+        Self::reset(self)?;
+        self.pending_dev_ir = 0;
+
+        // The reset cleared the MRAM configuration, from here on the device follows the pending layout
+        self.mram_layout = self.pending_mram_layout;
+
+        // Kept for `resume` after a power cycle
+        self.sid_filters = sidf.to_vec();
+        self.xid_filters = xidf.to_vec();
+
+        Self::switch_standby_mode(self)?;
     
         // Clear SPI error
-        Self::clear_spi_error(self).expect("Cannot clear SPI error");
+        Self::clear_spi_error(self)?;
     
         // Clear device interrupt flags
-        let dev_ir: u32 = Self::read_device_irq(self).expect("Cannot read device irq");
+        let dev_ir: u32 = Self::read_device_irq(self)?;
         if (dev_ir >> 20) & 0x01 == 0x01 {
-            Self::clear_device_irq_flags(self, dev_ir).expect("Cannot clear device irq flags");
+            Self::clear_device_irq_flags(self, dev_ir)?;
         }
 
         // Unprotect config registers
-        Self::lock_mcan_cccr(self).expect("Cannot lock MCAN CCCR");
+        Self::lock_mcan_cccr(self)?;
     
         // Configuration::enable CAN FD
-        Self::configure_mcan_cccr(self).expect("Cannot configure MCAN CCCR");
+        Self::configure_mcan_cccr(self)?;
         // Configuration::global filter
-        Self::configure_global_filter(self).expect("Cannot configure global filter");
+        Self::configure_global_filter(self)?;
         // Configuration::bit timing
-        Self::configure_bit_timing(self).expect("Cannot configure bit timing");
+        Self::write_bit_timing(self, self.bit_timing)?;
+        // Configuration::timestamp counter
+        Self::configure_timestamp(self)?;
         // Configuration::clear MRAM
-        Self::clear_mram(self).expect("Cannot clear MRAM");
+        Self::clear_mram(self)?;
         // Configuration::MRAM
-        Self::configure_mram(self).expect("Cannot configure MRAM");
+        Self::configure_mram(self)?;
         // Configuration::SID and XID filter
-        Self::configure_filter(self, sidf, xidf).expect("Cannot configure SID/XID filter");
+        Self::configure_filter(self, sidf, xidf)?;
     
         // Protect config registers
-        Self::unlock_mcan_cccr(self).expect("Cannot unlock MCAN CCCR ");
+        Self::unlock_mcan_cccr(self)?;
     
         // Test mode: Only enable when REG_MCAN_CCCR[7] = 1 & REG_MCAN_CCCR[5] = 1
-        Self::switch_test_mode(self).expect("Cannot shift into test mode");
+        Self::switch_test_mode(self)?;
     
         // Configure MCAN IRQ
-        Self::configure_mcan_irq(self).expect("Cannot configure MCAN IRQ");
+        Self::configure_mcan_irq(self)?;
     
         // Configure device
-        Self::configure_mode_and_pins(self).expect("Cannot configure mode and pins");
+        Self::configure_mode_and_pins(self)?;
     
-        Self::switch_normal_mode(self).expect("Cannot shift into normal mode");
+        Self::switch_normal_mode(self)?;
     
         // Clear all MCAN interrupt flags
-        Self::clear_mcan_irq_flags(self).expect("Cannot clear MCAN IRQ");
+        Self::clear_mcan_irq_flags(self)?;
+
+        // Read back everything written above, after an SPI fault some registers may not hold what was written
+        if self.verify_setup {
+            Self::check_spi_status(self)?;
+            let mismatches: Vec<verify::ConfigMismatch> = Self::verify_configuration(self, sidf, xidf)?;
+            if !mismatches.is_empty() {
+                return Err(CandsError::ConfigMismatch(mismatches));
+            }
+        }
 
         Ok(())
     }
 
-    pub fn close(&mut self) -> io::Result<()> {
-        Self::switch_sleep_mode(self)
+    pub fn close(&mut self) -> CandsResult<()> {
+        Self::enter_sleep(self)
     }
 
-    pub fn get_device_id(&mut self) -> io::Result<String>{
+    pub fn get_device_id(&mut self) -> CandsResult<String>{
         let fut = async {
-            let info: Vec<u8> = match self.read_bytes(REG_SPI_DEVICE_ID0, 2) {
-                Ok(val) => val,
-                Err(_) => return Err(io::ErrorKind::InvalidData.into())
-            };
+            let info: Vec<u8> = self.read_bytes(REG_SPI_DEVICE_ID0, 2)?;
             let info: String = String::from_utf8_lossy(&info).to_string();
             Ok(info)
         };
         block_on(fut.or(Self::timeout()))
     }
     
-    pub fn read_spi_status(&mut self) -> io::Result<u32>{
+    pub fn read_spi_status(&mut self) -> CandsResult<u32>{
         let fut = async {
-            match self.read_device(REG_SPI_STATUS) {
-                Ok(val) => Ok(val),
-                Err(_) => Err(io::ErrorKind::InvalidData.into())
-            }
+            self.read_device(REG_SPI_STATUS)
         };
         block_on(fut.or(Self::timeout()))
     }
     
-    pub fn read_device_irq(&mut self) -> io::Result<u32>{
+    pub fn read_device_irq(&mut self) -> CandsResult<u32>{
         let fut = async {
-            match self.read_device(REG_DEV_IR) {
-                Ok(val) => Ok(val),
-                Err(_) => Err(io::ErrorKind::InvalidData.into())
-            }
+            self.read_device(REG_DEV_IR)
         };
         block_on(fut.or(Self::timeout()))
     }
 
-    pub fn clear_spi_error(&mut self) -> io::Result<()> {
+    /// Fails with `CandsError::SpiStatus` if SPI_STATUS reports a fault, clearing it for the next access
+    pub fn check_spi_status(&mut self) -> CandsResult<()> {
+        let status: u32 = self.read_spi_status()?;
+        if status == 0 {
+            return Ok(());
+        }
+        Self::clear_spi_error(self)?;
+        Err(CandsError::SpiStatus(status))
+    }
+
+    pub fn clear_spi_error(&mut self) -> CandsResult<()> {
         let fut = async {
             let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_SPI_STATUS, vec![0xFFFFFFFF]);
             self.write(&cmd)?;
@@ -181,7 +307,7 @@ impl TCAN455xTranceiver {
         block_on(fut.or(Self::timeout()))
     }
 
-    pub fn clear_device_irq_flags(&mut self, dev_ir: u32) -> io::Result<()> {
+    pub fn clear_device_irq_flags(&mut self, dev_ir: u32) -> CandsResult<()> {
         let fut = async {
             let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_DEV_IR, vec![dev_ir]);
             self.write(&cmd)?;
@@ -190,7 +316,7 @@ impl TCAN455xTranceiver {
         block_on(fut.or(Self::timeout()))
     }
 
-    pub fn clear_mcan_irq_flags(&mut self) -> io::Result<()> {
+    pub fn clear_mcan_irq_flags(&mut self) -> CandsResult<()> {
         let fut = async {
             let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_IR, vec![0xFFFFFFFF]);
             self.write(&cmd)?;
@@ -199,61 +325,74 @@ impl TCAN455xTranceiver {
         block_on(fut.or(Self::timeout()))
     }
 
-    pub fn clear_mram(&mut self) -> io::Result<()> {
+    pub fn clear_mram(&mut self) -> CandsResult<()> {
+        let mut batch: WriteBatch = WriteBatch::new();
+        batch.write_words(REG_MRAM, &vec![0; MRAM_SIZE as usize / 4]);
         let fut = async {
-            const MRAM_SIZE: u16 = 2048;
-            for addr in (REG_MRAM..(REG_MRAM + MRAM_SIZE)).step_by(4) {
-                let cmd: Vec<u8> = TCAN455xController::generate_write_command(addr, vec![0]);
-                self.write(&cmd)?;
-            }
-            Ok(())
+            self.write_batch(&batch)
         };
         block_on(fut.or(Self::timeout()))
     }
     
-    pub fn lock_mcan_cccr(&mut self) -> io::Result<()> {
+    pub fn lock_mcan_cccr(&mut self) -> CandsResult<()> {
         let fut = async {
-            let ccr: u32 = match self.read_device(REG_MCAN_CCCR) {
-                Ok(val) => val,
-                Err(_) => return Err(io::ErrorKind::InvalidData.into())
-            };
-            let cce: u32 = TCAN455xController::unprotect_register(ccr);
-            let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_CCCR, vec![cce]);
-            self.write(&cmd)?;
+            self.modify_reg(|cccr: &mut McanCccr| {
+                cccr.csa = false;
+                cccr.csr = false;
+                cccr.init = true;
+                cccr.cce = true;
+            })?;
             Ok(())
         };
         block_on(fut.or(Self::timeout()))
     }
 
-    pub fn unlock_mcan_cccr(&mut self) -> io::Result<()> {
+    pub fn unlock_mcan_cccr(&mut self) -> CandsResult<()> {
         let fut = async {
-            let ccr: u32 = match self.read_device(REG_MCAN_CCCR) {
-                Ok(val) => val,
-                Err(_) => return Err(io::ErrorKind::InvalidData.into())
-            };
-            let ccd: u32 = TCAN455xController::protect_register(ccr);
-            let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_CCCR, vec![ccd]);
-            self.write(&cmd)?;
+            self.modify_reg(|cccr: &mut McanCccr| {
+                cccr.csa = false;
+                cccr.csr = false;
+                cccr.init = false;
+                cccr.cce = false;
+            })?;
             Ok(())
         };
         block_on(fut.or(Self::timeout()))
     }
 
-    pub fn configure_global_filter(&mut self) -> io::Result<()> {
+    /// Global filter applied by the next `setup`
+    pub fn set_global_filter(&mut self, config: GlobalFilterConfig) -> CandsResult<()> {
+        config.validate()?;
+        self.global_filter = config;
+        Ok(())
+    }
+
+    pub fn get_global_filter(&self) -> GlobalFilterConfig {
+        self.global_filter
+    }
+
+    /// GFC and XIDAM can only be written while CCCR.CCE = 1 and CCCR.INIT = 1
+    pub fn configure_global_filter(&mut self) -> CandsResult<()> {
+        let config: GlobalFilterConfig = self.global_filter;
+        let mut batch: WriteBatch = WriteBatch::new();
+        batch.push_register(&config.gfc());
+        batch.push_command(&TCAN455xController::set_xidam(&config));
         let fut = async {
-            const ANFS: u32 = 1;   // Incoming message doesn't match a filter is to accept into RXFIO0 for SID messages (11 bit IDs)
-            const ANFE: u32 = 1;   // Incoming message doesn't match a filter is to accept into RXFIO0 for XID messages (29 bit IDs)
-            const RRFS: u32 = 0;   // Reject remote frames (TCAN4x5x doesn't support this)
-            const RRFE: u32 = 0;   // Reject remote frames (TCAN4x5x doesn't support this)
-            const PAYLOAD: u32 = ((ANFS << 4) | (ANFE << 2) | (RRFS << 1) | (RRFE << 0)) & REG_BITS_MCAN_GFC_MASK;
-            let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_GFC, vec![PAYLOAD]);
-            self.write(&cmd)?;
-            Ok(())
+            self.write_batch(&batch)
         };
         block_on(fut.or(Self::timeout()))
     }
 
-    pub fn configure_mcan_cccr(&mut self) -> io::Result<()> {
+    /// Change the global filter while running. The core stops taking part in bus traffic while INIT is set.
+    pub fn update_global_filter(&mut self, config: GlobalFilterConfig) -> CandsResult<()> {
+        Self::set_global_filter(self, config)?;
+        Self::lock_mcan_cccr(self)?;
+        let result: CandsResult<()> = Self::configure_global_filter(self);
+        Self::unlock_mcan_cccr(self)?;
+        result
+    }
+
+    pub fn configure_mcan_cccr(&mut self) -> CandsResult<()> {
         let fut = async {
             self.write(&TCAN455xController::set_mcan_cccr())?;
             Ok(())
@@ -261,108 +400,193 @@ impl TCAN455xTranceiver {
         block_on(fut.or(Self::timeout())) 
     }
 
-    pub fn configure_bit_timing(&mut self) -> io::Result<()> {
+    /// Bit timing applied by the next `setup`
+    pub fn set_bit_timing(&mut self, timing: BitTiming) -> CandsResult<()> {
+        timing.validate()?;
+        self.bit_timing = timing;
+        Ok(())
+    }
+
+    pub fn get_bit_timing(&self) -> BitTiming {
+        self.bit_timing
+    }
+
+    // A timing derived for the other reference clock runs the bus at half or twice the bit rate
+    fn check_bit_timing_clock(&self, timing: &BitTiming) -> CandsResult<()> {
+        if timing.clock != self.device_config.clk_ref {
+            let msg: String = format!("Bit timing is derived for {} Hz, CLK_REF selects {} Hz", timing.clock.hz(), self.device_config.clk_ref.hz());
+            return Err(CandsError::InvalidConfig(msg));
+        }
+        Ok(())
+    }
+
+    /// NBTP, DBTP and TDCR can only be written while CCCR.CCE = 1 and CCCR.INIT = 1
+    pub fn configure_bit_timing(&mut self, timing: BitTiming) -> CandsResult<()> {
+        timing.validate()?;
+        Self::write_bit_timing(self, timing)
+    }
+
+    // `setup` writes the default timing too, its NSJW is above NTSEG2
+    fn write_bit_timing(&mut self, timing: BitTiming) -> CandsResult<()> {
+        timing.validate_fields()?;
+        Self::check_bit_timing_clock(self, &timing)?;
+        let mut batch: WriteBatch = WriteBatch::new();
+        batch.push_register(&timing.dbtp());
+        batch.push_register(&timing.nbtp());
+        batch.push_register(&timing.tdcr());
         let fut = async {
-            self.write(&TCAN455xController::set_dbtp())?;
-            self.write(&TCAN455xController::set_nbtp())?;
-            self.write(&TCAN455xController::set_tdcr())?;
-            self.write(&TCAN455xController::set_tscc())?;
-            Ok(())
+            self.write_batch(&batch)
         };
-        block_on(fut.or(Self::timeout())) 
+        block_on(fut.or(Self::timeout()))?;
+        self.bit_timing = timing;
+        Ok(())
+    }
+
+    /// MRAM layout applied by the next `setup`. Until then the device keeps using the active layout.
+    pub fn set_mram_layout(&mut self, layout: MramLayout) {
+        self.pending_mram_layout = layout;
+    }
+
+    /// Layout applied by the next `setup`
+    pub fn get_mram_layout(&self) -> MramLayout {
+        self.pending_mram_layout
+    }
+
+    /// Layout the device was last set up with
+    pub fn active_mram_layout(&self) -> MramLayout {
+        self.mram_layout
     }
 
-    pub fn configure_mram(&mut self) -> io::Result<()> {
+    pub fn configure_mram(&mut self) -> CandsResult<()> {
     // Following registers cannot change unless Configuration Change Enable (CCE) = HIGH
+        let layout: MramLayout = self.mram_layout;
+        let mut batch: WriteBatch = WriteBatch::new();
+        batch.push_command(&TCAN455xController::set_sidfc(&layout));
+        batch.push_command(&TCAN455xController::set_xidfc(&layout));
+        batch.push_command(&TCAN455xController::set_rxf0c(&layout));
+        batch.push_command(&TCAN455xController::set_rxf1c(&layout));
+        batch.push_command(&TCAN455xController::set_rxbc(&layout));
+        batch.push_command(&TCAN455xController::set_rxesc(&layout));
+        batch.push_command(&TCAN455xController::set_txefc(&layout));
+        batch.push_command(&TCAN455xController::set_txbc(&layout));
+        batch.push_command(&TCAN455xController::set_txesc(&layout));
         let fut = async {
-            self.write(&TCAN455xController::set_sidfc())?;
-            self.write(&TCAN455xController::set_xidfc())?;
-            self.write(&TCAN455xController::set_rxf0c())?;
-            self.write(&TCAN455xController::set_rxf1c())?;
-            self.write(&TCAN455xController::set_rxbc())?;
-            self.write(&TCAN455xController::set_rxesc())?;
-            self.write(&TCAN455xController::set_txefc())?;
-            self.write(&TCAN455xController::set_txbc())?;
-            self.write(&TCAN455xController::set_txesc())?;
-            Ok(())
+            self.write_batch(&batch)
         };
         block_on(fut.or(Self::timeout())) 
     }
 
-    pub fn configure_mode_and_pins(&mut self) -> io::Result<()> {
+    pub fn set_device_config(&mut self, config: DeviceConfig) {
+        self.device_config = config;
+    }
+
+    pub fn get_device_config(&self) -> DeviceConfig {
+        self.device_config
+    }
+
+    /// Reference clock selected by CLK_REF
+    pub fn clock_ref(&self) -> ClockRef {
+        self.device_config.clk_ref
+    }
+
+    pub fn configure_mode_and_pins(&mut self) -> CandsResult<()> {
+        let config: DeviceConfig = self.device_config;
         let fut = async {
-            self.write(&TCAN455xController::set_device_modes_and_pins())?;
+            self.write(&TCAN455xController::set_device_modes_and_pins(&config))?;
             Ok(())
         };
         block_on(fut.or(Self::timeout()))
     }
 
-    pub fn configure_mcan_irq(&mut self) -> io::Result<()> {
+    /// Interrupts enabled on top of RF0N and RF1N. DRX follows the RX buffers of the MRAM layout.
+    fn mcan_ie_enable(&self) -> u32 {
+        let mut enable: u32 = 0;
+        if self.error_interrupts {
+            enable |= MCAN_IE_ERROR_FLAGS;
+        }
+        if self.bus_off_recovery != BusOffRecovery::Manual {
+            enable |= REG_BITS_MCAN_IE_BOE;
+        }
+        if self.mram_layout.num_of_elements(MramSection::RxBuffer) > 0 {
+            enable |= REG_BITS_MCAN_IE_DRXE;
+        }
+        if self.tx_event_interrupts {
+            enable |= MCAN_IE_TX_EVENT_FLAGS;
+        }
+        if self.high_priority_interrupts {
+            enable |= REG_BITS_MCAN_IE_HPME;
+        }
+        if self.timestamp_interrupts && self.timestamp.is_enabled() {
+            enable |= REG_BITS_MCAN_IE_TSWE;
+        }
+        enable
+    }
+
+    pub fn configure_mcan_irq(&mut self) -> CandsResult<()> {
+        let mut batch: WriteBatch = WriteBatch::new();
+        batch.push_command(&TCAN455xController::set_mcan_ie(self.mcan_ie_enable()));
+        batch.push_command(&TCAN455xController::set_mcan_ile());
         let fut = async {
-            self.write(&TCAN455xController::set_mcan_ie())?;
-            self.write(&TCAN455xController::set_mcan_ile())?;
-            Ok(())
+            self.write_batch(&batch)
         };
         block_on(fut.or(Self::timeout())) 
     }
 
-    pub fn configure_filter(&mut self, sidf: &[SIDConfig], xidf: &[XIDConfig]) -> io::Result<()>{
+    /// Fails with `CandsError::InvalidConfig` if there are more filters than MRAM filter elements
+    pub fn configure_filter(&mut self, sidf: &[SIDConfig], xidf: &[XIDConfig]) -> CandsResult<()>{
+        let layout: MramLayout = self.mram_layout;
+        check_filter_capacity(&layout, sidf.len(), xidf.len())?;
+        let mut batch: WriteBatch = WriteBatch::new();
+        if !sidf.is_empty() && layout.num_of_elements(MramSection::Sid) > 0 {
+            batch.push_command(&TCAN455xController::set_sid(&layout, sidf));
+        }
+        if !xidf.is_empty() && layout.num_of_elements(MramSection::Xid) > 0 {
+            batch.push_command(&TCAN455xController::set_xid(&layout, xidf));
+        }
         let fut = async {
-            self.write(&TCAN455xController::set_sid(sidf))?;
-            self.write(&TCAN455xController::set_xid(xidf))?;
-            Ok(())
+            self.write_batch(&batch)
         };
         block_on(fut.or(Self::timeout()))
     }
 
-    pub fn switch_operation_mode(&mut self, mode: u8) -> io::Result<()> {
+    /// 0: normal, 1: standby, 2: sleep
+    pub fn switch_operation_mode(&mut self, mode: u8) -> CandsResult<()> {
+        let mode_sel: u8 = match mode {
+            0 => DevModesAndPins::MODE_NORMAL,
+            1 => DevModesAndPins::MODE_STANDBY,
+            2 => DevModesAndPins::MODE_SLEEP,
+            _ => return Err(CandsError::InvalidConfig(format!("Operation mode {} out of range 0..=2", mode))),
+        };
         let fut = async {
-            let config: u32 = match self.read_device(REG_DEV_MODES_AND_PINS) {
-                Ok(val) => val,
-                Err(_) => return Err(io::ErrorKind::InvalidData.into())
-            };
-
-            let config_masked: u32 = config & !REG_BITS_DEVICE_MODE_DEVICEMODE_MASK;
-            let payload: u32 = match mode {
-                0 => config_masked | REG_BITS_DEVICE_MODE_DEVICEMODE_NORMAL,
-                1 => config_masked | REG_BITS_DEVICE_MODE_DEVICEMODE_STANDBY,
-                2 => config_masked | REG_BITS_DEVICE_MODE_DEVICEMODE_SLEEP,
-                _ => config_masked
-            };
-            let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_DEV_MODES_AND_PINS, vec![payload]);
-            self.write(&cmd)?;
+            self.modify_reg(|config: &mut DevModesAndPins| {
+                config.mode_sel = mode_sel;
+            })?;
             Ok(())
         };
         block_on(fut.or(Self::timeout()))
     }
 
-    pub fn switch_normal_mode(&mut self) -> io::Result<()> {
+    pub fn switch_normal_mode(&mut self) -> CandsResult<()> {
         Self::switch_operation_mode(self, 0)
     }
 
-    pub fn switch_standby_mode(&mut self) -> io::Result<()> {
+    pub fn switch_standby_mode(&mut self) -> CandsResult<()> {
         Self::switch_operation_mode(self, 1)
     }
 
-    pub fn switch_sleep_mode(&mut self) -> io::Result<()> {
+    pub fn switch_sleep_mode(&mut self) -> CandsResult<()> {
         Self::switch_operation_mode(self, 2)
     }
 
-    pub fn switch_test_mode(&mut self) -> io::Result<()> {
+    pub fn switch_test_mode(&mut self) -> CandsResult<()> {
         // This register can be written only when REG_MCAN_CCCR[21] = 1;
         let fut = async {
-            let cccr: u32 = match self.read_device(REG_MCAN_CCCR) {
-                Ok(val) => val,
-                Err(_) => return Err(io::ErrorKind::InvalidData.into())
-            };
+            let cccr: u32 = self.read_device(REG_MCAN_CCCR)?;
 
             let is_test_ena: bool = ((cccr & REG_BITS_MCAN_CCCR_TEST) > 0) & ((cccr & REG_BITS_MCAN_CCCR_MON) > 0);
             if !is_test_ena { return Ok(()); }
 
-            let test: u32 = match self.read_device(REG_MCAN_TEST) {
-                Ok(val) => val,
-                Err(_) => return Err(io::ErrorKind::InvalidData.into())
-            };
+            let test: u32 = self.read_device(REG_MCAN_TEST)?;
             let payload: u32 = test | REG_BITS_MCAN_TEST_LOOP_BACK;
             let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_TEST, vec![payload]);
             self.write(&cmd)?;
@@ -384,52 +608,34 @@ impl TCAN455xTranceiver {
         15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15, 15,  // 49-64
     ];
 
-    pub fn transmit(&mut self, xid: u32, data: &[u8], size: usize) -> io::Result<()> {
+    pub(crate) fn push_tx_fifo(&mut self, element: Vec<u32>) -> CandsResult<()> {
+        let tx_fqs: McanTxfqs = self.read_reg()?;
+        let tx_put_index: u16 = tx_fqs.tfqpi as u16;
+
+        if tx_fqs.tffl == 0 { return Err(CandsError::TxFifoFull) }
+
+        let addr: u16 = TCAN455xController::get_txdata_start_addr(&self.mram_layout, tx_put_index);
+        let cmd: Vec<u8> = TCAN455xController::generate_write_command(addr, element);
+        self.write(&cmd)?;
+
+        let add_req: u32 = 1 << tx_put_index;
+        let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_TXBAR, vec![add_req]);
+        self.write(&cmd)?;
+        Ok(())
+    }
+
+    pub fn transmit(&mut self, xid: u32, data: &[u8], size: usize) -> CandsResult<()> {
         let fut = async {
             
-            let payloads: Vec<Vec<u8>> = data
-                .chunks(TXFIFODATASIZE.size as usize)
-                .map(|x| Vec::from(x))
-                .collect();
-
-            for payload in payloads {
-
-                let byte_array: Vec<u32> = payload
-                    .chunks(4)
-                    .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
-                    .collect();
-
-                let tx_fqs: u32 = match self.read_device(REG_MCAN_TXFQS) {
-                    Ok(val) => val,
-                    Err(_) => return Err(io::ErrorKind::InvalidData.into())
-                };
-                let tx_free_level: u32 = tx_fqs & & 0x000000FF;
-                let tx_put_index: u16 = ((tx_fqs & 0x001F0000) >> 16) as u16;
-            
-                if tx_free_level == 0 { return Err(io::ErrorKind::Interrupted.into()) }
-                
-                let dlc: u32 = Self::CAN_DLEN_TO_DLC[size] as u32;
-
-                const MM: u32 = 1;
-                const EFC: u32 = 1;
-                const FDF: u32 = 1;
-                const BRS: u32 = 1;
-                
-                let addr: u16 = TCAN455xController::get_txdata_start_addr(tx_put_index);
-                let xid: u32 = (1u32 << 30) |  xid;
-                let header: u32 = (MM << 24) |(EFC << 23) | (FDF << 21) | (BRS << 20) | (dlc << 16);
-
-                let mut payload: Vec<u32> = Vec::<u32>::new();
-                payload.push(xid);
-                payload.push(header);
-                payload.extend(&byte_array);
-
-                let cmd: Vec<u8> = TCAN455xController::generate_write_command(addr, payload); 
-                self.write(&cmd)?;
-
-                let add_req: u32 = 1 << tx_put_index;
-                let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_TXBAR, vec![add_req]); 
-                self.write(&cmd)?;
+            const MM: u8 = 1;
+            let element_size: usize = self.mram_layout.tx_data_size().size as usize;
+
+            for payload in data.chunks(element_size) {
+                // Every element announces `size` bytes, the chunk is zero padded or cut to it
+                let mut frame: CanFrame = CanFrame::new_fd(xid, true, payload, true);
+                frame.data.resize(size, 0);
+                let element: Vec<u32> = tx_buffer::encode_tx_element_with_marker(&frame, element_size, MM)?;
+                self.push_tx_fifo(element)?;
             }
             Ok(())
         };
@@ -437,20 +643,34 @@ impl TCAN455xTranceiver {
         block_on(fut.or(Self::timeout()))
     }
 
-    pub fn receive(&mut self) -> io::Result<Option<RxData>> {
+    pub fn transmit_frame(&mut self, frame: &CanFrame) -> CandsResult<()> {
         let fut = async {
+            let element: Vec<u32> = tx_buffer::encode_tx_element(frame, self.mram_layout.tx_data_size().size as usize)?;
+            self.push_tx_fifo(element)
+        };
+
+        block_on(fut.or(Self::timeout()))
+    }
+
+    pub fn receive(&mut self) -> CandsResult<Option<RxData>> {
+        let fut = async {
+
+            let dev_ir: u32 = Self::read_device_irq(self)?;
+            if self.device_event_sender.is_some() {
+                self.handle_device_events(dev_ir)?;
+            }
 
-            let dev_ir: u32 = match Self::read_device_irq(self) {
-                Ok(x) => x,
-                Err(err) => return Err(err),
-            };
-            
             let mcan_int: bool =  (dev_ir & REG_BITS_DEVICE_IR_M_CAN_INT) >> 1 != 0;
             if !mcan_int {
                 return Ok(None);
             }
 
             let mcan_ir: u32 = self.read_device(REG_MCAN_IR)?;
+            self.handle_error_interrupts(mcan_ir)?;
+            let sync: Option<TimestampSync> = self.handle_timestamp_interrupts(mcan_ir)?;
+            let high_priority: Option<(usize, u8)> = self.handle_high_priority_interrupts(mcan_ir)?;
+            self.handle_tx_event_interrupts(mcan_ir)?;
+            let rx_buffer_frames: Vec<CanFrame> = self.handle_rx_buffer_interrupts(mcan_ir)?;
 
             let rx_fifo0_new_message: bool = (mcan_ir & REG_BITS_MCAN_IR_RF0N) != 0;
             let rx_fifo1_new_message: bool = (mcan_ir & REG_BITS_MCAN_IR_RF1N) != 0;
@@ -459,7 +679,11 @@ impl TCAN455xTranceiver {
             const RXFIFO_STATUS_ADDR: [u16; 2] = [REG_MCAN_RXF0S, REG_MCAN_RXF1S];
             const RXFIFO_ACK_ADDR: [u16; 2] = [REG_MCAN_RXF0A, REG_MCAN_RXF1A];
 
+            let layout: MramLayout = self.mram_layout;
             let mut rx_buffer: RxData = RxData::new();
+            rx_buffer.element_size = [layout.rx_fifo_element_size(0), layout.rx_fifo_element_size(1)];
+            rx_buffer.buffers = rx_buffer_frames;
+            rx_buffer.sync = sync;
 
             if rx_fifo0_new_message | rx_fifo1_new_message {
 
@@ -470,35 +694,37 @@ impl TCAN455xTranceiver {
                     if rx_fifo_new_message[ch] {
                         let rx_fifo_status: u32 = self.read_device(RXFIFO_STATUS_ADDR[ch])?;
 
-                        let rx_fifo_put_index: u32 = (rx_fifo_status >> 16) & 0x3f;
                         let rx_fifo_get_index: u32 = (rx_fifo_status >> 8) & 0x3f;
                         let rx_fifo_unread: u32 = rx_fifo_status & 0x7f;
-            
+
+                        let rx_fifo_size: u32 = layout.rx_fifo_elements(ch);
+                        let rx_element_size: u32 = layout.rx_fifo_element_size(ch);
+                        // An index beyond the layout means the device holds a different configuration
+                        if rx_fifo_size == 0 || rx_fifo_unread == 0 || rx_fifo_get_index >= rx_fifo_size { continue; }
+
                         let mut rx_data: Vec<u8> = Vec::<u8>::new();
-                        
-                        let rx_fifo_overwrapped: bool = rx_fifo_put_index <= rx_fifo_get_index;
-                        if !rx_fifo_overwrapped {
-                            let addr: u16 = TCAN455xController::get_rxdata_start_addr(ch as u16, rx_fifo_get_index as u16);
-                            let len: u32 =  (RXDATA_BLOCKSIZE[ch] * rx_fifo_unread) / 4;
-                            match self.read_bytes(addr, len as u8) {
-                                Ok(vec) => rx_data.extend(vec),
-                                Err(_) => {}
-                            };
-                        } else {
-                            if rx_fifo_unread >= rx_fifo_put_index {
-                                let addr: u16 = TCAN455xController::get_rxdata_start_addr(ch as u16, rx_fifo_get_index as u16);
-                                let len: u32 =  (RXDATA_BLOCKSIZE[ch] * (rx_fifo_unread - rx_fifo_put_index)) / 4;
-                                match self.read_bytes(addr, len as u8) {
-                                    Ok(vec) => rx_data.extend(vec),
-                                    Err(_) => {}
-                                };
+
+                        // Unread elements run from the get index to the end of the FIFO, then wrap around to index 0
+                        let rx_fifo_unread_to_end: u32 = rx_fifo_unread.min(rx_fifo_size - rx_fifo_get_index);
+                        let rx_fifo_unread_wrapped: u32 = rx_fifo_unread - rx_fifo_unread_to_end;
+
+                        let addr: u16 = TCAN455xController::get_rxdata_start_addr(&layout, ch as u16, rx_fifo_get_index as u16);
+                        let len: u32 = (rx_element_size * rx_fifo_unread_to_end) / 4;
+                        rx_data.extend(self.read_mram(addr, len as usize)?);
+
+                        if rx_fifo_unread_wrapped > 0 {
+                            let addr: u16 = TCAN455xController::get_rxdata_start_addr(&layout, ch as u16, 0);
+                            let len: u32 = (rx_element_size * rx_fifo_unread_wrapped) / 4;
+                            rx_data.extend(self.read_mram(addr, len as usize)?);
+                        }
+
+                        // Already delivered on the high priority channel
+                        if let Some((_, index)) = high_priority.filter(|(hp_ch, _)| *hp_ch == ch) {
+                            let position: u32 = (index as u32 + rx_fifo_size - rx_fifo_get_index) % rx_fifo_size;
+                            if position < rx_fifo_unread {
+                                let start: usize = (position * rx_element_size) as usize;
+                                rx_data.drain(start..start + rx_element_size as usize);
                             }
-                            let addr: u16 = TCAN455xController::get_rxdata_start_addr(ch as u16, 0);
-                            let len: u32 =  (RXDATA_BLOCKSIZE[ch] * (rx_fifo_put_index)) / 4;
-                            match self.read_bytes(addr, len as u8) {
-                                Ok(vec) => rx_data.extend(vec),
-                                Err(_) => {}
-                            };
                         }
 
                         match ch {
@@ -507,7 +733,7 @@ impl TCAN455xTranceiver {
                             _ => {}
                         }
 
-                        let rx_fifo_ack_index: u32 = (rx_fifo_put_index + RXDATA_FIFOSIZE[ch] - 1) % RXDATA_FIFOSIZE[ch];
+                        let rx_fifo_ack_index: u32 = (rx_fifo_get_index + rx_fifo_unread - 1) % rx_fifo_size;
                         let cmd: Vec<u8> = TCAN455xController::generate_write_command(RXFIFO_ACK_ADDR[ch], vec![rx_fifo_ack_index]); 
                         self.write(&cmd)?;
                     }
@@ -515,13 +741,19 @@ impl TCAN455xTranceiver {
 
             }
 
-            let _spi_staus = self.read_spi_status();
-            let _dev_ir = self.read_device_irq();
-
             Ok(Some(rx_buffer))
         };
 
-        block_on(fut.or(Self::timeout()))
+        let rx_data: Option<RxData> = block_on(fut.or(Self::timeout()))?;
+        self.poll_bus_off_recovery()?;
+        Ok(rx_data)
+    }
+
+    pub fn receive_frames(&mut self) -> CandsResult<Vec<CanFrame>> {
+        match self.receive()? {
+            Some(rx_data) => Ok(rx_data.frames()),
+            None => Ok(Vec::new())
+        }
     }
 
 }
\ No newline at end of file
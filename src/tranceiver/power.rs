@@ -0,0 +1,141 @@
+use std::time::{Duration, Instant};
+
+use async_io::{block_on, Timer};
+use futures_lite::FutureExt;
+
+use crate::error::{CandsError, CandsResult};
+use crate::tcan4550::{bitfield::McanCccr, id_filter::{SIDConfig, XIDConfig}, register::*};
+
+// Wake flags are polled at least this often, in case nINT is not enabled for them
+const WAKE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Flags cleared before sleeping so the next wake reason is not stale
+const WAKE_FLAGS: u32 = REG_BITS_DEVICE_IR_CANINT | REG_BITS_DEVICE_IR_LWU | REG_BITS_DEVICE_IR_WKERR;
+
+/// Why the device left sleep or standby
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WakeReason {
+    /// Wake up pattern on the bus (CANINT)
+    CanBus,
+    /// Edge on the WAKE pin (LWU)
+    WakePin,
+    /// Power cycle, the configuration is lost (PWRON)
+    PowerOn,
+}
+
+impl WakeReason {
+    /// Decode DEV_IR. A power on takes precedence since it also clears the other flags.
+    pub fn from_dev_ir(dev_ir: u32) -> Option<Self> {
+        if dev_ir & REG_BITS_DEVICE_IR_PWRON != 0 {
+            Some(WakeReason::PowerOn)
+        } else if dev_ir & REG_BITS_DEVICE_IR_LWU != 0 {
+            Some(WakeReason::WakePin)
+        } else if dev_ir & REG_BITS_DEVICE_IR_CANINT != 0 {
+            Some(WakeReason::CanBus)
+        } else {
+            None
+        }
+    }
+}
+
+impl super::TCAN455xTranceiver {
+
+    /// Request clock stop (CCCR.CSR) and wait until the core acknowledges it with CSA.
+    /// The core finishes a transfer in progress first and enters INIT.
+    pub fn stop_mcan_clock(&mut self) -> CandsResult<()> {
+        let fut = async {
+            self.modify_reg(|cccr: &mut McanCccr| cccr.csr = true)?;
+            loop {
+                let cccr: McanCccr = self.read_reg()?;
+                if cccr.csa {
+                    return Ok(());
+                }
+                Timer::after(Duration::from_millis(1)).await;
+            }
+        };
+        block_on(fut.or(Self::timeout()))
+    }
+
+    /// Release the clock stop by clearing CCCR.CSR and wait until the core clears CSA.
+    /// INIT stays set until then, so the core only rejoins the bus once it is cleared afterwards.
+    pub fn restart_mcan_clock(&mut self) -> CandsResult<()> {
+        let fut = async {
+            self.modify_reg(|cccr: &mut McanCccr| cccr.csr = false)?;
+            loop {
+                let cccr: McanCccr = self.read_reg()?;
+                if !cccr.csa {
+                    return Ok(());
+                }
+                Timer::after(Duration::from_millis(1)).await;
+            }
+        };
+        block_on(fut.or(Self::timeout()))
+    }
+
+    /// Stop the M_CAN clock and enter standby. SPI stays available.
+    pub fn enter_standby(&mut self) -> CandsResult<()> {
+        Self::stop_mcan_clock(self)?;
+        Self::clear_wake_flags(self)?;
+        Self::switch_standby_mode(self)
+    }
+
+    /// Stop the M_CAN clock and enter sleep. SPI is unavailable until a wake event returns the device to standby.
+    pub fn enter_sleep(&mut self) -> CandsResult<()> {
+        Self::stop_mcan_clock(self)?;
+        Self::clear_wake_flags(self)?;
+        Self::switch_sleep_mode(self)
+    }
+
+    // Wake flags in DEV_IR and those device event handling already cleared
+    fn clear_wake_flags(&mut self) -> CandsResult<()> {
+        self.pending_dev_ir &= !WAKE_FLAGS;
+        Self::clear_device_irq_flags(self, WAKE_FLAGS)
+    }
+
+    /// Wake reason in DEV_IR or cleared by device event handling, None while the device has not woken up
+    pub fn read_wake_reason(&mut self) -> CandsResult<Option<WakeReason>> {
+        let dev_ir: u32 = self.read_device_irq()?;
+        // A sleeping device does not drive SDO
+        if dev_ir == u32::MAX {
+            return Ok(None);
+        }
+        Ok(WakeReason::from_dev_ir(dev_ir | self.pending_dev_ir))
+    }
+
+    /// Wait for a wake event after `enter_sleep` or `enter_standby`. Fails with `CandsError::Timeout`
+    /// if none arrives within `timeout`; None waits forever.
+    ///
+    /// CANINT and LWU are cleared, PWRON is left for `resume`.
+    pub fn wait_for_wake(&mut self, timeout: Option<Duration>) -> CandsResult<WakeReason> {
+        let deadline: Option<Instant> = timeout.map(|t| Instant::now() + t);
+        loop {
+            if let Some(reason) = self.read_wake_reason()? {
+                Self::clear_wake_flags(self)?;
+                return Ok(reason);
+            }
+
+            let remaining: Option<Duration> = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            if remaining.is_some_and(|remaining| remaining.is_zero()) {
+                return Err(CandsError::Timeout);
+            }
+            let wait: Duration = remaining.map_or(WAKE_POLL_INTERVAL, |remaining| remaining.min(WAKE_POLL_INTERVAL));
+            self.driver.wait_for_interrupt(Some(wait))?;
+        }
+    }
+
+    /// Return to normal mode after a wake event. A power cycled device is set up again with
+    /// the filters of the last `setup`, otherwise only the clock stop is released.
+    pub fn resume(&mut self) -> CandsResult<()> {
+        let dev_ir: u32 = self.read_device_irq()?;
+        if dev_ir & REG_BITS_DEVICE_IR_PWRON != 0 {
+            let sidf: Vec<SIDConfig> = self.sid_filters.clone();
+            let xidf: Vec<XIDConfig> = self.xid_filters.clone();
+            return Self::setup(self, &sidf, &xidf);
+        }
+
+        Self::restart_mcan_clock(self)?;
+        Self::switch_normal_mode(self)?;
+        // Clearing INIT rejoins the bus
+        Self::unlock_mcan_cccr(self)
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::mpsc;
+
+use crate::error::CandsResult;
+use crate::tcan4550::{controller::{configurator::mram::*, TCAN455xController}, register::*};
+use super::rx_buffer::{CanFrame, RxSource};
+
+/// Where a high priority message was stored (HPMS.MSI)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MessageStorage {
+    /// Filter action without storage
+    NoFifo,
+    /// The FIFO was full and the message was lost
+    Lost,
+    Fifo0,
+    Fifo1,
+}
+
+impl MessageStorage {
+    pub fn from_u32(code: u32) -> Self {
+        match code & 0x03 {
+            0 => MessageStorage::NoFifo,
+            1 => MessageStorage::Lost,
+            2 => MessageStorage::Fifo0,
+            _ => MessageStorage::Fifo1,
+        }
+    }
+
+    fn fifo(&self) -> Option<usize> {
+        match self {
+            MessageStorage::Fifo0 => Some(0),
+            MessageStorage::Fifo1 => Some(1),
+            _ => None,
+        }
+    }
+}
+
+/// High priority message status (HPMS)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HighPriorityStatus {
+    /// FIFO element index the message was stored into
+    pub bidx: u8,
+    pub msi: MessageStorage,
+    /// Index of the matching filter element
+    pub fidx: u8,
+    /// Filter list: false for standard, true for extended filters
+    pub flst: bool,
+}
+
+impl HighPriorityStatus {
+    pub fn from_u32(hpms: u32) -> Self {
+        Self {
+            bidx: (hpms & 0x3F) as u8,
+            msi: MessageStorage::from_u32(hpms >> 6),
+            fidx: ((hpms >> 8) & 0x7F) as u8,
+            flst: (hpms >> 15) & 0x01 != 0,
+        }
+    }
+}
+
+/// Filter match flagged as high priority, with the frame if it was stored in a FIFO
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighPriorityMessage {
+    pub status: HighPriorityStatus,
+    pub frame: Option<CanFrame>,
+}
+
+impl super::TCAN455xTranceiver {
+
+    pub fn read_high_priority_status(&mut self) -> CandsResult<HighPriorityStatus> {
+        let hpms: u32 = self.read_device(REG_MCAN_HPMS)?;
+        Ok(HighPriorityStatus::from_u32(hpms))
+    }
+
+    /// Enable the HPM interrupt on the next `setup` or `configure_mcan_irq`
+    pub fn set_high_priority_interrupts(&mut self, enable: bool) {
+        self.high_priority_interrupts = enable;
+    }
+
+    /// Channel of high priority messages, replacing any previous subscriber.
+    ///
+    /// Messages are read by `receive` before the FIFOs, and a frame delivered here is not returned
+    /// by `receive` again. HPMS only holds the latest match, earlier ones between two reads are lost.
+    pub fn high_priority_messages(&mut self) -> mpsc::Receiver<HighPriorityMessage> {
+        let (sender, receiver) = mpsc::channel();
+        self.high_priority_sender = Some(sender);
+        receiver
+    }
+
+    /// Read the element at `index` of RX FIFO `ch` without acknowledging it
+    fn peek_rx_fifo(&mut self, ch: usize, index: u8) -> CandsResult<Option<CanFrame>> {
+        let layout: MramLayout = self.mram_layout;
+        if index as u32 >= layout.rx_fifo_elements(ch) {
+            return Ok(None);
+        }
+        let source: RxSource = if ch == 0 { RxSource::Fifo0 } else { RxSource::Fifo1 };
+        let addr: u16 = TCAN455xController::get_rxdata_start_addr(&layout, ch as u16, index as u16);
+        let element: Vec<u8> = self.read_mram(addr, (layout.rx_fifo_element_size(ch) / 4) as usize)?;
+        Ok(CanFrame::from_rx_element(&element, source))
+    }
+
+    /// Clear HPM in `mcan_ir` and deliver the message HPMS points to.
+    /// Returns the FIFO and element index of a frame handed to the subscriber.
+    pub(crate) fn handle_high_priority_interrupts(&mut self, mcan_ir: u32) -> CandsResult<Option<(usize, u8)>> {
+        if mcan_ir & REG_BITS_MCAN_IR_HPM == 0 {
+            return Ok(None);
+        }
+        let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_IR, vec![REG_BITS_MCAN_IR_HPM]);
+        self.write(&cmd)?;
+
+        if self.high_priority_sender.is_none() {
+            return Ok(None);
+        }
+
+        let status: HighPriorityStatus = self.read_high_priority_status()?;
+        let mut frame: Option<CanFrame> = match status.msi.fifo() {
+            Some(ch) => self.peek_rx_fifo(ch, status.bidx)?,
+            None => None,
+        };
+        if let (Some(frame), Some(sync)) = (frame.as_mut(), self.timestamp_sync) {
+            frame.timestamp = Some(sync.stamp(frame.rxts));
+        }
+        let delivered: Option<(usize, u8)> = match (&frame, status.msi.fifo()) {
+            (Some(_), Some(ch)) => Some((ch, status.bidx)),
+            _ => None,
+        };
+
+        let message: HighPriorityMessage = HighPriorityMessage { status, frame };
+        if self.high_priority_sender.as_ref().is_some_and(|sender| sender.send(message).is_err()) {
+            self.high_priority_sender = None;
+            return Ok(None);
+        }
+        Ok(delivered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HighPriorityStatus, MessageStorage};
+
+    #[test]
+    fn hpms_fields_are_decoded() {
+        let status: HighPriorityStatus = HighPriorityStatus::from_u32((1 << 15) | (0x45 << 8) | (3 << 6) | 0x2A);
+        assert_eq!(status, HighPriorityStatus { bidx: 0x2A, msi: MessageStorage::Fifo1, fidx: 0x45, flst: true });
+
+        let status: HighPriorityStatus = HighPriorityStatus::from_u32((0x7F << 8) | (2 << 6) | 0x3F);
+        assert_eq!(status, HighPriorityStatus { bidx: 0x3F, msi: MessageStorage::Fifo0, fidx: 0x7F, flst: false });
+
+        assert_eq!(HighPriorityStatus::from_u32(1 << 6).msi, MessageStorage::Lost);
+        assert_eq!(HighPriorityStatus::from_u32(0).msi, MessageStorage::NoFifo);
+        // Reserved bits above FLST are ignored
+        assert_eq!(HighPriorityStatus::from_u32(0xFFFF0000), HighPriorityStatus::from_u32(0));
+    }
+}
@@ -1,39 +1,44 @@
 use crate::device_driver::raspberrypi_cm::{RaspiIF, GPIO_INPUT_PIN_NUM};
+use crate::error::CandsResult;
 
 impl super::TCAN455xTranceiver {
-    pub fn new () -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new () -> CandsResult<Self> {
         let driver: RaspiIF = RaspiIF::new()?;
-        Ok(Self { driver: Box::new(driver) })
+        Ok(Self::from_driver(Box::new(driver)))
     }
 
-    pub fn gpo_write(&mut self, state: u8) {
-        self.driver.gpio_out(state).unwrap()
+    /// Like `new` for a board wiring nINT to BCM pin `nint_pin_bcm` instead of `DEFAULT_TCAN_NINT_PIN_BCM`
+    pub fn with_nint_pin(nint_pin_bcm: u8) -> CandsResult<Self> {
+        let driver: RaspiIF = RaspiIF::with_nint_pin(nint_pin_bcm)?;
+        Ok(Self::from_driver(Box::new(driver)))
     }
 
-    pub fn gpi_read(&mut self, channel: usize) -> bool {
-        self.driver.gpio_read(channel).unwrap()
+    pub fn gpo_write(&mut self, state: u8) -> CandsResult<()> {
+        self.driver.gpio_out(state)
     }
 
-    pub fn gpi_read_all(&mut self) -> [bool; GPIO_INPUT_PIN_NUM] {
+    pub fn gpi_read(&mut self, channel: usize) -> CandsResult<bool> {
+        self.driver.gpio_read(channel)
+    }
+
+    pub fn gpi_read_all(&mut self) -> CandsResult<[bool; GPIO_INPUT_PIN_NUM]> {
         let mut ret: [bool; GPIO_INPUT_PIN_NUM] = [false; GPIO_INPUT_PIN_NUM];
-        let gpi = self.driver.gpio_read_all().unwrap();
-        for i in 0..GPIO_INPUT_PIN_NUM {
-            ret[i] = gpi[i];
-        }
-        ret
+        let gpi = self.driver.gpio_read_all()?;
+        ret.copy_from_slice(&gpi[..GPIO_INPUT_PIN_NUM]);
+        Ok(ret)
     }
 
-    pub fn ws2812_write(&mut self, buffer: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn ws2812_write(&mut self, buffer: &[u8]) -> CandsResult<()> {
         self.driver.ws2812_write(buffer)?;
         Ok(())
     }
 
-    pub fn adc_reset(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn adc_reset(&mut self) -> CandsResult<()> {
         self.driver.adc_reset()?;
         Ok(())
     }
 
-    pub fn adc_read(&mut self) -> Result<[u8; 3], Box<dyn std::error::Error>> {
+    pub fn adc_read(&mut self) -> CandsResult<[u8; 3]> {
         let mut buf: [u8; 3] = [0u8; 3];
         self.driver.adc_read(&mut buf)?;
         Ok(buf)
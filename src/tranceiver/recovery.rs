@@ -0,0 +1,170 @@
+use std::time::{Duration, Instant};
+
+use crate::error::CandsResult;
+use crate::tcan4550::bitfield::McanPsr;
+use super::bus_state::{BusEvent, BusState, ErrorState};
+
+// The core leaves bus off after 129 occurrences of 11 consecutive recessive bits
+const RECOVERY_SEQUENCE_BITS: u64 = 129 * 11;
+
+// Time allowed on top of the recovery sequence for a busy bus
+const RECOVERY_TIMEOUT: Duration = Duration::from_millis(100);
+
+// Retry delay of the immediate policy after a failed attempt, doubled for each further failure
+const IMMEDIATE_RETRY_DELAY: Duration = Duration::from_millis(10);
+const IMMEDIATE_RETRY_DELAY_MAX: Duration = Duration::from_secs(1);
+
+/// What to do when the node goes bus off and the core sets CCCR.INIT
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum BusOffRecovery {
+    /// Stay bus off until `recover_bus_off` is called
+    #[default]
+    Manual,
+    /// Clear INIT as soon as bus off is detected, retrying after a short, growing delay while attempts fail
+    Immediate,
+    /// Wait `delay` before the first attempt, doubling it for each consecutive bus off,
+    /// and give up after `max_retries` attempts
+    Backoff { delay: Duration, max_retries: u32 },
+}
+
+/// Progress of the bus off recovery
+#[derive(Debug, Default)]
+pub(crate) struct RecoveryState {
+    /// Consecutive recovery attempts
+    attempts: u32,
+    /// Delay before the last scheduled attempt
+    delay: Duration,
+    /// Next automatic attempt
+    due: Option<Instant>,
+    /// Attempt waiting for the recovery sequence, failed once still bus off at the deadline
+    running: Option<(u32, Instant)>,
+    /// Last successful recovery
+    recovered_at: Option<Instant>,
+}
+
+impl super::TCAN455xTranceiver {
+
+    /// Bus off recovery policy. Automatic policies enable the BO interrupt on the next `setup` or `configure_mcan_irq`.
+    pub fn set_bus_off_recovery(&mut self, policy: BusOffRecovery) {
+        self.bus_off_recovery = policy;
+        self.recovery = RecoveryState::default();
+    }
+
+    pub fn get_bus_off_recovery(&self) -> BusOffRecovery {
+        self.bus_off_recovery
+    }
+
+    /// Time the core needs to see 129 × 11 recessive bits at the nominal bitrate
+    pub fn bus_off_recovery_time(&self) -> Duration {
+        let bitrate: u64 = self.bit_timing.nominal_bitrate(self.clock_ref()).max(1) as u64;
+        Duration::from_micros(RECOVERY_SEQUENCE_BITS * 1_000_000 / bitrate)
+    }
+
+    /// Time until the next automatic recovery attempt or the deadline of the running one
+    pub fn bus_off_recovery_pending(&self) -> Option<Duration> {
+        let next: Option<Instant> = match (self.recovery.due, self.recovery.running) {
+            (Some(due), Some((_, deadline))) => Some(due.min(deadline)),
+            (due, running) => due.or(running.map(|(_, deadline)| deadline)),
+        };
+        next.map(|next| next.saturating_duration_since(Instant::now()))
+    }
+
+    /// Clear INIT to start the recovery sequence without waiting for it. Returns true if the node is not bus off.
+    ///
+    /// Later calls of `receive`, `poll_bus_state` or `poll_bus_off_recovery` check PSR.BO
+    /// and report `BusEvent::Recovered` or, after the sequence time, `BusEvent::RecoveryFailed`.
+    pub fn recover_bus_off(&mut self) -> CandsResult<bool> {
+        let state: ErrorState = self.read_error_state()?;
+        if !state.bo {
+            self.update_bus_state(state);
+            return Ok(true);
+        }
+        if self.recovery.running.is_some() {
+            return Ok(false);
+        }
+
+        self.recovery.due = None;
+        self.recovery.attempts += 1;
+        let attempt: u32 = self.recovery.attempts;
+
+        // Clearing INIT starts the recovery sequence
+        Self::unlock_mcan_cccr(self)?;
+        let deadline: Instant = Instant::now() + self.bus_off_recovery_time() + RECOVERY_TIMEOUT;
+        self.recovery.running = Some((attempt, deadline));
+        self.emit_bus_event(BusEvent::RecoveryStarted { attempt });
+        Ok(false)
+    }
+
+    /// Finish a running recovery attempt or start one that is due. Called by `receive` and `poll_bus_state`.
+    pub fn poll_bus_off_recovery(&mut self) -> CandsResult<()> {
+        if let Some((attempt, deadline)) = self.recovery.running {
+            let psr: McanPsr = self.read_reg()?;
+            if !psr.bo {
+                self.recovery.running = None;
+                let state: ErrorState = self.read_error_state()?;
+                self.update_bus_state(state);
+                self.emit_bus_event(BusEvent::Recovered { attempt });
+            } else if Instant::now() >= deadline {
+                self.recovery.running = None;
+                self.emit_bus_event(BusEvent::RecoveryFailed { attempt });
+                self.schedule_recovery();
+            }
+            return Ok(());
+        }
+
+        if self.recovery.due.is_some_and(|due| Instant::now() >= due) {
+            self.recover_bus_off()?;
+        }
+        Ok(())
+    }
+
+    /// Plan the next automatic attempt after entering bus off or a failed attempt
+    pub(crate) fn schedule_recovery(&mut self) {
+        let now: Instant = Instant::now();
+
+        // A bus that stayed up at least as long as the last delay starts over
+        let delay: Duration = self.recovery.delay;
+        if self.recovery.recovered_at.take().is_some_and(|recovered_at| now.duration_since(recovered_at) >= delay) {
+            self.recovery.attempts = 0;
+        }
+
+        match self.bus_off_recovery {
+            BusOffRecovery::Manual => {},
+            BusOffRecovery::Immediate => {
+                let attempts: u32 = self.recovery.attempts;
+                if attempts == 0 {
+                    self.recovery.delay = Duration::ZERO;
+                    self.recovery.due = Some(now);
+                    return;
+                }
+                let delay: Duration = IMMEDIATE_RETRY_DELAY.saturating_mul(1 << (attempts - 1).min(16)).min(IMMEDIATE_RETRY_DELAY_MAX);
+                self.recovery.delay = delay;
+                self.recovery.due = Some(now + delay);
+                self.emit_bus_event(BusEvent::RecoveryScheduled { attempt: attempts + 1, delay });
+            },
+            BusOffRecovery::Backoff { delay, max_retries } => {
+                let attempts: u32 = self.recovery.attempts;
+                if attempts >= max_retries {
+                    self.recovery.due = None;
+                    self.emit_bus_event(BusEvent::RecoveryAbandoned { attempts });
+                    return;
+                }
+                let delay: Duration = delay.saturating_mul(1 << attempts.min(16));
+                self.recovery.delay = delay;
+                self.recovery.due = Some(now + delay);
+                self.emit_bus_event(BusEvent::RecoveryScheduled { attempt: attempts + 1, delay });
+            },
+        }
+    }
+
+    pub(crate) fn on_bus_state_changed(&mut self, from: BusState, to: BusState) {
+        if to == BusState::BusOff && from != BusState::BusOff {
+            self.schedule_recovery();
+        } else if from == BusState::BusOff && to != BusState::BusOff {
+            // Left bus off, possibly through a recovery the application started itself.
+            // A running attempt is reported as recovered by the next poll.
+            self.recovery.due = None;
+            self.recovery.recovered_at = Some(Instant::now());
+        }
+    }
+}
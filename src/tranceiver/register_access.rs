@@ -0,0 +1,27 @@
+use crate::error::CandsResult;
+use crate::tcan4550::{bitfield::Register, controller::TCAN455xController};
+
+impl super::TCAN455xTranceiver {
+
+    pub fn read_reg<R: Register>(&mut self) -> CandsResult<R> {
+        Ok(R::from_u32(self.read_device(R::ADDR)?))
+    }
+
+    pub fn write_reg<R: Register>(&mut self, reg: R) -> CandsResult<()> {
+        let cmd: Vec<u8> = TCAN455xController::generate_register_write(&reg);
+        self.write(&cmd)?;
+        Ok(())
+    }
+
+    /// Read `R`, change it with `f` and write it back. Returns the value written.
+    ///
+    /// Write 1 to clear registers (IR, DEV_IR, SPI_STATUS) would clear every flag that was set,
+    /// use `write_reg` with only the flags to clear instead.
+    pub fn modify_reg<R: Register, F: FnOnce(&mut R)>(&mut self, f: F) -> CandsResult<R> {
+        let mut reg: R = self.read_reg()?;
+        f(&mut reg);
+        let cmd: Vec<u8> = TCAN455xController::generate_register_write(&reg);
+        self.write(&cmd)?;
+        Ok(reg)
+    }
+}
@@ -0,0 +1,16 @@
+use crate::error::CandsResult;
+use crate::tcan4550::register_map::{RegisterDump, REGISTER_MAP};
+
+impl super::TCAN455xTranceiver {
+
+    /// Read and decode every SPI, device and M_CAN register.
+    ///
+    /// Reading PSR resets LEC and DLEC, and reading ECR resets CEL, as any other read of them does.
+    pub fn dump_registers(&mut self) -> CandsResult<RegisterDump> {
+        let mut values: Vec<(u16, u32)> = Vec::with_capacity(REGISTER_MAP.len());
+        for info in REGISTER_MAP.iter() {
+            values.push((info.addr, self.read_device(info.addr)?));
+        }
+        Ok(RegisterDump::from_values(&values))
+    }
+}
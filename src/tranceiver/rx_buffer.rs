@@ -1,10 +1,31 @@
+use crate::tcan4550::controller::configurator::mram::RXDATA_BLOCKSIZE;
+use super::timestamp::{RxTimestamp, TimestampSync};
+
 const FIFOSIZE: usize = 1024;
 
+// RX element header size: R0 + R1
+const RX_ELEMENT_HEADER_SIZE: usize = 8;
+
+// Classic CAN frames never carry more than 8 bytes
+const CAN_CLASSIC_MAX_DLEN: usize = 8;
+
 /// Receive data buffer on user space
 #[derive(Debug)]
 pub struct RxData {
     pub fifo0: Vec<u8>,
-    pub fifo1: Vec<u8>
+    pub fifo1: Vec<u8>,
+    /// RX element size of FIFO0 and FIFO1 in bytes
+    pub element_size: [u32; 2],
+    /// Frames taken from dedicated RX buffers, already decoded
+    pub buffers: Vec<CanFrame>,
+    /// Counter sync taken while receiving, None while timestamps are disabled
+    pub sync: Option<TimestampSync>,
+}
+
+impl Default for RxData {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl RxData {
@@ -12,11 +33,145 @@ impl RxData {
         Self {
             fifo0: Vec::with_capacity(FIFOSIZE),
             fifo1: Vec::with_capacity(FIFOSIZE),
+            element_size: RXDATA_BLOCKSIZE,
+            buffers: Vec::new(),
+            sync: None,
         }
     }
 
     pub fn reset(&mut self) {
         self.fifo0.clear();
         self.fifo1.clear();
+        self.buffers.clear();
+        self.sync = None;
+    }
+
+    /// Decode both FIFOs into frames, after the dedicated RX buffers and FIFO0 first.
+    /// Frames are timestamped from `sync` when it is set.
+    pub fn frames(&self) -> Vec<CanFrame> {
+        let mut frames: Vec<CanFrame> = self.buffers.clone();
+        frames.extend(parse_rx_elements(RxSource::Fifo0, &self.fifo0, self.element_size[0] as usize));
+        frames.extend(parse_rx_elements(RxSource::Fifo1, &self.fifo1, self.element_size[1] as usize));
+        if let Some(sync) = self.sync {
+            for frame in frames.iter_mut() {
+                frame.timestamp = Some(sync.stamp(frame.rxts));
+            }
+        }
+        frames
+    }
+}
+
+/// Location in the message RAM a frame was received into
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RxSource {
+    Fifo0,
+    Fifo1,
+    /// Dedicated RX buffer index
+    Buffer(u8),
+}
+
+/// CAN frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanFrame {
+    /// 11 bit standard ID or 29 bit extended ID
+    pub id: u32,
+    /// Extended identifier
+    pub xtd: bool,
+    /// Remote transmission request
+    pub rtr: bool,
+    /// Error state indicator
+    pub esi: bool,
+    /// FD format
+    pub fdf: bool,
+    /// Bit rate switch
+    pub brs: bool,
+    /// Data length code
+    pub dlc: u8,
+    pub data: Vec<u8>,
+    /// RX timestamp
+    pub rxts: u16,
+    /// `rxts` extended to 64 bits and correlated with the host clock, None unless received with timestamps enabled
+    pub timestamp: Option<RxTimestamp>,
+    /// Index of the matching filter element
+    pub fidx: u8,
+    /// Accepted non-matching frame
+    pub anmf: bool,
+    /// None for frames built on user space
+    pub source: Option<RxSource>,
+}
+
+impl CanFrame {
+    /// Classic CAN data frame
+    pub fn new(id: u32, xtd: bool, data: &[u8]) -> Self {
+        Self {
+            id,
+            xtd,
+            rtr: false,
+            esi: false,
+            fdf: false,
+            brs: false,
+            dlc: data.len().min(CAN_CLASSIC_MAX_DLEN) as u8,
+            data: data.to_vec(),
+            rxts: 0,
+            timestamp: None,
+            fidx: 0,
+            anmf: false,
+            source: None,
+        }
+    }
+
+    /// Classic CAN remote frame requesting `dlc` bytes
+    pub fn new_remote(id: u32, xtd: bool, dlc: u8) -> Self {
+        Self { rtr: true, dlc, ..Self::new(id, xtd, &[]) }
+    }
+
+    /// CAN FD data frame, with or without bit rate switching
+    pub fn new_fd(id: u32, xtd: bool, data: &[u8], brs: bool) -> Self {
+        let dlc: u8 = super::TCAN455xTranceiver::CAN_DLEN_TO_DLC[data.len().min(64)];
+        Self { fdf: true, brs, dlc, ..Self::new(id, xtd, data) }
     }
+
+    /// Decode one RX element (R0, R1 and data field) laid out as little endian words
+    pub fn from_rx_element(element: &[u8], source: RxSource) -> Option<Self> {
+        if element.len() < RX_ELEMENT_HEADER_SIZE {
+            return None;
+        }
+
+        let r0: u32 = u32::from_le_bytes([element[0], element[1], element[2], element[3]]);
+        let r1: u32 = u32::from_le_bytes([element[4], element[5], element[6], element[7]]);
+
+        let esi: bool = (r0 >> 31) & 0x01 != 0;
+        let xtd: bool = (r0 >> 30) & 0x01 != 0;
+        let rtr: bool = (r0 >> 29) & 0x01 != 0;
+        let id: u32 = if xtd { r0 & 0x1FFFFFFF } else { (r0 >> 18) & 0x7FF };
+
+        let anmf: bool = (r1 >> 31) & 0x01 != 0;
+        let fidx: u8 = ((r1 >> 24) & 0x7F) as u8;
+        let fdf: bool = (r1 >> 21) & 0x01 != 0;
+        let brs: bool = (r1 >> 20) & 0x01 != 0;
+        let dlc: u8 = ((r1 >> 16) & 0x0F) as u8;
+        let rxts: u16 = (r1 & 0xFFFF) as u16;
+
+        let dlen: usize = match (rtr, fdf) {
+            (true, false) => 0,
+            (_, true) => super::TCAN455xTranceiver::CAN_DLC_TO_DLEN[dlc as usize] as usize,
+            (_, false) => (dlc as usize).min(CAN_CLASSIC_MAX_DLEN),
+        };
+        let field: &[u8] = &element[RX_ELEMENT_HEADER_SIZE..];
+        let data: Vec<u8> = field[..dlen.min(field.len())].to_vec();
+
+        Some(Self { id, xtd, rtr, esi, fdf, brs, dlc, data, rxts, timestamp: None, fidx, anmf, source: Some(source) })
+    }
+}
+
+/// Split raw FIFO bytes into RX elements of `element_size` bytes and decode each of them
+pub fn parse_rx_elements(source: RxSource, bytes: &[u8], element_size: usize) -> Vec<CanFrame> {
+    if element_size < RX_ELEMENT_HEADER_SIZE {
+        return Vec::new();
+    }
+
+    bytes
+        .chunks_exact(element_size)
+        .filter_map(|element| CanFrame::from_rx_element(element, source))
+        .collect()
 }
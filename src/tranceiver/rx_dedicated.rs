@@ -0,0 +1,82 @@
+use crate::error::{CandsError, CandsResult};
+use crate::tcan4550::{controller::{configurator::mram::*, TCAN455xController}, register::*};
+use super::rx_buffer::{CanFrame, RxSource};
+
+impl super::TCAN455xTranceiver {
+
+    fn check_rx_buffer_index(&self, index: u8) -> CandsResult<()> {
+        let buffers: u32 = self.mram_layout.num_of_elements(MramSection::RxBuffer);
+        if index as u32 >= buffers {
+            let msg: String = format!("RX buffer {} out of range, {} buffers configured", index, buffers);
+            return Err(CandsError::InvalidConfig(msg));
+        }
+        Ok(())
+    }
+
+    /// New data flags of the RX buffers, NDAT1 in the low word and NDAT2 in the high word
+    pub fn read_new_data(&mut self) -> CandsResult<u64> {
+        let ndat1: u32 = self.read_device(REG_MCAN_NDAT1)?;
+        let ndat2: u32 = self.read_device(REG_MCAN_NDAT2)?;
+        Ok(((ndat2 as u64) << 32) | ndat1 as u64)
+    }
+
+    /// Clear the new data flags in `mask` so the buffers can be written again
+    pub fn clear_new_data(&mut self, mask: u64) -> CandsResult<()> {
+        let ndat1: u32 = (mask & 0xFFFFFFFF) as u32;
+        let ndat2: u32 = (mask >> 32) as u32;
+        if ndat1 != 0 {
+            self.write(&TCAN455xController::generate_write_command(REG_MCAN_NDAT1, vec![ndat1]))?;
+        }
+        if ndat2 != 0 {
+            self.write(&TCAN455xController::generate_write_command(REG_MCAN_NDAT2, vec![ndat2]))?;
+        }
+        Ok(())
+    }
+
+    fn fetch_rx_buffer(&mut self, index: u8) -> CandsResult<Option<CanFrame>> {
+        let layout: MramLayout = self.mram_layout;
+        let element_size: u32 = layout.bytes_per_element(MramSection::RxBuffer);
+        let addr: u16 = TCAN455xController::get_rxbuffer_start_addr(&layout, index as u16);
+        let element: Vec<u8> = self.read_mram(addr, (element_size / 4) as usize)?;
+        Ok(CanFrame::from_rx_element(&element, RxSource::Buffer(index)))
+    }
+
+    /// Take the frame in RX buffer `index` and acknowledge it, None if the buffer holds no new data
+    pub fn read_rx_buffer(&mut self, index: u8) -> CandsResult<Option<CanFrame>> {
+        self.check_rx_buffer_index(index)?;
+        if self.read_new_data()? & (1 << index) == 0 {
+            return Ok(None);
+        }
+        let frame: Option<CanFrame> = self.fetch_rx_buffer(index)?;
+        self.clear_new_data(1 << index)?;
+        Ok(frame)
+    }
+
+    /// Take and acknowledge every RX buffer holding new data, lowest index first
+    pub fn read_rx_buffers(&mut self) -> CandsResult<Vec<CanFrame>> {
+        let buffers: u32 = self.mram_layout.num_of_elements(MramSection::RxBuffer);
+        if buffers == 0 {
+            return Ok(Vec::new());
+        }
+
+        let configured: u64 = if buffers >= 64 { u64::MAX } else { (1u64 << buffers) - 1 };
+        let new_data: u64 = self.read_new_data()? & configured;
+
+        let mut frames: Vec<CanFrame> = Vec::new();
+        for index in (0..buffers as u8).filter(|index| new_data & (1 << index) != 0) {
+            frames.extend(self.fetch_rx_buffer(index)?);
+        }
+        self.clear_new_data(new_data)?;
+        Ok(frames)
+    }
+
+    /// Clear DRX in `mcan_ir` and take the RX buffers it announces
+    pub(crate) fn handle_rx_buffer_interrupts(&mut self, mcan_ir: u32) -> CandsResult<Vec<CanFrame>> {
+        if mcan_ir & REG_BITS_MCAN_IR_DRX == 0 {
+            return Ok(Vec::new());
+        }
+        let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_IR, vec![REG_BITS_MCAN_IR_DRX]);
+        self.write(&cmd)?;
+        self.read_rx_buffers()
+    }
+}
@@ -0,0 +1,314 @@
+use crate::device_driver::simulator::SimulatedTCAN4550;
+
+impl super::TCAN455xTranceiver {
+    /// Tranceiver on top of a simulated TCAN4550. Keep a clone of `sim` to inject frames and inspect the device.
+    pub fn new_simulated(sim: SimulatedTCAN4550) -> Self {
+        Self::from_driver(Box::new(sim))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::SimulatedTCAN4550;
+    use crate::error::CandsError;
+    use crate::tcan4550::controller::configurator::bit_timing::{BitTiming, ClockRef};
+    use crate::tcan4550::id_filter::{SIDConfig, XIDConfig};
+    use crate::tcan4550::register::*;
+    use crate::tranceiver::TCAN455xTranceiver;
+    use crate::tcan4550::controller::configurator::modes_and_pins::{DeviceConfig, WatchdogAction, WatchdogTimer};
+    use crate::tranceiver::bus_state::{BusEvent, BusState};
+    use crate::tranceiver::device_event::DeviceEvent;
+    use crate::tranceiver::power::WakeReason;
+    use crate::tranceiver::priority::{HighPriorityMessage, MessageStorage};
+    use crate::tranceiver::recovery::BusOffRecovery;
+    use crate::tranceiver::rx_buffer::{CanFrame, RxSource};
+    use crate::tranceiver::timestamp::TimestampSync;
+
+    // Classic filter storing 0x100 in FIFO0, other frames go to FIFO1 by default
+    const SID_FILTER: SIDConfig = SIDConfig { sft: 1, sfec: 1, sidf1: 0x100, sidf2: 0x7FF };
+
+    fn setup(sim: &SimulatedTCAN4550) -> TCAN455xTranceiver {
+        let mut tranceiver: TCAN455xTranceiver = TCAN455xTranceiver::new_simulated(sim.clone());
+        tranceiver.setup(&[SID_FILTER], &[]).unwrap();
+        tranceiver
+    }
+
+    fn payload(frames: &[CanFrame]) -> Vec<(u32, bool, Vec<u8>)> {
+        frames.iter().map(|frame| (frame.id, frame.xtd, frame.data.clone())).collect()
+    }
+
+    #[test]
+    fn setup_reaches_normal_mode_with_no_pending_frames() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let mut tranceiver: TCAN455xTranceiver = setup(&sim);
+        assert_eq!(tranceiver.verify_configuration(&[SID_FILTER], &[]).unwrap(), vec![]);
+        assert!(tranceiver.receive_frames().unwrap().is_empty());
+        assert!(sim.transmitted_frames().is_empty());
+    }
+
+    #[test]
+    fn verified_setup_passes_on_the_simulator() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let mut tranceiver: TCAN455xTranceiver = TCAN455xTranceiver::new_simulated(sim);
+        tranceiver.set_verify_setup(true);
+        tranceiver.setup(&[SID_FILTER], &[]).unwrap();
+        assert_eq!(tranceiver.verify_device().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn loopback_returns_transmitted_frames() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let mut tranceiver: TCAN455xTranceiver = setup(&sim);
+        sim.set_loopback(true);
+
+        let sent: [CanFrame; 3] = [
+            CanFrame::new(0x100, false, &[1, 2, 3]),
+            CanFrame::new(0x1234567, true, &[0xAA; 8]),
+            CanFrame::new_fd(0x200, false, &[0x55; 24], true),
+        ];
+        for frame in sent.iter() {
+            tranceiver.transmit_frame(frame).unwrap();
+        }
+        assert_eq!(payload(&sim.transmitted_frames()), payload(&sent));
+
+        let received: Vec<CanFrame> = tranceiver.receive_frames().unwrap();
+        assert_eq!(payload(&received), payload(&sent));
+
+        let fd: &CanFrame = &received[2];
+        assert!(fd.fdf && fd.brs);
+        assert_eq!(fd.dlc, 12);
+        assert!(tranceiver.receive_frames().unwrap().is_empty());
+    }
+
+    #[test]
+    fn legacy_transmit_sends_fd_frames_and_rejects_oversized_lengths() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let mut tranceiver: TCAN455xTranceiver = setup(&sim);
+
+        tranceiver.transmit(0x123, &[1, 2, 3], 3).unwrap();
+        let sent: Vec<CanFrame> = sim.transmitted_frames();
+        assert_eq!(payload(&sent), vec![(0x123, true, vec![1, 2, 3])]);
+        assert!(sent[0].fdf && sent[0].brs);
+
+        assert!(tranceiver.transmit(0x123, &[0; 8], 65).is_err());
+        assert_eq!(sim.transmitted_frames().len(), 1);
+    }
+
+    #[test]
+    fn injected_frames_are_filtered_into_fifos() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let mut tranceiver: TCAN455xTranceiver = setup(&sim);
+
+        assert!(sim.inject_frame(&CanFrame::new(0x100, false, &[0x10])));
+        assert!(sim.inject_frame(&CanFrame::new(0x300, false, &[0x30, 0x31])));
+        assert!(sim.inject_frame(&CanFrame::new_remote(0x100, false, 4)));
+
+        let received: Vec<CanFrame> = tranceiver.receive_frames().unwrap();
+        let decoded: Vec<(u32, Option<RxSource>, bool, u8)> = received
+            .iter()
+            .map(|frame| (frame.id, frame.source, frame.rtr, frame.dlc))
+            .collect();
+        assert_eq!(decoded, vec![
+            (0x100, Some(RxSource::Fifo0), false, 1),
+            (0x100, Some(RxSource::Fifo0), true, 4),
+            (0x300, Some(RxSource::Fifo1), false, 2),
+        ]);
+        assert_eq!(received[2].data, vec![0x30, 0x31]);
+        assert!(received.iter().all(|frame| frame.timestamp.is_some()));
+    }
+
+    #[test]
+    fn receive_wraps_around_the_end_of_the_fifo() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let mut tranceiver: TCAN455xTranceiver = setup(&sim);
+        let fifo_size: u8 = tranceiver.active_mram_layout().rx_fifo_elements(0) as u8;
+
+        // Move the get index close to the end, then fill past it
+        for i in 0..fifo_size - 1 {
+            sim.inject_frame(&CanFrame::new(0x100, false, &[i]));
+        }
+        assert_eq!(tranceiver.receive_frames().unwrap().len(), fifo_size as usize - 1);
+
+        for i in 0..3 {
+            sim.inject_frame(&CanFrame::new(0x100, false, &[i]));
+        }
+        let data: Vec<Vec<u8>> = tranceiver.receive_frames().unwrap().into_iter().map(|frame| frame.data).collect();
+        assert_eq!(data, vec![vec![0], vec![1], vec![2]]);
+    }
+
+    #[test]
+    fn setup_rejects_a_timing_for_another_reference_clock() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let mut tranceiver: TCAN455xTranceiver = TCAN455xTranceiver::new_simulated(sim);
+        tranceiver.set_device_config(DeviceConfig { clk_ref: ClockRef::Mhz20, ..DeviceConfig::default() });
+        assert!(matches!(tranceiver.setup(&[], &[]), Err(CandsError::InvalidConfig(_))));
+
+        let timing: BitTiming = BitTiming::calculate(ClockRef::Mhz20, 500_000, 2_000_000, 800, 1).unwrap();
+        tranceiver.set_bit_timing(timing).unwrap();
+        tranceiver.setup(&[], &[]).unwrap();
+    }
+
+    #[test]
+    fn operation_modes_beyond_sleep_are_rejected() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let mut tranceiver: TCAN455xTranceiver = setup(&sim);
+        let modes: u32 = sim.peek(REG_DEV_MODES_AND_PINS);
+        assert!(matches!(tranceiver.switch_operation_mode(3), Err(CandsError::InvalidConfig(_))));
+        assert_eq!(sim.peek(REG_DEV_MODES_AND_PINS), modes);
+
+        tranceiver.switch_operation_mode(1).unwrap();
+        assert_eq!(sim.peek(REG_DEV_MODES_AND_PINS) & REG_BITS_DEVICE_MODE_DEVICEMODE_MASK, REG_BITS_DEVICE_MODE_DEVICEMODE_STANDBY);
+    }
+
+    #[test]
+    fn filters_beyond_the_layout_are_rejected() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let mut tranceiver: TCAN455xTranceiver = TCAN455xTranceiver::new_simulated(sim);
+        let xidf: Vec<XIDConfig> = vec![XIDConfig::default(); 64];
+        assert!(tranceiver.setup(&[], &xidf).is_err());
+    }
+
+    fn recovery_events(events: &mpsc::Receiver<BusEvent>) -> Vec<BusEvent> {
+        events.try_iter().filter(|event| !matches!(event, BusEvent::StateChanged { .. })).collect()
+    }
+
+    #[test]
+    fn bus_off_recovery_does_not_block_receive() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let mut tranceiver: TCAN455xTranceiver = TCAN455xTranceiver::new_simulated(sim.clone());
+        tranceiver.set_bus_off_recovery(BusOffRecovery::Immediate);
+        tranceiver.setup(&[], &[]).unwrap();
+        let events: mpsc::Receiver<BusEvent> = tranceiver.bus_events();
+
+        sim.set_bus_fault(true);
+        sim.set_error_counters(256, 0);
+        tranceiver.receive().unwrap();
+        assert_eq!(tranceiver.get_bus_state(), BusState::BusOff);
+        assert_eq!(recovery_events(&events), vec![BusEvent::RecoveryStarted { attempt: 1 }]);
+
+        // Still bus off at the deadline, the retry waits instead of starting at once
+        thread::sleep(tranceiver.bus_off_recovery_time() + Duration::from_millis(110));
+        tranceiver.receive().unwrap();
+        assert_eq!(recovery_events(&events), vec![
+            BusEvent::RecoveryFailed { attempt: 1 },
+            BusEvent::RecoveryScheduled { attempt: 2, delay: Duration::from_millis(10) },
+        ]);
+
+        thread::sleep(Duration::from_millis(10));
+        tranceiver.receive().unwrap();
+        assert_eq!(recovery_events(&events), vec![BusEvent::RecoveryStarted { attempt: 2 }]);
+
+        sim.set_bus_fault(false);
+        tranceiver.receive().unwrap();
+        assert_eq!(recovery_events(&events), vec![BusEvent::Recovered { attempt: 2 }]);
+        assert_eq!(tranceiver.get_bus_state(), BusState::ErrorActive);
+        assert_eq!(tranceiver.bus_off_recovery_pending(), None);
+    }
+
+    #[test]
+    fn wait_for_frame_clears_flags_no_handler_takes() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let mut tranceiver: TCAN455xTranceiver = setup(&sim);
+
+        // Message lost has no handler, once enabled it holds nINT low until it is cleared
+        sim.poke(REG_MCAN_IE, sim.peek(REG_MCAN_IE) | REG_BITS_MCAN_IE_RF0LE);
+        sim.poke(REG_MCAN_IR, REG_BITS_MCAN_IR_RF0L);
+        assert!(sim.nint_asserted());
+
+        let before: usize = sim.spi_transactions();
+        assert!(matches!(tranceiver.wait_for_frame(Some(Duration::from_millis(50))), Err(CandsError::Timeout)));
+        assert!(!sim.nint_asserted());
+        assert!(sim.spi_transactions() - before < 20);
+
+        assert!(sim.inject_frame(&CanFrame::new(0x100, false, &[1])));
+        sim.poke(REG_MCAN_IR, sim.peek(REG_MCAN_IR) | REG_BITS_MCAN_IR_RF0L);
+        let frames: Vec<CanFrame> = tranceiver.wait_for_frame(Some(Duration::from_millis(50))).unwrap();
+        assert_eq!(payload(&frames), vec![(0x100, false, vec![1])]);
+        assert!(!sim.nint_asserted());
+    }
+
+    #[test]
+    fn high_priority_frames_are_delivered_once_on_their_channel() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let mut tranceiver: TCAN455xTranceiver = TCAN455xTranceiver::new_simulated(sim.clone());
+        tranceiver.set_high_priority_interrupts(true);
+        // 0x100 is stored in FIFO0 and flagged as high priority
+        tranceiver.setup(&[SIDConfig { sfec: 5, ..SID_FILTER }], &[]).unwrap();
+        let messages: mpsc::Receiver<HighPriorityMessage> = tranceiver.high_priority_messages();
+
+        assert!(sim.inject_frame(&CanFrame::new(0x100, false, &[0x10])));
+        assert!(sim.inject_frame(&CanFrame::new(0x300, false, &[0x30])));
+        let frames: Vec<CanFrame> = tranceiver.receive_frames().unwrap();
+        assert_eq!(payload(&frames), vec![(0x300, false, vec![0x30])]);
+
+        let message: HighPriorityMessage = messages.try_recv().unwrap();
+        assert_eq!(message.status.msi, MessageStorage::Fifo0);
+        assert_eq!((message.status.fidx, message.status.flst), (0, false));
+        assert_eq!(payload(message.frame.as_slice()), vec![(0x100, false, vec![0x10])]);
+        assert!(messages.try_recv().is_err());
+
+        assert!(tranceiver.receive_frames().unwrap().is_empty());
+        assert!(messages.try_recv().is_err());
+    }
+
+    #[test]
+    fn wraparounds_missed_between_syncs_are_estimated_from_the_host_clock() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let mut tranceiver: TCAN455xTranceiver = setup(&sim);
+        let tick: Duration = tranceiver.timestamp_tick().unwrap();
+        let first: TimestampSync = tranceiver.sync_timestamp().unwrap().unwrap();
+
+        // More than two counter periods pass without a sync
+        thread::sleep(tick * 0x10000 * 2 + tick * 0x4000);
+        let ticks: u32 = (first.instant.elapsed().as_nanos() / tick.as_nanos()) as u32;
+        sim.advance_timestamp(ticks);
+
+        let second: TimestampSync = tranceiver.sync_timestamp().unwrap().unwrap();
+        assert_eq!(second.ticks, first.ticks + ticks as u64);
+    }
+
+    #[test]
+    fn resume_releases_the_clock_stop_before_leaving_init() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let mut tranceiver: TCAN455xTranceiver = setup(&sim);
+        tranceiver.enter_standby().unwrap();
+        assert_ne!(sim.peek(REG_MCAN_CCCR) & REG_BITS_MCAN_CCCR_CSA, 0);
+
+        sim.wake(WakeReason::CanBus);
+        assert_eq!(tranceiver.wait_for_wake(Some(Duration::ZERO)).unwrap(), WakeReason::CanBus);
+        tranceiver.resume().unwrap();
+
+        let cccr: u32 = sim.peek(REG_MCAN_CCCR);
+        assert_eq!(cccr & (REG_BITS_MCAN_CCCR_CSR | REG_BITS_MCAN_CCCR_CSA | REG_BITS_MCAN_CCCR_INIT), 0);
+        assert_eq!(sim.peek(REG_DEV_MODES_AND_PINS) & REG_BITS_DEVICE_MODE_DEVICEMODE_MASK, REG_BITS_DEVICE_MODE_DEVICEMODE_NORMAL);
+
+        assert!(sim.inject_frame(&CanFrame::new(0x100, false, &[0x10])));
+        assert_eq!(payload(&tranceiver.receive_frames().unwrap()), vec![(0x100, false, vec![0x10])]);
+    }
+
+    #[test]
+    fn device_events_leave_watchdog_and_wake_flags_to_their_readers() {
+        let sim: SimulatedTCAN4550 = SimulatedTCAN4550::new();
+        let mut tranceiver: TCAN455xTranceiver = TCAN455xTranceiver::new_simulated(sim.clone());
+        tranceiver.set_watchdog(WatchdogTimer::Ms60, WatchdogAction::Interrupt);
+        tranceiver.setup(&[], &[]).unwrap();
+        let events: mpsc::Receiver<DeviceEvent> = tranceiver.device_events();
+
+        sim.advance_watchdog(Duration::from_millis(60));
+        tranceiver.receive().unwrap();
+        assert!(events.try_recv().unwrap().dev_ir.wdto);
+        assert!(tranceiver.take_watchdog_timeout().unwrap());
+        assert!(!tranceiver.take_watchdog_timeout().unwrap());
+
+        tranceiver.enter_standby().unwrap();
+        sim.wake(WakeReason::WakePin);
+        tranceiver.receive().unwrap();
+        assert!(events.try_recv().unwrap().dev_ir.lwu);
+        assert_eq!(tranceiver.wait_for_wake(Some(Duration::ZERO)).unwrap(), WakeReason::WakePin);
+        assert_eq!(tranceiver.read_wake_reason().unwrap(), None);
+    }
+}
@@ -0,0 +1,194 @@
+use std::time::{Duration, Instant};
+use futures_lite::FutureExt;
+use async_io::block_on;
+
+use crate::error::CandsResult;
+use crate::tcan4550::{controller::{configurator::timestamp::TimestampConfig, TCAN455xController}, register::*};
+use super::batch::WriteBatch;
+
+// TSCV and RXTS hold the low 16 bits of the counter
+const COUNTER_PERIOD: u64 = 1 << 16;
+
+// RX timestamps at most this many ticks past a sync belong to frames received after it was taken
+const SYNC_LEAD_TICKS: u16 = 0x1000;
+
+// Interrupts announcing frames `receive` reads and stamps
+const MCAN_IR_RX_FLAGS: u32 = REG_BITS_MCAN_IR_RF0N | REG_BITS_MCAN_IR_RF1N | REG_BITS_MCAN_IR_DRX | REG_BITS_MCAN_IR_HPM;
+
+/// RX timestamp extended to 64 bits, with the host time the frame was received at
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RxTimestamp {
+    /// Counter ticks since the timestamp counter was configured
+    pub ticks: u64,
+    /// Estimated from the last sync, accurate to the SPI latency of that TSCV read
+    pub instant: Instant,
+}
+
+/// Extended counter value paired with the host time it was read at
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimestampSync {
+    pub ticks: u64,
+    pub instant: Instant,
+    /// Duration of one counter tick
+    pub tick: Duration,
+}
+
+impl TimestampSync {
+    /// Extend a 16 bit RX timestamp captured less than one counter period before this sync
+    pub fn extend(&self, rxts: u16) -> u64 {
+        let counter: u16 = self.ticks as u16;
+        let ahead: u16 = rxts.wrapping_sub(counter);
+        if ahead < SYNC_LEAD_TICKS {
+            return self.ticks + ahead as u64;
+        }
+        self.ticks.saturating_sub(counter.wrapping_sub(rxts) as u64)
+    }
+
+    /// Host time at which the counter read `ticks`
+    pub fn instant_at(&self, ticks: u64) -> Instant {
+        let span = |diff: u64| Duration::from_nanos((self.tick.as_nanos() * diff as u128).min(u64::MAX as u128) as u64);
+        if ticks >= self.ticks {
+            self.instant + span(ticks - self.ticks)
+        } else {
+            self.instant.checked_sub(span(self.ticks - ticks)).unwrap_or(self.instant)
+        }
+    }
+
+    pub fn stamp(&self, rxts: u16) -> RxTimestamp {
+        let ticks: u64 = self.extend(rxts);
+        RxTimestamp { ticks, instant: self.instant_at(ticks) }
+    }
+}
+
+impl super::TCAN455xTranceiver {
+
+    /// Timestamp counter applied by the next `setup`
+    pub fn set_timestamp_config(&mut self, config: TimestampConfig) -> CandsResult<()> {
+        config.validate()?;
+        self.timestamp = config;
+        Ok(())
+    }
+
+    pub fn get_timestamp_config(&self) -> TimestampConfig {
+        self.timestamp
+    }
+
+    /// Duration of one counter tick, None while timestamps are disabled
+    pub fn timestamp_tick(&self) -> Option<Duration> {
+        self.timestamp.tick_duration(self.clock_ref(), &self.bit_timing)
+    }
+
+    /// Enable the TSW interrupt on the next `setup` or `configure_mcan_irq`.
+    ///
+    /// `receive` syncs the counter whenever it reads frames. TSW makes it run at least once per
+    /// counter period, so wraparounds are counted while no frames arrive.
+    pub fn set_timestamp_interrupts(&mut self, enable: bool) {
+        self.timestamp_interrupts = enable;
+    }
+
+    /// TSCC can only be written while CCCR.CCE = 1 and CCCR.INIT = 1
+    pub fn configure_timestamp(&mut self) -> CandsResult<()> {
+        let config: TimestampConfig = self.timestamp;
+        config.validate()?;
+        let mut batch: WriteBatch = WriteBatch::new();
+        if let Some(cmd) = TCAN455xController::set_timestamp_prescaler(&config) {
+            batch.push_command(&cmd);
+        }
+        batch.push_register(&config.tscc());
+        let fut = async {
+            self.write_batch(&batch)
+        };
+        block_on(fut.or(Self::timeout()))?;
+        self.timestamp_sync = None;
+        Ok(())
+    }
+
+    /// Read TSCV and extend it to 64 bits with the wraparounds since the previous sync.
+    ///
+    /// Wraparounds missed because no sync happened within one counter period are
+    /// estimated from the host clock. None while timestamps are disabled.
+    pub fn sync_timestamp(&mut self) -> CandsResult<Option<TimestampSync>> {
+        let tick: Duration = match self.timestamp_tick() {
+            Some(tick) => tick,
+            None => return Ok(None),
+        };
+
+        let before: Instant = Instant::now();
+        let counter: u64 = (self.read_device(REG_MCAN_TSCV)? & 0xFFFF) as u64;
+        let instant: Instant = before + before.elapsed() / 2;
+
+        let ticks: u64 = match self.timestamp_sync {
+            Some(last) => {
+                let delta: u64 = counter.wrapping_sub(last.ticks) % COUNTER_PERIOD;
+                let elapsed: u128 = instant.duration_since(last.instant).as_nanos() / tick.as_nanos().max(1);
+                let missed: u64 = ((elapsed.saturating_sub(delta as u128) + (COUNTER_PERIOD / 2) as u128) / COUNTER_PERIOD as u128) as u64;
+                last.ticks + delta + missed * COUNTER_PERIOD
+            },
+            None => counter,
+        };
+
+        let sync: TimestampSync = TimestampSync { ticks, instant, tick };
+        self.timestamp_sync = Some(sync);
+        Ok(Some(sync))
+    }
+
+    /// Clear TSW in `mcan_ir` and sync the counter on a wraparound or when frames are about to be read
+    pub(crate) fn handle_timestamp_interrupts(&mut self, mcan_ir: u32) -> CandsResult<Option<TimestampSync>> {
+        let wrapped: bool = mcan_ir & REG_BITS_MCAN_IR_TSW != 0;
+        if wrapped {
+            let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_IR, vec![REG_BITS_MCAN_IR_TSW]);
+            self.write(&cmd)?;
+        }
+        if !wrapped && mcan_ir & MCAN_IR_RX_FLAGS == 0 {
+            return Ok(None);
+        }
+        self.sync_timestamp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{RxTimestamp, TimestampSync};
+
+    fn sync(ticks: u64) -> TimestampSync {
+        TimestampSync { ticks, instant: Instant::now(), tick: Duration::from_micros(1) }
+    }
+
+    #[test]
+    fn timestamps_before_the_sync_are_extended_backwards() {
+        let sync: TimestampSync = sync(0x2_0100);
+        assert_eq!(sync.extend(0x0100), 0x2_0100);
+        assert_eq!(sync.extend(0x00F0), 0x2_00F0);
+        // Captured before the counter wrapped to the value read at the sync
+        assert_eq!(sync.extend(0xFFF0), 0x1_FFF0);
+        assert_eq!(sync.extend(0x1100), 0x1_1100);
+    }
+
+    #[test]
+    fn timestamps_shortly_after_the_sync_cross_the_16_bit_wrap() {
+        let sync: TimestampSync = sync(0x2_FFF0);
+        assert_eq!(sync.extend(0xFFFF), 0x2_FFFF);
+        assert_eq!(sync.extend(0x0010), 0x3_0010);
+        assert_eq!(sync.extend(0x0FEF), 0x3_0FEF);
+        // Beyond the lead window it is taken as an old timestamp instead
+        assert_eq!(sync.extend(0x0FF0), 0x2_0FF0);
+    }
+
+    #[test]
+    fn timestamps_before_the_first_period_do_not_underflow() {
+        assert_eq!(sync(0x10).extend(0xFFF0), 0);
+    }
+
+    #[test]
+    fn stamp_places_the_frame_relative_to_the_sync_instant() {
+        let sync: TimestampSync = sync(0x1_0100);
+        let after: RxTimestamp = sync.stamp(0x0164);
+        assert_eq!(after.ticks, 0x1_0164);
+        assert_eq!(after.instant - sync.instant, Duration::from_micros(100));
+        let before: RxTimestamp = sync.stamp(0x009C);
+        assert_eq!(before.ticks, 0x1_009C);
+        assert_eq!(sync.instant - before.instant, Duration::from_micros(100));
+    }
+}
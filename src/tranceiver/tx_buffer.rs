@@ -0,0 +1,169 @@
+use crate::error::{CandsError, CandsResult};
+
+use super::rx_buffer::CanFrame;
+use super::TCAN455xTranceiver;
+
+const CAN_SID_MAX: u32 = 0x7FF;
+const CAN_XID_MAX: u32 = 0x1FFFFFFF;
+const CAN_CLASSIC_MAX_DLEN: usize = 8;
+const CAN_FD_MAX_DLEN: usize = 64;
+
+fn invalid_frame(msg: &str) -> CandsError {
+    CandsError::InvalidFrame(msg.to_string())
+}
+
+/// Pack bytes into little endian words, zero padding the last word
+pub(crate) fn pack_words(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks(4)
+        .map(|x| {
+            let mut word: [u8; 4] = [0u8; 4];
+            word[..x.len()].copy_from_slice(x);
+            u32::from_le_bytes(word)
+        })
+        .collect()
+}
+
+/// Check that the frame flags, ID and payload can be put on the bus as they are
+pub fn validate_tx_frame(frame: &CanFrame) -> CandsResult<()> {
+    let id_max: u32 = if frame.xtd { CAN_XID_MAX } else { CAN_SID_MAX };
+    if frame.id > id_max {
+        return Err(invalid_frame("ID exceeds the identifier width"));
+    }
+    if frame.fdf && frame.rtr {
+        return Err(invalid_frame("CAN FD frames cannot be remote frames"));
+    }
+    if frame.brs && !frame.fdf {
+        return Err(invalid_frame("Bit rate switching requires FD format"));
+    }
+    if frame.rtr && !frame.data.is_empty() {
+        return Err(invalid_frame("Remote frames cannot carry data"));
+    }
+    if frame.rtr && frame.dlc as usize > CAN_CLASSIC_MAX_DLEN {
+        return Err(invalid_frame("Remote frames cannot request more than 8 bytes"));
+    }
+    if !frame.fdf && frame.data.len() > CAN_CLASSIC_MAX_DLEN {
+        return Err(invalid_frame("Classic CAN frames cannot carry more than 8 bytes"));
+    }
+    if frame.data.len() > CAN_FD_MAX_DLEN {
+        return Err(invalid_frame("CAN FD frames cannot carry more than 64 bytes"));
+    }
+    Ok(())
+}
+
+/// Encode T0, T1 and the data field of a TX element
+pub fn encode_tx_element(frame: &CanFrame, element_data_size: usize) -> CandsResult<Vec<u32>> {
+    validate_tx_frame(frame)?;
+
+    let (dlc, dlen): (u8, usize) = if frame.rtr {
+        (frame.dlc, 0)
+    } else {
+        let dlc: u8 = TCAN455xTranceiver::CAN_DLEN_TO_DLC[frame.data.len()];
+        (dlc, TCAN455xTranceiver::CAN_DLC_TO_DLEN[dlc as usize] as usize)
+    };
+
+    if dlen > element_data_size {
+        return Err(invalid_frame("Payload exceeds the TX buffer data field size"));
+    }
+
+    let id: u32 = if frame.xtd { frame.id } else { frame.id << 18 };
+    let t0: u32 = ((frame.esi as u32) << 31)
+        | ((frame.xtd as u32) << 30)
+        | ((frame.rtr as u32) << 29)
+        | id;
+
+    let t1: u32 = ((frame.fdf as u32) << 21)
+        | ((frame.brs as u32) << 20)
+        | ((dlc as u32 & 0x0F) << 16);
+
+    // Pad the payload up to the length the DLC announces
+    let mut payload: Vec<u8> = frame.data.clone();
+    payload.resize(dlen, 0);
+
+    let mut element: Vec<u32> = vec![t0, t1];
+    element.extend(pack_words(&payload));
+    Ok(element)
+}
+
+/// Encode a TX element that stores a TX event tagged with `marker` once it is sent
+pub fn encode_tx_element_with_marker(frame: &CanFrame, element_data_size: usize, marker: u8) -> CandsResult<Vec<u32>> {
+    const EFC: u32 = 1;
+    let mut element: Vec<u32> = encode_tx_element(frame, element_data_size)?;
+    element[1] |= ((marker as u32) << 24) | (EFC << 23);
+    Ok(element)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_tx_element, encode_tx_element_with_marker, validate_tx_frame};
+    use crate::error::CandsError;
+    use crate::tranceiver::rx_buffer::CanFrame;
+
+    const ELEMENT_DATA_SIZE: usize = 64;
+
+    fn encode(frame: &CanFrame) -> Vec<u32> {
+        encode_tx_element(frame, ELEMENT_DATA_SIZE).unwrap()
+    }
+
+    fn rejected(frame: &CanFrame) -> bool {
+        matches!(validate_tx_frame(frame), Err(CandsError::InvalidFrame(_)))
+    }
+
+    #[test]
+    fn standard_id_is_shifted_into_t0() {
+        let element: Vec<u32> = encode(&CanFrame::new(0x123, false, &[1, 2, 3]));
+        assert_eq!(element, vec![0x123 << 18, 3 << 16, 0x00030201]);
+    }
+
+    #[test]
+    fn flags_are_placed_in_t0_and_t1() {
+        let mut remote: CanFrame = CanFrame::new_remote(0x1ABCDEF, true, 4);
+        remote.esi = true;
+        assert_eq!(encode(&remote), vec![(1 << 31) | (1 << 30) | (1 << 29) | 0x1ABCDEF, 4 << 16]);
+
+        let fd: CanFrame = CanFrame::new_fd(0x10, false, &[0xAA; 8], true);
+        assert_eq!(encode(&fd)[1], (1 << 21) | (1 << 20) | (8 << 16));
+
+        let marked: Vec<u32> = encode_tx_element_with_marker(&CanFrame::new(0x10, false, &[]), ELEMENT_DATA_SIZE, 0x5A).unwrap();
+        assert_eq!(marked[1], (0x5A << 24) | (1 << 23));
+    }
+
+    #[test]
+    fn fd_payload_is_rounded_up_to_the_next_dlc_and_zero_padded() {
+        let rounded: [(usize, u32, usize); 7] = [(12, 9, 12), (16, 10, 16), (20, 11, 20), (24, 12, 24), (32, 13, 32), (48, 14, 48), (64, 15, 64)];
+        for len in 9..=64 {
+            let (_, dlc, dlen) = *rounded.iter().find(|(max, _, _)| len <= *max).unwrap();
+            let element: Vec<u32> = encode(&CanFrame::new_fd(0x10, false, &vec![0xFF; len], false));
+            assert_eq!((element[1] >> 16) & 0x0F, dlc, "{} bytes", len);
+            assert_eq!(element.len(), 2 + dlen / 4, "{} bytes", len);
+        }
+
+        let element: Vec<u32> = encode(&CanFrame::new_fd(0x10, false, &[0xAA; 9], false));
+        assert_eq!(element[2..], [0xAAAAAAAA, 0xAAAAAAAA, 0x000000AA]);
+    }
+
+    #[test]
+    fn invalid_frames_are_rejected() {
+        let mut fd_remote: CanFrame = CanFrame::new_remote(0x10, false, 0);
+        fd_remote.fdf = true;
+        assert!(rejected(&fd_remote));
+
+        let mut classic_brs: CanFrame = CanFrame::new(0x10, false, &[1]);
+        classic_brs.brs = true;
+        assert!(rejected(&classic_brs));
+
+        assert!(rejected(&CanFrame::new(0x800, false, &[])));
+        assert!(!rejected(&CanFrame::new(0x7FF, false, &[])));
+        assert!(rejected(&CanFrame::new(0x20000000, true, &[])));
+        assert!(rejected(&CanFrame::new(0x10, false, &[0; 9])));
+        assert!(rejected(&CanFrame::new_fd(0x10, false, &[0; 65], false)));
+        assert!(rejected(&CanFrame::new_remote(0x10, false, 9)));
+    }
+
+    #[test]
+    fn payload_larger_than_the_element_is_rejected() {
+        let frame: CanFrame = CanFrame::new_fd(0x10, false, &[0; 9], false);
+        assert!(encode_tx_element(&frame, 8).is_err());
+        assert!(encode_tx_element(&frame, 12).is_ok());
+    }
+}
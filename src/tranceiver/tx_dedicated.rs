@@ -0,0 +1,117 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::{CandsError, CandsResult};
+use crate::tcan4550::{controller::TCAN455xController, register::*};
+use super::rx_buffer::CanFrame;
+use super::tx_buffer;
+
+const TX_BUFFER_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Transmission state of one TX buffer from TXBRP, TXBTO and TXBCF
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct TxBufferStatus {
+    /// Transmission request pending
+    pub pending: bool,
+    /// Transmission occurred
+    pub transmitted: bool,
+    /// Cancellation finished. Set together with `transmitted` if the frame was sent before it could be cancelled.
+    pub cancelled: bool,
+}
+
+impl TxBufferStatus {
+    pub fn from_registers(index: u8, txbrp: u32, txbto: u32, txbcf: u32) -> Self {
+        let bit: u32 = 1 << index;
+        Self {
+            pending: txbrp & bit != 0,
+            transmitted: txbto & bit != 0,
+            cancelled: txbcf & bit != 0,
+        }
+    }
+
+    /// No request pending: transmitted, cancelled or never requested
+    pub fn is_complete(&self) -> bool {
+        !self.pending
+    }
+}
+
+impl super::TCAN455xTranceiver {
+
+    fn check_tx_buffer_index(&self, index: u8) -> CandsResult<()> {
+        let buffers: u32 = self.mram_layout.tx_dedicated_buffers();
+        if index as u32 >= buffers {
+            let msg: String = format!("TX buffer {} out of range, {} dedicated buffers configured", index, buffers);
+            return Err(CandsError::InvalidConfig(msg));
+        }
+        Ok(())
+    }
+
+    fn push_tx_buffer(&mut self, index: u8, element: Vec<u32>) -> CandsResult<()> {
+        self.check_tx_buffer_index(index)?;
+
+        // The element must not change while its request is pending
+        let txbrp: u32 = self.read_device(REG_MCAN_TXBRP)?;
+        if txbrp & (1 << index) != 0 {
+            return Err(CandsError::TxBufferPending(index));
+        }
+
+        let addr: u16 = TCAN455xController::get_txdata_start_addr(&self.mram_layout, index as u16);
+        let cmd: Vec<u8> = TCAN455xController::generate_write_command(addr, element);
+        self.write(&cmd)?;
+
+        let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_TXBAR, vec![1 << index]);
+        self.write(&cmd)?;
+        Ok(())
+    }
+
+    /// Write a frame to dedicated TX buffer `index` and request its transmission
+    pub fn transmit_buffer(&mut self, index: u8, frame: &CanFrame) -> CandsResult<()> {
+        let element: Vec<u32> = tx_buffer::encode_tx_element(frame, self.mram_layout.tx_data_size().size as usize)?;
+        self.push_tx_buffer(index, element)
+    }
+
+    /// Same as `transmit_buffer`, storing a TX event tagged with `marker` once the frame is sent
+    pub fn transmit_buffer_with_marker(&mut self, index: u8, frame: &CanFrame, marker: u8) -> CandsResult<()> {
+        let element: Vec<u32> = tx_buffer::encode_tx_element_with_marker(frame, self.mram_layout.tx_data_size().size as usize, marker)?;
+        self.push_tx_buffer(index, element)
+    }
+
+    /// Request cancellation of a pending transmission. A frame already in arbitration may still be sent.
+    pub fn cancel_tx_buffer(&mut self, index: u8) -> CandsResult<()> {
+        self.check_tx_buffer_index(index)?;
+        let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_TXBCR, vec![1 << index]);
+        self.write(&cmd)?;
+        Ok(())
+    }
+
+    /// Status of dedicated TX buffer `index`
+    pub fn read_tx_buffer_status(&mut self, index: u8) -> CandsResult<TxBufferStatus> {
+        self.check_tx_buffer_index(index)?;
+        let statuses: Vec<TxBufferStatus> = self.read_tx_buffer_statuses()?;
+        Ok(statuses[index as usize])
+    }
+
+    /// Status of all dedicated TX buffers, indexed by buffer
+    pub fn read_tx_buffer_statuses(&mut self) -> CandsResult<Vec<TxBufferStatus>> {
+        let txbrp: u32 = self.read_device(REG_MCAN_TXBRP)?;
+        let txbto: u32 = self.read_device(REG_MCAN_TXBTO)?;
+        let txbcf: u32 = self.read_device(REG_MCAN_TXBCF)?;
+        let buffers: u8 = self.mram_layout.tx_dedicated_buffers() as u8;
+        Ok((0..buffers).map(|index| TxBufferStatus::from_registers(index, txbrp, txbto, txbcf)).collect())
+    }
+
+    /// Poll TX buffer `index` until its request completes. Fails with `CandsError::Timeout`.
+    pub fn wait_tx_buffer(&mut self, index: u8, timeout: Duration) -> CandsResult<TxBufferStatus> {
+        let deadline: Instant = Instant::now() + timeout;
+        loop {
+            let status: TxBufferStatus = self.read_tx_buffer_status(index)?;
+            if status.is_complete() {
+                return Ok(status);
+            }
+            if Instant::now() >= deadline {
+                return Err(CandsError::Timeout);
+            }
+            thread::sleep(TX_BUFFER_POLL_INTERVAL);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::sync::mpsc;
+
+use crate::error::CandsResult;
+use crate::tcan4550::{controller::{configurator::mram::*, TCAN455xController}, register::*};
+use super::rx_buffer::CanFrame;
+use super::tx_buffer;
+
+// TX event element size: E0 + E1
+const TX_EVENT_ELEMENT_SIZE: usize = 8;
+
+pub(crate) const MCAN_IR_TX_EVENT_FLAGS: u32 = REG_BITS_MCAN_IR_TEFN
+    | REG_BITS_MCAN_IR_TEFW
+    | REG_BITS_MCAN_IR_TEFF
+    | REG_BITS_MCAN_IR_TEFL;
+
+pub(crate) const MCAN_IE_TX_EVENT_FLAGS: u32 = REG_BITS_MCAN_IE_TEFNE | REG_BITS_MCAN_IE_TEFLE;
+
+/// Event type of E1.ET
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TxEventType {
+    Transmitted,
+    /// Sent although a cancellation was requested
+    TransmittedDespiteCancellation,
+    Reserved(u8),
+}
+
+impl TxEventType {
+    pub fn from_u32(code: u32) -> Self {
+        match code & 0x03 {
+            1 => TxEventType::Transmitted,
+            2 => TxEventType::TransmittedDespiteCancellation,
+            x => TxEventType::Reserved(x as u8),
+        }
+    }
+}
+
+/// Transmit confirmation read from the TX event FIFO
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TxEvent {
+    /// Message marker given at transmit
+    pub marker: u8,
+    /// 11 bit standard ID or 29 bit extended ID
+    pub id: u32,
+    /// Extended identifier
+    pub xtd: bool,
+    /// Remote transmission request
+    pub rtr: bool,
+    /// Error state indicator
+    pub esi: bool,
+    /// FD format
+    pub fdf: bool,
+    /// Bit rate switch
+    pub brs: bool,
+    /// Data length code
+    pub dlc: u8,
+    /// TX timestamp
+    pub txts: u16,
+    pub event_type: TxEventType,
+}
+
+impl TxEvent {
+    /// Decode one TX event element (E0, E1) laid out as little endian words
+    pub fn from_element(element: &[u8]) -> Option<Self> {
+        if element.len() < TX_EVENT_ELEMENT_SIZE {
+            return None;
+        }
+
+        let e0: u32 = u32::from_le_bytes([element[0], element[1], element[2], element[3]]);
+        let e1: u32 = u32::from_le_bytes([element[4], element[5], element[6], element[7]]);
+
+        let xtd: bool = (e0 >> 30) & 0x01 != 0;
+        let id: u32 = if xtd { e0 & 0x1FFFFFFF } else { (e0 >> 18) & 0x7FF };
+
+        Some(Self {
+            marker: ((e1 >> 24) & 0xFF) as u8,
+            id,
+            xtd,
+            rtr: (e0 >> 29) & 0x01 != 0,
+            esi: (e0 >> 31) & 0x01 != 0,
+            fdf: (e1 >> 21) & 0x01 != 0,
+            brs: (e1 >> 20) & 0x01 != 0,
+            dlc: ((e1 >> 16) & 0x0F) as u8,
+            txts: (e1 & 0xFFFF) as u16,
+            event_type: TxEventType::from_u32(e1 >> 22),
+        })
+    }
+}
+
+impl super::TCAN455xTranceiver {
+
+    /// Queue a frame that reports a `TxEvent` carrying `marker` once it has been sent
+    pub fn transmit_frame_with_marker(&mut self, frame: &CanFrame, marker: u8) -> CandsResult<()> {
+        let element: Vec<u32> = tx_buffer::encode_tx_element_with_marker(frame, self.mram_layout.tx_data_size().size as usize, marker)?;
+        self.push_tx_fifo(element)
+    }
+
+    /// Drain the TX event FIFO, oldest first
+    pub fn read_tx_events(&mut self) -> CandsResult<Vec<TxEvent>> {
+        let layout: MramLayout = self.mram_layout;
+        let size: u32 = layout.num_of_elements(MramSection::TxEvent);
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+
+        let txefs: u32 = self.read_device(REG_MCAN_TXEFS)?;
+        let get_index: u32 = (txefs >> 8) & 0x1F;
+        let fill_level: u32 = txefs & 0x3F;
+        // An index beyond the layout means the device holds a different configuration
+        if fill_level == 0 || get_index >= size {
+            return Ok(Vec::new());
+        }
+
+        // Unread elements run from the get index to the end of the FIFO, then wrap around to index 0
+        let unread_to_end: u32 = fill_level.min(size - get_index);
+        let unread_wrapped: u32 = fill_level - unread_to_end;
+
+        let element_size: u32 = layout.bytes_per_element(MramSection::TxEvent);
+        let addr: u16 = TCAN455xController::get_txevent_start_addr(&layout, get_index as u16);
+        let mut data: Vec<u8> = self.read_mram(addr, (element_size * unread_to_end / 4) as usize)?;
+        if unread_wrapped > 0 {
+            let addr: u16 = TCAN455xController::get_txevent_start_addr(&layout, 0);
+            data.extend(self.read_mram(addr, (element_size * unread_wrapped / 4) as usize)?);
+        }
+
+        let ack_index: u32 = (get_index + fill_level - 1) % size;
+        let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_TXEFA, vec![ack_index]);
+        self.write(&cmd)?;
+
+        Ok(data.chunks(element_size as usize).filter_map(TxEvent::from_element).collect())
+    }
+
+    /// Enable TEFN and TEFL interrupts on the next `setup` or `configure_mcan_irq`
+    pub fn set_tx_event_interrupts(&mut self, enable: bool) {
+        self.tx_event_interrupts = enable;
+    }
+
+    /// Channel of transmit confirmations, replacing any previous subscriber. Events are read by `receive` on TEFN.
+    pub fn tx_events(&mut self) -> mpsc::Receiver<TxEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.tx_event_sender = Some(sender);
+        receiver
+    }
+
+    /// Clear the TX event interrupts in `mcan_ir` and forward the events to the subscriber
+    pub(crate) fn handle_tx_event_interrupts(&mut self, mcan_ir: u32) -> CandsResult<()> {
+        let flags: u32 = mcan_ir & MCAN_IR_TX_EVENT_FLAGS;
+        if flags == 0 {
+            return Ok(());
+        }
+
+        let cmd: Vec<u8> = TCAN455xController::generate_write_command(REG_MCAN_IR, vec![flags]);
+        self.write(&cmd)?;
+
+        // Without a subscriber the events are left to `read_tx_events`
+        if self.tx_event_sender.is_none() {
+            return Ok(());
+        }
+        for event in self.read_tx_events()? {
+            if self.tx_event_sender.as_ref().is_some_and(|sender| sender.send(event).is_err()) {
+                self.tx_event_sender = None;
+            }
+        }
+        Ok(())
+    }
+}
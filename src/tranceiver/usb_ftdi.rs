@@ -1,10 +1,11 @@
 use crate::device_driver::ftdi::{FtdiDriver, Ft232h};
+use crate::error::CandsResult;
 
 impl super::TCAN455xTranceiver {
-    pub fn new () -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new () -> CandsResult<Self> {
         const SPI_CLK_FREQ: u32 = 15_000_000;
         const SPI_CLK_POLARITY: u8 = 0;
         let driver: FtdiDriver<Ft232h, _> = FtdiDriver::new(SPI_CLK_FREQ, SPI_CLK_POLARITY)?;
-        Ok(Self { driver: Box::new(driver) })
+        Ok(Self::from_driver(Box::new(driver)))
     }
 }
\ No newline at end of file
@@ -0,0 +1,165 @@
+use std::fmt;
+
+use crate::error::CandsResult;
+use crate::tcan4550::{
+    bitfield::*,
+    controller::TCAN455xController,
+    id_filter::{SIDConfig, XIDConfig},
+    register::*,
+    register_map::REGISTER_MAP,
+};
+use crate::tcan4550::controller::configurator::{mram::*, timestamp::TimestampConfig};
+use super::batch::command_words;
+
+// DEVICE_ID1 of the TCAN4550 and the TCAN4551
+const DEVICE_ID_SUFFIXES: [&str; 2] = ["4550", "4551"];
+
+/// Difference between what `setup` configured and what the device reads back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigMismatch {
+    /// DEVICE_ID0/1 do not read "TCAN4550" or "TCAN4551"
+    DeviceId(String),
+    /// REVISION reads all zeros or all ones, as with a floating or shorted MISO line
+    Revision(u32),
+    /// Register or MRAM filter word differing in the compared bits
+    Value { name: String, addr: u16, expected: u32, actual: u32, mask: u32 },
+}
+
+impl fmt::Display for ConfigMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigMismatch::DeviceId(id) => write!(f, "Device ID {:?} is not a TCAN4550 or TCAN4551", id),
+            ConfigMismatch::Revision(revision) => write!(f, "Revision reads 0x{:08X}", revision),
+            ConfigMismatch::Value { name, addr, expected, actual, mask } => write!(
+                f, "{} at 0x{:04X}: expected 0x{:08X}, read 0x{:08X} (mask 0x{:08X})",
+                name, addr, expected & mask, actual & mask, mask
+            ),
+        }
+    }
+}
+
+// Configuration word written by `setup` and the bits of it that read back as written
+struct ExpectedWord {
+    name: String,
+    addr: u16,
+    value: u32,
+    mask: u32,
+}
+
+fn register_name(addr: u16) -> String {
+    REGISTER_MAP
+        .iter()
+        .find(|info| info.addr == addr)
+        .map_or_else(|| format!("0x{:04X}", addr), |info| info.name.to_string())
+}
+
+fn expect_reg<R: Register>(expected: &mut Vec<ExpectedWord>, reg: &R) {
+    expected.push(ExpectedWord { name: register_name(R::ADDR), addr: R::ADDR, value: reg.to_u32(), mask: R::FIELD_MASK });
+}
+
+fn expect_register(expected: &mut Vec<ExpectedWord>, cmd: &[u8], mask: u32) {
+    for (addr, value) in command_words(cmd) {
+        expected.push(ExpectedWord { name: register_name(addr), addr, value, mask });
+    }
+}
+
+impl super::TCAN455xTranceiver {
+
+    /// At the end of `setup`, check SPI_STATUS, the device ID and revision and read back the configuration.
+    /// Fails with `CandsError::SpiStatus` or `CandsError::ConfigMismatch`.
+    pub fn set_verify_setup(&mut self, enable: bool) {
+        self.verify_setup = enable;
+    }
+
+    /// Words `setup` writes with the current settings, after CCCR is protected again and the device is in normal mode
+    fn expected_configuration(&self, sidf: &[SIDConfig], xidf: &[XIDConfig]) -> Vec<ExpectedWord> {
+        let layout: MramLayout = self.mram_layout;
+        let timestamp: TimestampConfig = self.timestamp;
+        let mut expected: Vec<ExpectedWord> = Vec::new();
+
+        // Unlocked by `unlock_mcan_cccr`, CSA only reflects the clock stop state
+        let cccr_mask: u32 = McanCccr::FIELD_MASK & !REG_BITS_MCAN_CCCR_CSA;
+        let cccr_protected: u32 = REG_BITS_MCAN_CCCR_INIT | REG_BITS_MCAN_CCCR_CCE | REG_BITS_MCAN_CCCR_CSR;
+        for (addr, value) in command_words(&TCAN455xController::set_mcan_cccr()) {
+            expected.push(ExpectedWord { name: register_name(addr), addr, value: value & !cccr_protected, mask: cccr_mask });
+        }
+
+        expect_reg(&mut expected, &self.global_filter.gfc());
+        expect_register(&mut expected, &TCAN455xController::set_xidam(&self.global_filter), u32::MAX);
+        expect_reg(&mut expected, &self.bit_timing.dbtp());
+        expect_reg(&mut expected, &self.bit_timing.nbtp());
+        expect_reg(&mut expected, &self.bit_timing.tdcr());
+        expect_reg(&mut expected, &timestamp.tscc());
+        if let Some(cmd) = TCAN455xController::set_timestamp_prescaler(&timestamp) {
+            expect_register(&mut expected, &cmd, 0xFF);
+        }
+
+        expect_register(&mut expected, &TCAN455xController::set_sidfc(&layout), u32::MAX);
+        expect_register(&mut expected, &TCAN455xController::set_xidfc(&layout), u32::MAX);
+        expect_register(&mut expected, &TCAN455xController::set_rxf0c(&layout), McanRxf0c::FIELD_MASK);
+        expect_register(&mut expected, &TCAN455xController::set_rxf1c(&layout), McanRxf1c::FIELD_MASK);
+        expect_register(&mut expected, &TCAN455xController::set_rxbc(&layout), u32::MAX);
+        expect_register(&mut expected, &TCAN455xController::set_rxesc(&layout), u32::MAX);
+        expect_register(&mut expected, &TCAN455xController::set_txefc(&layout), u32::MAX);
+        expect_register(&mut expected, &TCAN455xController::set_txbc(&layout), McanTxbc::FIELD_MASK);
+        expect_register(&mut expected, &TCAN455xController::set_txesc(&layout), u32::MAX);
+
+        if !sidf.is_empty() && layout.num_of_elements(MramSection::Sid) > 0 {
+            for (i, (addr, value)) in command_words(&TCAN455xController::set_sid(&layout, sidf)).into_iter().enumerate() {
+                expected.push(ExpectedWord { name: format!("SID filter {}", i), addr, value, mask: u32::MAX });
+            }
+        }
+        if !xidf.is_empty() && layout.num_of_elements(MramSection::Xid) > 0 {
+            for (i, (addr, value)) in command_words(&TCAN455xController::set_xid(&layout, xidf)).into_iter().enumerate() {
+                let name: String = format!("XID filter {} F{}", i / 2, i % 2);
+                expected.push(ExpectedWord { name, addr, value, mask: u32::MAX });
+            }
+        }
+
+        expect_register(&mut expected, &TCAN455xController::set_mcan_ie(self.mcan_ie_enable()), McanIe::FIELD_MASK);
+        expect_register(&mut expected, &TCAN455xController::set_mcan_ile(), 0x03);
+
+        // `switch_normal_mode` runs last, the watchdog reset and device reset bits clear themselves
+        let mut modes: DevModesAndPins = self.device_config.dev_modes_and_pins();
+        modes.mode_sel = DevModesAndPins::MODE_NORMAL;
+        let modes_mask: u32 = DevModesAndPins::FIELD_MASK & !(REG_BITS_DEVICE_MODE_WDT_RESET_BIT | REG_BITS_DEVICE_MODE_DEVICE_RESET);
+        expected.push(ExpectedWord {
+            name: register_name(REG_DEV_MODES_AND_PINS),
+            addr: REG_DEV_MODES_AND_PINS,
+            value: modes.to_u32(),
+            mask: modes_mask,
+        });
+
+        expected
+    }
+
+    /// Check DEVICE_ID and REVISION
+    pub fn verify_device(&mut self) -> CandsResult<Vec<ConfigMismatch>> {
+        let mut mismatches: Vec<ConfigMismatch> = Vec::new();
+
+        let id: String = self.get_device_id()?;
+        let known: bool = DEVICE_ID_SUFFIXES.iter().any(|suffix| id == format!("TCAN{}", suffix));
+        if !known {
+            mismatches.push(ConfigMismatch::DeviceId(id));
+        }
+
+        let revision: u32 = self.read_device(REG_SPI_REVISION)?;
+        if revision == 0 || revision == u32::MAX {
+            mismatches.push(ConfigMismatch::Revision(revision));
+        }
+        Ok(mismatches)
+    }
+
+    /// Compare the device ID, every configuration register `setup` writes and the filter elements in MRAM
+    /// against the current settings. An empty list means the device holds the intended configuration.
+    pub fn verify_configuration(&mut self, sidf: &[SIDConfig], xidf: &[XIDConfig]) -> CandsResult<Vec<ConfigMismatch>> {
+        let mut mismatches: Vec<ConfigMismatch> = self.verify_device()?;
+        for ExpectedWord { name, addr, value, mask } in self.expected_configuration(sidf, xidf) {
+            let actual: u32 = self.read_device(addr)?;
+            if (actual ^ value) & mask != 0 {
+                mismatches.push(ConfigMismatch::Value { name, addr, expected: value, actual, mask });
+            }
+        }
+        Ok(mismatches)
+    }
+}
@@ -0,0 +1,149 @@
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::error::{CandsError, CandsResult};
+use crate::tcan4550::{bitfield::DevModesAndPins, register::*};
+use crate::tcan4550::controller::configurator::modes_and_pins::{WatchdogAction, WatchdogTimer};
+use super::TCAN455xTranceiver;
+
+// Kicks per watchdog period, leaving room for SPI latency and scheduling jitter
+const KICKS_PER_PERIOD: u32 = 4;
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+impl TCAN455xTranceiver {
+
+    /// Enable the watchdog on the next `setup`
+    pub fn set_watchdog(&mut self, timer: WatchdogTimer, action: WatchdogAction) {
+        self.device_config.wd_enable = true;
+        self.device_config.wd_timer = timer;
+        self.device_config.wd_action = action;
+    }
+
+    /// Disable the watchdog on the next `setup`
+    pub fn disable_watchdog(&mut self) {
+        self.device_config.wd_enable = false;
+    }
+
+    /// Watchdog period, None while the watchdog is disabled
+    pub fn watchdog_timeout(&self) -> Option<Duration> {
+        self.device_config.wd_enable.then(|| self.device_config.wd_timer.duration())
+    }
+
+    /// Restart the watchdog timer
+    pub fn kick_watchdog(&mut self) -> CandsResult<()> {
+        self.modify_reg(|modes: &mut DevModesAndPins| modes.wd_bit_set = true)?;
+        Ok(())
+    }
+
+    /// WDTO was set when `setup` last ran, so the previous session stopped kicking the watchdog.
+    /// The reset in `setup` clears DEV_IR, this is the only record of it.
+    pub fn watchdog_expired_before_setup(&self) -> bool {
+        self.watchdog_expired
+    }
+
+    /// Read WDTO from DEV_IR and clear it, including a timeout already cleared by device event handling
+    pub fn take_watchdog_timeout(&mut self) -> CandsResult<bool> {
+        let dev_ir: u32 = self.read_device_irq()?;
+        let expired: bool = (dev_ir | self.pending_dev_ir) & REG_BITS_DEVICE_IR_WDTO != 0;
+        if dev_ir & REG_BITS_DEVICE_IR_WDTO != 0 {
+            self.clear_device_irq_flags(REG_BITS_DEVICE_IR_WDTO)?;
+        }
+        self.pending_dev_ir &= !REG_BITS_DEVICE_IR_WDTO;
+        Ok(expired)
+    }
+
+    // An absent device reads all ones, which would report a timeout
+    pub(super) fn read_watchdog_expired(&mut self) -> CandsResult<bool> {
+        let dev_ir: u32 = self.read_device_irq()?;
+        Ok(self.pending_dev_ir & REG_BITS_DEVICE_IR_WDTO != 0 || (dev_ir != u32::MAX && dev_ir & REG_BITS_DEVICE_IR_WDTO != 0))
+    }
+}
+
+/// Handle the application beats to show it is alive to a `WatchdogService`
+#[derive(Clone)]
+pub struct Heartbeat {
+    last: Arc<Mutex<Instant>>,
+}
+
+impl Heartbeat {
+    pub fn beat(&self) {
+        *lock(&self.last) = Instant::now();
+    }
+}
+
+/// Kicks the watchdog of a shared tranceiver from a background thread
+///
+/// Kicking stops once the application has not beaten its `Heartbeat` within the liveness timeout,
+/// or has dropped every `Heartbeat`, so the watchdog fires when the application hangs or exits.
+pub struct WatchdogService {
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<CandsResult<()>>>,
+}
+
+impl WatchdogService {
+    /// Start kicking at a quarter of the watchdog period. Fails with `CandsError::InvalidConfig` if the watchdog is disabled.
+    pub fn spawn(tranceiver: Arc<Mutex<TCAN455xTranceiver>>, liveness_timeout: Duration) -> CandsResult<(Self, Heartbeat)> {
+        let period: Duration = match lock(&tranceiver).watchdog_timeout() {
+            Some(period) => period,
+            None => return Err(CandsError::InvalidConfig("Watchdog is disabled".to_string())),
+        };
+        let interval: Duration = period / KICKS_PER_PERIOD;
+
+        let heartbeat: Heartbeat = Heartbeat { last: Arc::new(Mutex::new(Instant::now())) };
+        let last: Weak<Mutex<Instant>> = Arc::downgrade(&heartbeat.last);
+        let stop: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let stopped: Arc<AtomicBool> = stop.clone();
+
+        let worker = thread::Builder::new()
+            .name("cands-watchdog".to_string())
+            .spawn(move || {
+                while !stopped.load(Ordering::Acquire) {
+                    let alive: bool = last.upgrade().is_some_and(|last| lock(&last).elapsed() <= liveness_timeout);
+                    if !alive {
+                        return Ok(());
+                    }
+                    lock(&tranceiver).kick_watchdog()?;
+                    thread::park_timeout(interval);
+                }
+                Ok(())
+            })?;
+
+        Ok((Self { stop, worker: Some(worker) }, heartbeat))
+    }
+
+    /// False once kicking stopped because the application stopped beating or a kick failed
+    pub fn is_running(&self) -> bool {
+        self.worker.as_ref().is_some_and(|worker| !worker.is_finished())
+    }
+
+    /// Stop kicking. Returns the error of a failed kick.
+    pub fn stop(mut self) -> CandsResult<()> {
+        self.shutdown()
+    }
+
+    fn shutdown(&mut self) -> CandsResult<()> {
+        self.stop.store(true, Ordering::Release);
+        match self.worker.take() {
+            Some(worker) => {
+                worker.thread().unpark();
+                worker.join().map_err(|_| io::Error::other("Watchdog thread panicked"))?
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for WatchdogService {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}